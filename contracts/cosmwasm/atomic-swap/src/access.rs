@@ -0,0 +1,26 @@
+use cosmwasm_std::{Addr, Storage};
+
+use crate::error::ContractError;
+use crate::state::{ACCESS_CONFIG, ALLOWLIST, DENYLIST};
+
+pub fn is_denied(storage: &dyn Storage, address: &Addr) -> bool {
+    DENYLIST.has(storage, address)
+}
+
+// Rejects denied addresses, and anyone not on the allowlist while it is enforced
+pub fn ensure_permitted(storage: &dyn Storage, address: &Addr) -> Result<(), ContractError> {
+    if is_denied(storage, address) {
+        return Err(ContractError::AddressDenied {
+            address: address.to_string(),
+        });
+    }
+
+    let access = ACCESS_CONFIG.may_load(storage)?.unwrap_or_default();
+    if access.allowlist_only && !ALLOWLIST.has(storage, address) {
+        return Err(ContractError::AddressNotAllowed {
+            address: address.to_string(),
+        });
+    }
+
+    Ok(())
+}
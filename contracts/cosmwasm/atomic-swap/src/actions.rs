@@ -0,0 +1,75 @@
+use cosmwasm_std::{Addr, Api, Coin, StdResult, Storage, SubMsg, WasmMsg};
+
+use crate::error::ContractError;
+use crate::payouts::stage_payout;
+use crate::state::{CompletionAction, IbcDelivery, PayoutRoute, PendingPayout, ACTION_CONTRACTS};
+
+// Caps the call so running out of gas is caught by its submessage
+const ACTION_GAS_LIMIT: u64 = 2_000_000;
+
+// Returns the action with its contract address normalized
+pub fn validate_action(
+    api: &dyn Api,
+    storage: &dyn Storage,
+    action: CompletionAction,
+) -> Result<CompletionAction, ContractError> {
+    let contract = api.addr_validate(&action.contract)?;
+    if !ACTION_CONTRACTS.has(storage, &contract) {
+        return Err(ContractError::InvalidCompletionAction {
+            reason: format!("{} is not an allowed action contract", contract),
+        });
+    }
+
+    Ok(CompletionAction {
+        contract: contract.into_string(),
+        msg: action.msg,
+    })
+}
+
+// A payout either leaves over IBC or goes through a contract call, not both
+pub fn ensure_single_route(
+    delivery: &Option<IbcDelivery>,
+    action: &Option<CompletionAction>,
+) -> Result<(), ContractError> {
+    if delivery.is_some() && action.is_some() {
+        return Err(ContractError::InvalidCompletionAction {
+            reason: "an action cannot be combined with an IBC delivery".to_string(),
+        });
+    }
+    Ok(())
+}
+
+// Whether the contract is still allowed when the swap completes
+pub fn is_allowed_action(storage: &dyn Storage, action: &CompletionAction) -> bool {
+    ACTION_CONTRACTS.has(storage, &Addr::unchecked(&action.contract))
+}
+
+// Sends the payout along with the call. A failed call is paid to the
+// recipient with a bank send from its reply instead.
+pub fn run_action(
+    storage: &mut dyn Storage,
+    action: &CompletionAction,
+    swap_id: &str,
+    recipient: &Addr,
+    amount: Coin,
+) -> StdResult<SubMsg> {
+    let id = stage_payout(
+        storage,
+        PendingPayout {
+            swap_id: swap_id.to_string(),
+            recipient: recipient.clone(),
+            amount: amount.clone(),
+            route: PayoutRoute::Action,
+        },
+    )?;
+
+    Ok(SubMsg::reply_always(
+        WasmMsg::Execute {
+            contract_addr: action.contract.clone(),
+            msg: action.msg.clone(),
+            funds: vec![amount],
+        },
+        id,
+    )
+    .with_gas_limit(ACTION_GAS_LIMIT))
+}
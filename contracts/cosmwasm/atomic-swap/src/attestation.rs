@@ -0,0 +1,125 @@
+use cosmwasm_std::{Addr, Event, Storage};
+
+use crate::error::ContractError;
+use crate::msg::SwapState;
+use crate::state::{AtomicSwap, AttestationStatus, AttestorConfig, ATTESTOR_CONFIG};
+
+pub fn default_attestor_config() -> AttestorConfig {
+    AttestorConfig {
+        attestors: vec![],
+        threshold: 0,
+        require_lock_attestation: false,
+    }
+}
+
+pub fn validate_attestor_config(config: &AttestorConfig) -> Result<(), ContractError> {
+    let mut attestors = config.attestors.clone();
+    attestors.sort();
+    attestors.dedup();
+
+    if attestors.len() != config.attestors.len()
+        || (config.attestors.is_empty() && config.threshold != 0)
+        || (!config.attestors.is_empty()
+            && (config.threshold == 0 || config.threshold as usize > config.attestors.len()))
+    {
+        return Err(ContractError::InvalidConfigAttestors {});
+    }
+
+    // Gating completions on attestations nobody can submit would freeze every swap
+    if config.require_lock_attestation && config.attestors.is_empty() {
+        return Err(ContractError::InvalidConfigAttestors {});
+    }
+
+    Ok(())
+}
+
+pub fn load_attestor(
+    storage: &dyn Storage,
+    sender: &Addr,
+) -> Result<AttestorConfig, ContractError> {
+    let config = ATTESTOR_CONFIG.load(storage)?;
+    if !config.attestors.contains(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(config)
+}
+
+// Records one attestor's vote that the counterpart HTLC was locked.
+// The status flips once the threshold of distinct attestors is reached.
+// Settled swaps take no more lock votes, the lock can no longer gate anything.
+pub fn attest_lock(
+    swap: &mut AtomicSwap,
+    config: &AttestorConfig,
+    attestor: &Addr,
+    current_time: u64,
+) -> Result<Option<Event>, ContractError> {
+    match swap.state {
+        SwapState::Initiated => {}
+        SwapState::Completed => return Err(ContractError::SwapAlreadyCompleted {}),
+        SwapState::Refunded => return Err(ContractError::SwapAlreadyRefunded {}),
+    }
+    if swap.attestation.status == AttestationStatus::RefundAttested {
+        return Err(ContractError::AttestationConflict {
+            swap_id: swap.swap_id.clone(),
+        });
+    }
+    if swap.attestation.lock_attestors.contains(attestor) {
+        return Err(ContractError::AlreadyAttested {
+            swap_id: swap.swap_id.clone(),
+        });
+    }
+
+    swap.attestation.lock_attestors.push(attestor.clone());
+    if swap.attestation.status == AttestationStatus::Unattested
+        && swap.attestation.lock_attestors.len() >= config.threshold as usize
+    {
+        swap.attestation.status = AttestationStatus::LockAttested;
+        swap.attestation.attested_at = Some(current_time);
+        return Ok(Some(attested_event(swap, "lock", current_time)));
+    }
+
+    Ok(None)
+}
+
+// Records one attestor's vote that the counterpart HTLC was refunded. An
+// attested lock takes precedence, the participant may already rely on it.
+pub fn attest_refund(
+    swap: &mut AtomicSwap,
+    config: &AttestorConfig,
+    attestor: &Addr,
+    current_time: u64,
+) -> Result<Option<Event>, ContractError> {
+    match swap.state {
+        SwapState::Initiated => {}
+        SwapState::Completed => return Err(ContractError::SwapAlreadyCompleted {}),
+        SwapState::Refunded => return Err(ContractError::SwapAlreadyRefunded {}),
+    }
+    if swap.attestation.status == AttestationStatus::LockAttested {
+        return Err(ContractError::AttestationConflict {
+            swap_id: swap.swap_id.clone(),
+        });
+    }
+    if swap.attestation.refund_attestors.contains(attestor) {
+        return Err(ContractError::AlreadyAttested {
+            swap_id: swap.swap_id.clone(),
+        });
+    }
+
+    swap.attestation.refund_attestors.push(attestor.clone());
+    if swap.attestation.status != AttestationStatus::RefundAttested
+        && swap.attestation.refund_attestors.len() >= config.threshold as usize
+    {
+        swap.attestation.status = AttestationStatus::RefundAttested;
+        swap.attestation.attested_at = Some(current_time);
+        return Ok(Some(attested_event(swap, "refund", current_time)));
+    }
+
+    Ok(None)
+}
+
+fn attested_event(swap: &AtomicSwap, kind: &str, current_time: u64) -> Event {
+    Event::new("counterpart_attested")
+        .add_attribute("swap_id", &swap.swap_id)
+        .add_attribute("kind", kind)
+        .add_attribute("attested_at", current_time.to_string())
+}
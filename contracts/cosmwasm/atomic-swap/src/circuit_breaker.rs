@@ -0,0 +1,162 @@
+use cosmwasm_std::{Coin, Event, StdResult, Storage, Uint128};
+
+use crate::error::ContractError;
+use crate::state::{
+    CircuitBreakerConfig, CircuitBreakerState, CompletionVolume, TripReason, CIRCUIT_BREAKER,
+    COMPLETION_VOLUME,
+};
+
+pub fn default_circuit_breaker_config() -> CircuitBreakerConfig {
+    CircuitBreakerConfig {
+        window_seconds: 3600,
+        trailing_windows: 24,
+        completion_volume_multiplier: None,
+        max_emergency_refunds: None,
+    }
+}
+
+pub fn validate_circuit_breaker_config(config: &CircuitBreakerConfig) -> Result<(), ContractError> {
+    if config.window_seconds == 0
+        || config.trailing_windows == 0
+        || config.completion_volume_multiplier == Some(0)
+    {
+        return Err(ContractError::InvalidConfigCircuitBreaker {});
+    }
+    Ok(())
+}
+
+pub fn ensure_not_tripped(storage: &dyn Storage) -> Result<(), ContractError> {
+    if CIRCUIT_BREAKER.load(storage)?.tripped {
+        return Err(ContractError::CircuitBreakerTripped {});
+    }
+    Ok(())
+}
+
+// Adds a completion to the volume of its window. Trips the breaker when the
+// window volume exceeds the configured multiple of the trailing average.
+pub fn record_completion(
+    storage: &mut dyn Storage,
+    config: &CircuitBreakerConfig,
+    amount: &Coin,
+    current_time: u64,
+) -> StdResult<Option<Event>> {
+    let mut volume = current_completion_volume(storage, config, &amount.denom, current_time)?;
+    volume.volume += amount.amount;
+    COMPLETION_VOLUME.save(storage, &amount.denom, &volume)?;
+
+    match config.completion_volume_multiplier {
+        // Without a trailing average there is nothing to compare against yet
+        Some(multiplier) if !volume.trailing_average.is_zero() => {
+            if volume.volume > volume.trailing_average * Uint128::from(multiplier) {
+                let reason = TripReason::CompletionVolume {
+                    denom: amount.denom.clone(),
+                };
+                return trip(storage, reason, current_time).map(Some);
+            }
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+pub fn record_emergency_refund(
+    storage: &mut dyn Storage,
+    config: &CircuitBreakerConfig,
+    current_time: u64,
+) -> StdResult<Option<Event>> {
+    let mut state = current_state(storage, config, current_time)?;
+    state.emergency_refunds += 1;
+    CIRCUIT_BREAKER.save(storage, &state)?;
+
+    match config.max_emergency_refunds {
+        // Refunds beyond the limit trip it, a breaker already tripped stays as it is
+        Some(max) if !state.tripped && state.emergency_refunds > max => {
+            trip(storage, TripReason::EmergencyRefunds {}, current_time).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+pub fn reset(storage: &mut dyn Storage, current_time: u64) -> StdResult<Event> {
+    CIRCUIT_BREAKER.save(storage, &initial_state())?;
+
+    Ok(Event::new("circuit_breaker_reset").add_attribute("reset_at", current_time.to_string()))
+}
+
+pub fn initial_state() -> CircuitBreakerState {
+    CircuitBreakerState {
+        tripped: false,
+        tripped_at: None,
+        reason: None,
+        window: 0,
+        emergency_refunds: 0,
+    }
+}
+
+fn trip(storage: &mut dyn Storage, reason: TripReason, current_time: u64) -> StdResult<Event> {
+    let mut state = CIRCUIT_BREAKER.load(storage)?;
+    state.tripped = true;
+    state.tripped_at = Some(current_time);
+    state.reason = Some(reason.clone());
+    CIRCUIT_BREAKER.save(storage, &state)?;
+
+    let mut event =
+        Event::new("circuit_breaker_tripped").add_attribute("tripped_at", current_time.to_string());
+    event = match reason {
+        TripReason::CompletionVolume { denom } => event
+            .add_attribute("reason", "completion_volume")
+            .add_attribute("denom", denom),
+        TripReason::EmergencyRefunds {} => event.add_attribute("reason", "emergency_refunds"),
+    };
+    Ok(event)
+}
+
+// Refund counters start over with every window
+pub fn current_state(
+    storage: &dyn Storage,
+    config: &CircuitBreakerConfig,
+    current_time: u64,
+) -> StdResult<CircuitBreakerState> {
+    let window = current_time / config.window_seconds;
+    let mut state = CIRCUIT_BREAKER.load(storage)?;
+    if state.window != window {
+        state.window = window;
+        state.emergency_refunds = 0;
+    }
+    Ok(state)
+}
+
+pub fn current_completion_volume(
+    storage: &dyn Storage,
+    config: &CircuitBreakerConfig,
+    denom: &str,
+    current_time: u64,
+) -> StdResult<CompletionVolume> {
+    let window = current_time / config.window_seconds;
+    let mut volume = COMPLETION_VOLUME
+        .may_load(storage, denom)?
+        .unwrap_or(CompletionVolume {
+            window,
+            volume: Uint128::zero(),
+            trailing_average: Uint128::zero(),
+        });
+
+    // Fold every window that has passed into the moving average, idle ones count as zero
+    let elapsed = window.saturating_sub(volume.window);
+    let periods = Uint128::from(config.trailing_windows);
+    let retained = Uint128::from(config.trailing_windows - 1);
+    for i in 0..elapsed.min(config.trailing_windows as u64) {
+        let sample = if i == 0 {
+            volume.volume
+        } else {
+            Uint128::zero()
+        };
+        volume.trailing_average = (volume.trailing_average * retained + sample) / periods;
+    }
+    if elapsed > 0 {
+        volume.window = window;
+        volume.volume = Uint128::zero();
+    }
+
+    Ok(volume)
+}
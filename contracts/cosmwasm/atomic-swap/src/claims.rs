@@ -0,0 +1,36 @@
+use cosmwasm_std::{Addr, Coin, Order, StdResult, Storage, Uint128};
+
+use crate::state::CLAIMABLE;
+
+// Credits coins the contract could not send right away to the recipient's claimable balance
+pub fn credit_claimable(storage: &mut dyn Storage, recipient: &Addr, coin: &Coin) -> StdResult<()> {
+    CLAIMABLE.update(
+        storage,
+        (recipient, &coin.denom),
+        |balance| -> StdResult<_> { Ok(balance.unwrap_or_default() + coin.amount) },
+    )?;
+    Ok(())
+}
+
+pub fn claimable_balances(storage: &dyn Storage, address: &Addr) -> StdResult<Vec<Coin>> {
+    CLAIMABLE
+        .prefix(address)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(Coin { denom, amount })
+        })
+        .collect()
+}
+
+// Removes and returns everything claimable by the address
+pub fn take_claimable(storage: &mut dyn Storage, address: &Addr) -> StdResult<Vec<Coin>> {
+    let balances = claimable_balances(storage, address)?;
+    for coin in &balances {
+        CLAIMABLE.remove(storage, (address, &coin.denom));
+    }
+    Ok(balances
+        .into_iter()
+        .filter(|coin| coin.amount > Uint128::zero())
+        .collect())
+}
@@ -1,21 +1,70 @@
 #[cfg(not(feature = "imported"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, to_json_binary, Addr, BankMsg, Binary, Bound, Coin, Deps, DepsMut, Env, MessageInfo, Order, Response,
-    StdResult, Uint128,
+    coin, to_json_binary, Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Env, Event,
+    MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsg, Uint128,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::{Bound, Prefix};
 use sha2::{Digest, Sha256};
 
+use crate::access::{ensure_permitted, is_denied};
+use crate::actions::{ensure_single_route, is_allowed_action, run_action, validate_action};
+use crate::attestation::{
+    attest_lock, attest_refund, default_attestor_config, load_attestor, validate_attestor_config,
+};
+use crate::circuit_breaker::{
+    current_state, default_circuit_breaker_config, ensure_not_tripped, initial_state,
+    record_completion, record_emergency_refund, reset, validate_circuit_breaker_config,
+};
+use crate::claims::{claimable_balances, credit_claimable, take_claimable};
+use crate::counterpart::{
+    build_counterpart_leg, link_counterpart_swap_id, load_enabled_chain,
+    normalize_counterpart_reference, resolve_counterpart_msg, validate_counterpart_chain,
+    validate_timelock_ordering,
+};
 use crate::error::ContractError;
+use crate::events::{RefundReason, SwapEvent};
+use crate::hooks::{hook_messages, party_hooks, swap_hooks, HOOK_REPLY_ID, MAX_SWAP_HOOKS};
+use crate::ibc::{apply_stored_remote_lock, notify_counterpart};
+use crate::ibc_msg::SwapPacket;
+use crate::ica::{
+    ensure_action_allowed, open_account, record_submission, register_msg, settle_ica_tx,
+    submit_remote, validate_ica_account, ICA_SUBMIT_REPLY_ID,
+};
+use crate::light_client::{submit_header, verify_lock_proof};
+use crate::limits::{
+    current_epoch, current_window, default_limits, default_outflow_limits, find_amount,
+    record_initiation, record_outflow, release_active, validate_limits, validate_outflow_limits,
+    volume_frees_at, window_volume,
+};
+use crate::migrations::migrate_legacy_swaps;
 use crate::msg::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SwapResponse, SwapState,
+    AccessStatusResponse, AddressesResponse, AllowanceResponse, CircuitBreakerResponse,
+    ClaimableResponse, ConfigResponse, CounterpartChainsResponse, CounterpartLegMsg,
+    ExchangeRateResponse, ExecuteMsg, ExpiringSwap, ExpiringSwapsResponse, ExpiryCursor,
+    IbcChannelsResponse, IbcLifecycleComplete, InstantiateMsg, MigrateMsg, OutflowUsageResponse,
+    QueryMsg, RequestPacket, SortOrder, SudoMsg, SwapFilter, SwapHookMsg, SwapResponse, SwapState,
     SwapStatsResponse, SwapsResponse,
 };
+use crate::payouts::{payout, take_staged_payout, PAYOUT_REPLY_ID_BASE};
+use crate::recipient::validate_recipient;
 use crate::state::{
-    AtomicSwap, Config, SwapStats, CONFIG, SWAPS, SWAPS_BY_HASHLOCK, SWAPS_BY_INITIATOR,
-    SWAPS_BY_PARTICIPANT, SWAP_STATS,
+    state_key, swaps, AtomicSwap, Attestation, AttestationStatus, AttestorConfig, ChainFamily,
+    CircuitBreakerConfig, CompletionAction, Config, CounterpartChain, HookFunder, IbcDelivery,
+    IcaAccount, OutflowLimits, PayoutRoute, RemoteAction, RemoteLeg, RemoteLegStatus,
+    RevealedSecret, SwapLimits, SwapRole, SwapStats, ACCESS_CONFIG, ACTION_CONTRACTS,
+    ACTIVE_SWAPS_BY_INITIATOR, ACTIVE_SWAPS_BY_PARTICIPANT, ALLOWLIST, ATTESTOR_CONFIG,
+    CIRCUIT_BREAKER, CIRCUIT_BREAKER_CONFIG, CONFIG, COUNTERPART_CHAINS, COUNTERPART_HEADERS,
+    DENYLIST, HOOKS, IBC_CHANNELS, IBC_TRANSFERS, ICA_ACCOUNTS, OUTFLOW_LIMITS, RELAYERS,
+    REMOTE_LEGS, REMOTE_LOCKS, REVEALED_SECRETS, SWAPS_BY_COUNTERPART_ID, SWAP_HOOKS, SWAP_LIMITS,
+    SWAP_STATS,
+};
+use crate::transfer::{
+    deliver, ensure_hook_sender, fail_staged_transfer, record_transfer_sequence, settle_transfer,
+    transfer_packet, validate_delivery, IBC_TRANSFER_REPLY_ID,
 };
+use crate::validation::{safety_deposit_ceiling, validate_admin, validate_config};
 
 const CONTRACT_NAME: &str = "crates.io:atomic-swap";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -24,10 +73,13 @@ const DEFAULT_MIN_TIMELOCK_DURATION: u64 = 3600; // 1 hour
 const DEFAULT_MAX_TIMELOCK_DURATION: u64 = 7 * 24 * 3600; // 1 week
 const DEFAULT_MINIMUM_SAFETY_DEPOSIT: u128 = 1000000; // 1 ujuno
 
+// Swaps a filtered listing looks at per page, whether they match or not
+const MAX_SWAPS_SCANNED: usize = 300;
+
 #[cfg_attr(not(feature = "imported"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -35,7 +87,7 @@ pub fn instantiate(
 
     let admin = msg
         .admin
-        .map(|s| deps.api.addr_validate(&s))
+        .map(|s| validate_admin(deps.api, &s, &env.contract.address))
         .transpose()?
         .unwrap_or_else(|| info.sender.clone());
 
@@ -44,6 +96,7 @@ pub fn instantiate(
         minimum_safety_deposit: msg
             .minimum_safety_deposit
             .unwrap_or_else(|| Uint128::new(DEFAULT_MINIMUM_SAFETY_DEPOSIT)),
+        safety_deposit_ceiling: msg.safety_deposit_ceiling,
         min_timelock_duration: msg
             .min_timelock_duration
             .unwrap_or(DEFAULT_MIN_TIMELOCK_DURATION),
@@ -53,8 +106,31 @@ pub fn instantiate(
         paused: false,
     };
 
+    validate_config(&config)?;
     CONFIG.save(deps.storage, &config)?;
 
+    let limits = msg.limits.unwrap_or_else(default_limits);
+    validate_limits(&limits)?;
+    SWAP_LIMITS.save(deps.storage, &limits)?;
+
+    let outflow_limits = msg.outflow_limits.unwrap_or_else(default_outflow_limits);
+    validate_outflow_limits(&outflow_limits)?;
+    OUTFLOW_LIMITS.save(deps.storage, &outflow_limits)?;
+
+    let circuit_breaker = msg
+        .circuit_breaker
+        .unwrap_or_else(default_circuit_breaker_config);
+    validate_circuit_breaker_config(&circuit_breaker)?;
+    CIRCUIT_BREAKER_CONFIG.save(deps.storage, &circuit_breaker)?;
+    CIRCUIT_BREAKER.save(deps.storage, &initial_state())?;
+
+    for chain in msg.counterpart_chains.unwrap_or_default() {
+        validate_counterpart_chain(&chain)?;
+        COUNTERPART_CHAINS.save(deps.storage, &chain.chain_id, &chain)?;
+    }
+
+    ATTESTOR_CONFIG.save(deps.storage, &default_attestor_config())?;
+
     let stats = SwapStats {
         total_swaps: 0,
         completed_swaps: 0,
@@ -85,26 +161,62 @@ pub fn execute(
             amount,
             hashlock,
             timelock,
+            counterpart,
             ethereum_recipient,
             ethereum_chain_id,
-        } => execute_initiate_swap(
-            deps,
-            env,
-            info,
+        } => {
+            let terms = SwapTerms {
+                participant,
+                hashlock,
+                timelock,
+                counterpart: resolve_counterpart_msg(
+                    counterpart,
+                    ethereum_recipient,
+                    ethereum_chain_id,
+                )?,
+            };
+            execute_initiate_swap(deps, env, info, amount, terms)
+        }
+        ExecuteMsg::HookInitiateSwap {
+            source_channel,
+            original_sender,
+            initiator,
             participant,
             amount,
             hashlock,
             timelock,
-            ethereum_recipient,
-            ethereum_chain_id,
-        ),
-        ExecuteMsg::CompleteSwap { swap_id, secret } => {
-            execute_complete_swap(deps, env, info, swap_id, secret)
+            counterpart,
+        } => {
+            let terms = SwapTerms {
+                participant,
+                hashlock,
+                timelock,
+                counterpart,
+            };
+            let funding = HookFunding {
+                source_channel,
+                original_sender,
+                initiator,
+                amount,
+            };
+            execute_hook_initiate_swap(deps, env, info, funding, terms)
         }
+        ExecuteMsg::SetPayoutRoute {
+            swap_id,
+            delivery,
+            action,
+        } => execute_set_payout_route(deps, info, swap_id, delivery, action),
+        ExecuteMsg::CompleteSwap {
+            swap_id,
+            secret,
+            delivery,
+            action,
+        } => execute_complete_swap(deps, env, info, swap_id, secret, delivery, action),
         ExecuteMsg::RefundSwap { swap_id } => execute_refund_swap(deps, env, info, swap_id),
         ExecuteMsg::UpdateConfig {
             admin,
             minimum_safety_deposit,
+            safety_deposit_ceiling,
             min_timelock_duration,
             max_timelock_duration,
             paused,
@@ -112,38 +224,255 @@ pub fn execute(
             deps,
             env,
             info,
-            admin,
-            minimum_safety_deposit,
-            min_timelock_duration,
-            max_timelock_duration,
-            paused,
+            ConfigUpdate {
+                admin,
+                minimum_safety_deposit,
+                safety_deposit_ceiling,
+                min_timelock_duration,
+                max_timelock_duration,
+                paused,
+            },
         ),
         ExecuteMsg::EmergencyRefund { swap_id } => {
             execute_emergency_refund(deps, env, info, swap_id)
         }
+        ExecuteMsg::UpdateLimits { limits } => execute_update_limits(deps, info, limits),
+        ExecuteMsg::UpdateOutflowLimits { limits } => {
+            execute_update_outflow_limits(deps, info, limits)
+        }
+        ExecuteMsg::UpdateCircuitBreaker { config } => {
+            execute_update_circuit_breaker(deps, info, config)
+        }
+        ExecuteMsg::ResetCircuitBreaker {} => execute_reset_circuit_breaker(deps, env, info),
+        ExecuteMsg::UpdateDenylist { add, remove } => {
+            execute_update_denylist(deps, info, add, remove)
+        }
+        ExecuteMsg::UpdateAllowlist { add, remove } => {
+            execute_update_allowlist(deps, info, add, remove)
+        }
+        ExecuteMsg::SetAllowlistOnly { enabled } => execute_set_allowlist_only(deps, info, enabled),
+        ExecuteMsg::Claim {} => execute_claim(deps, info),
+        ExecuteMsg::UpsertCounterpartChain { chain } => {
+            execute_upsert_counterpart_chain(deps, info, chain)
+        }
+        ExecuteMsg::RemoveCounterpartChain { chain_id } => {
+            execute_remove_counterpart_chain(deps, info, chain_id)
+        }
+        ExecuteMsg::UpdateRelayers { add, remove } => {
+            execute_update_relayers(deps, info, add, remove)
+        }
+        ExecuteMsg::UpdateHooks { add, remove } => execute_update_hooks(deps, info, add, remove),
+        ExecuteMsg::UpdateActionContracts { add, remove } => {
+            execute_update_action_contracts(deps, info, add, remove)
+        }
+        ExecuteMsg::UpdateSwapHooks {
+            swap_id,
+            add,
+            remove,
+        } => execute_update_swap_hooks(deps, info, swap_id, add, remove),
+        ExecuteMsg::LinkCounterpart {
+            swap_id,
+            counterpart_swap_id,
+            lock_tx_hash,
+        } => execute_link_counterpart(deps, info, swap_id, counterpart_swap_id, lock_tx_hash),
+        ExecuteMsg::UpdateAttestors {
+            attestors,
+            threshold,
+            require_lock_attestation,
+        } => execute_update_attestors(deps, info, attestors, threshold, require_lock_attestation),
+        ExecuteMsg::AttestCounterpartLock { swap_id } => {
+            execute_attest_counterpart_lock(deps, env, info, swap_id)
+        }
+        ExecuteMsg::AttestCounterpartRefund { swap_id } => {
+            execute_attest_counterpart_refund(deps, env, info, swap_id)
+        }
+        ExecuteMsg::SubmitCounterpartHeader {
+            chain_id,
+            header_rlp,
+        } => execute_submit_counterpart_header(deps, env, info, chain_id, header_rlp),
+        ExecuteMsg::RegisterIca {
+            ica_id,
+            chain_id,
+            connection_id,
+            remote_contract,
+            ack_fee,
+            timeout_fee,
+            timeout_seconds,
+        } => execute_register_ica(
+            deps,
+            env,
+            info,
+            IcaAccount {
+                ica_id,
+                chain_id,
+                connection_id,
+                remote_contract,
+                ack_fee,
+                timeout_fee,
+                timeout_seconds,
+                channel_id: None,
+                address: None,
+            },
+        ),
+        ExecuteMsg::IcaLockCounterpart {
+            swap_id,
+            ica_id,
+            amount,
+            safety_deposit,
+            timelock,
+        } => execute_ica_lock_counterpart(
+            deps,
+            env,
+            info,
+            swap_id,
+            ica_id,
+            RemoteLockTerms {
+                amount,
+                safety_deposit,
+                timelock,
+            },
+        ),
+        ExecuteMsg::IcaCompleteCounterpart {
+            swap_id,
+            ica_id,
+            remote_swap_id,
+        } => execute_ica_complete_counterpart(deps, env, info, swap_id, ica_id, remote_swap_id),
+        ExecuteMsg::IcaRefundCounterpart { swap_id } => {
+            execute_ica_refund_counterpart(deps, env, info, swap_id)
+        }
+        ExecuteMsg::ProveCounterpartLock {
+            swap_id,
+            block_number,
+            tx_index,
+            log_index,
+            receipt_proof,
+        } => execute_prove_counterpart_lock(
+            deps,
+            env,
+            swap_id,
+            block_number,
+            tx_index,
+            log_index,
+            receipt_proof,
+        ),
     }
 }
 
+// What a swap's initiator chose, regardless of how the swap is funded
+pub struct SwapTerms {
+    pub participant: String,
+    pub hashlock: String,
+    pub timelock: u64,
+    pub counterpart: CounterpartLegMsg,
+}
+
 pub fn execute_initiate_swap(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    participant: String,
     amount: Coin,
-    hashlock: String,
-    timelock: u64,
-    ethereum_recipient: String,
-    ethereum_chain_id: String,
+    terms: SwapTerms,
+) -> Result<Response, ContractError> {
+    // The funds sent in the swap's denom escrow the amount and the rest is the safety deposit
+    let safety_deposit = info
+        .funds
+        .iter()
+        .find(|coin| coin.denom == amount.denom)
+        .map(|coin| coin.amount)
+        .unwrap_or_default()
+        .checked_sub(amount.amount)
+        .map_err(|_| ContractError::InvalidFunds {})?;
+
+    initiate_swap(deps, env, info.sender, amount, safety_deposit, terms, None)
+}
+
+// How an ibc-hooks transfer funds a swap on behalf of its initiator
+pub struct HookFunding {
+    pub source_channel: String,
+    pub original_sender: String,
+    pub initiator: String,
+    pub amount: Uint128,
+}
+
+// Target of an ibc-hooks wasm memo. The hook executes from an intermediary
+// address with the received voucher as funds, so the initiator is named
+// explicitly and the single coin funds both the amount and the safety deposit.
+// Any error fails the transfer, which refunds the sender on the source chain.
+pub fn execute_hook_initiate_swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    funding: HookFunding,
+    terms: SwapTerms,
+) -> Result<Response, ContractError> {
+    let HookFunding {
+        source_channel,
+        original_sender,
+        initiator,
+        amount,
+    } = funding;
+    ensure_hook_sender(&info.sender, &source_channel, &original_sender)?;
+    ensure_permitted(deps.storage, &info.sender)?;
+
+    let funds = match info.funds.as_slice() {
+        [funds] => funds,
+        _ => return Err(ContractError::InvalidFunds {}),
+    };
+    let safety_deposit = funds
+        .amount
+        .checked_sub(amount)
+        .map_err(|_| ContractError::InvalidFunds {})?;
+    let initiator = deps.api.addr_validate(&initiator)?;
+
+    let amount = coin(amount.u128(), &funds.denom);
+    let funder = HookFunder {
+        channel_id: source_channel,
+        sender: original_sender,
+        intermediary: info.sender.clone(),
+    };
+
+    let response = initiate_swap(
+        deps,
+        env,
+        initiator,
+        amount,
+        safety_deposit,
+        terms,
+        Some(funder),
+    )?;
+
+    Ok(response.add_attribute("funded_by", info.sender))
+}
+
+fn initiate_swap(
+    deps: DepsMut,
+    env: Env,
+    initiator: Addr,
+    amount: Coin,
+    safety_deposit: Uint128,
+    terms: SwapTerms,
+    funder: Option<HookFunder>,
 ) -> Result<Response, ContractError> {
+    let SwapTerms {
+        participant,
+        hashlock,
+        timelock,
+        counterpart,
+    } = terms;
     let config = CONFIG.load(deps.storage)?;
 
     if config.paused {
         return Err(ContractError::ContractPaused {});
     }
+    ensure_not_tripped(deps.storage)?;
 
     // Validate participant address
     let participant_addr = deps.api.addr_validate(&participant)?;
 
+    // Screen both sides of the swap
+    ensure_permitted(deps.storage, &initiator)?;
+    ensure_permitted(deps.storage, &participant_addr)?;
+
     // Validate amount
     if amount.amount.is_zero() {
         return Err(ContractError::InvalidAmount {});
@@ -164,6 +493,11 @@ pub fn execute_initiate_swap(
         });
     }
 
+    // Validate the counterpart leg against the chain registry
+    let chain = load_enabled_chain(deps.storage, &counterpart.chain_id)?;
+    validate_recipient(&chain.address_format, &counterpart.recipient)?;
+    let counterpart = build_counterpart_leg(&chain, counterpart, timelock)?;
+
     // Validate hashlock format (must be 64 character hex string for SHA256)
     if hashlock.len() != 64 || !hashlock.chars().all(|c| c.is_ascii_hexdigit()) {
         return Err(ContractError::Std(cosmwasm_std::StdError::generic_err(
@@ -172,13 +506,6 @@ pub fn execute_initiate_swap(
     }
 
     // Check safety deposit
-    let safety_deposit = info
-        .funds
-        .iter()
-        .find(|coin| coin.denom == amount.denom)
-        .map(|coin| coin.amount)
-        .unwrap_or_default();
-
     if safety_deposit < config.minimum_safety_deposit {
         return Err(ContractError::SafetyDepositTooLow {
             minimum: config.minimum_safety_deposit.to_string(),
@@ -187,7 +514,7 @@ pub fn execute_initiate_swap(
 
     // Generate swap ID
     let swap_id = generate_swap_id(
-        &info.sender,
+        &initiator,
         &participant_addr,
         &hashlock,
         timelock,
@@ -195,34 +522,44 @@ pub fn execute_initiate_swap(
     );
 
     // Check if swap already exists
-    if SWAPS.has(deps.storage, &swap_id) {
+    if swaps().has(deps.storage, &swap_id) {
         return Err(ContractError::SwapAlreadyExists { swap_id });
     }
 
+    // Enforce per-address rate limits
+    let limits = SWAP_LIMITS.load(deps.storage)?;
+    record_initiation(
+        deps.storage,
+        &limits,
+        &initiator,
+        &participant_addr,
+        &amount,
+        current_time,
+    )?;
+
     // Create the swap
-    let swap = AtomicSwap {
+    let mut swap = AtomicSwap {
         swap_id: swap_id.clone(),
-        initiator: info.sender.clone(),
+        initiator: initiator.clone(),
         participant: participant_addr.clone(),
         amount: amount.clone(),
         hashlock: hashlock.clone(),
         timelock,
         state: SwapState::Initiated,
         secret: None,
-        ethereum_recipient,
-        ethereum_chain_id,
+        counterpart,
+        attestation: Attestation::default(),
         created_at: current_time,
         completed_at: None,
         safety_deposit,
+        delivery: None,
+        action: None,
+        funder,
     };
+    apply_stored_remote_lock(deps.storage, &chain, &mut swap, current_time)?;
 
     // Save the swap
-    SWAPS.save(deps.storage, &swap_id, &swap)?;
-
-    // Update indexes
-    SWAPS_BY_INITIATOR.save(deps.storage, (info.sender.as_str(), &swap_id), &true)?;
-    SWAPS_BY_PARTICIPANT.save(deps.storage, (participant_addr.as_str(), &swap_id), &true)?;
-    SWAPS_BY_HASHLOCK.save(deps.storage, (&hashlock, &swap_id), &true)?;
+    swaps().save(deps.storage, &swap_id, &swap)?;
 
     // Update stats
     let mut stats = SWAP_STATS.load(deps.storage)?;
@@ -230,53 +567,126 @@ pub fn execute_initiate_swap(
     add_to_volume(&mut stats.total_volume, &amount);
     SWAP_STATS.save(deps.storage, &stats)?;
 
+    // Announce the lock when the counterpart is another deployment of this contract
+    let announcement = notify_counterpart(
+        deps.storage,
+        &env,
+        &swap.counterpart.chain_id,
+        &SwapPacket::Lock {
+            swap_id: swap_id.clone(),
+            hashlock: hashlock.clone(),
+            amount: amount.clone(),
+            timelock,
+            recipient: swap.counterpart.recipient.clone(),
+        },
+    )?;
+
+    let hooks = hook_messages(
+        deps.storage,
+        &swap_id,
+        SwapHookMsg::Initiated {
+            swap_id: swap_id.clone(),
+            initiator: initiator.clone(),
+            participant: participant_addr.clone(),
+            amount: amount.clone(),
+            hashlock: hashlock.clone(),
+            timelock,
+        },
+    )?;
+
     Ok(Response::new()
+        .add_event(SwapEvent::initiated(&swap).to_event())
+        .add_messages(announcement)
+        .add_submessages(hooks)
+        .set_data(to_json_binary(&swap_id)?)
         .add_attribute("method", "initiate_swap")
         .add_attribute("swap_id", swap_id)
-        .add_attribute("initiator", info.sender)
+        .add_attribute("initiator", initiator)
         .add_attribute("participant", participant_addr)
         .add_attribute("amount", amount.to_string())
         .add_attribute("hashlock", hashlock)
         .add_attribute("timelock", timelock.to_string()))
 }
 
+// The payout belongs to the participant, so only they choose where it goes
+pub fn execute_set_payout_route(
+    deps: DepsMut,
+    info: MessageInfo,
+    swap_id: String,
+    delivery: Option<IbcDelivery>,
+    action: Option<CompletionAction>,
+) -> Result<Response, ContractError> {
+    let mut swap = swaps().load(deps.storage, &swap_id)?;
+
+    if info.sender != swap.participant {
+        return Err(ContractError::OnlyParticipant {});
+    }
+
+    match swap.state {
+        SwapState::Initiated => {}
+        SwapState::Completed => return Err(ContractError::SwapAlreadyCompleted {}),
+        SwapState::Refunded => return Err(ContractError::SwapAlreadyRefunded {}),
+    }
+
+    if let Some(delivery) = &delivery {
+        validate_delivery(delivery)?;
+    }
+    ensure_single_route(&delivery, &action)?;
+    swap.action = action
+        .map(|action| validate_action(deps.api, deps.storage, action))
+        .transpose()?;
+    swap.delivery = delivery;
+    swaps().save(deps.storage, &swap_id, &swap)?;
+
+    let route = match (&swap.delivery, &swap.action) {
+        (Some(_), _) => "ibc_delivery",
+        (_, Some(_)) => "action",
+        _ => "bank",
+    };
+
+    Ok(Response::new()
+        .add_attribute("method", "set_payout_route")
+        .add_attribute("swap_id", swap_id)
+        .add_attribute("route", route))
+}
+
 pub fn execute_complete_swap(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     swap_id: String,
     secret: String,
+    delivery: Option<IbcDelivery>,
+    action: Option<CompletionAction>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
 
     if config.paused {
         return Err(ContractError::ContractPaused {});
     }
+    ensure_not_tripped(deps.storage)?;
 
-    let mut swap = SWAPS.load(deps.storage, &swap_id)?;
+    let mut swap = swaps().load(deps.storage, &swap_id)?;
 
     // Verify only participant can complete
     if info.sender != swap.participant {
         return Err(ContractError::OnlyParticipant {});
     }
 
-    // Check if swap is in initiated state
-    match swap.state {
-        SwapState::Initiated => {}
-        SwapState::Completed => return Err(ContractError::SwapAlreadyCompleted {}),
-        SwapState::Refunded => return Err(ContractError::SwapAlreadyRefunded {}),
-    }
-
-    // Check if swap has not expired
     let current_time = env.block.time.seconds();
-    if current_time >= swap.timelock {
-        return Err(ContractError::SwapExpired {
-            current_time,
-            expiry: swap.timelock,
-        });
+    ensure_completable(deps.storage, &swap, current_time)?;
+    if let Some(delivery) = &delivery {
+        validate_delivery(delivery)?;
     }
+    ensure_single_route(&delivery, &action)?;
+    let action = action
+        .map(|action| validate_action(deps.api, deps.storage, action))
+        .transpose()?;
 
     // Verify secret against hashlock
+    // A wrong secret fails the transaction and reverts whatever it wrote, so
+    // failed attempts cannot be counted on chain towards the breaker
+    let breaker = CIRCUIT_BREAKER_CONFIG.load(deps.storage)?;
     if !verify_secret(&swap.hashlock, &secret)? {
         let actual_hash = hex::encode(Sha256::digest(secret.as_bytes()));
         return Err(ContractError::InvalidSecret {
@@ -285,36 +695,31 @@ pub fn execute_complete_swap(
         });
     }
 
-    // Update swap state
-    swap.state = SwapState::Completed;
-    swap.secret = Some(secret.clone());
-    swap.completed_at = Some(current_time);
-    SWAPS.save(deps.storage, &swap_id, &swap)?;
-
-    // Update stats
-    let mut stats = SWAP_STATS.load(deps.storage)?;
-    stats.completed_swaps += 1;
-    SWAP_STATS.save(deps.storage, &stats)?;
-
-    // Prepare messages
-    let mut messages = vec![];
-
-    // Transfer tokens to participant
-    messages.push(BankMsg::Send {
-        to_address: swap.participant.to_string(),
-        amount: coins(swap.amount.amount.u128(), &swap.amount.denom),
-    });
-
-    // Return safety deposit to initiator
-    if !swap.safety_deposit.is_zero() {
-        messages.push(BankMsg::Send {
-            to_address: swap.initiator.to_string(),
-            amount: coins(swap.safety_deposit.u128(), &swap.amount.denom),
-        });
-    }
+    let (messages, events) = settle_completion(
+        deps.storage,
+        &env,
+        &breaker,
+        &mut swap,
+        secret.clone(),
+        delivery,
+        action,
+    )?;
+
+    let revealed = notify_counterpart(
+        deps.storage,
+        &env,
+        &swap.counterpart.chain_id,
+        &SwapPacket::SecretRevealed {
+            swap_id: swap_id.clone(),
+            hashlock: swap.hashlock.clone(),
+            secret: secret.clone(),
+        },
+    )?;
 
     Ok(Response::new()
-        .add_messages(messages)
+        .add_submessages(messages)
+        .add_messages(revealed)
+        .add_events(events)
         .add_attribute("method", "complete_swap")
         .add_attribute("swap_id", swap_id)
         .add_attribute("secret", secret)
@@ -333,7 +738,7 @@ pub fn execute_refund_swap(
         return Err(ContractError::ContractPaused {});
     }
 
-    let mut swap = SWAPS.load(deps.storage, &swap_id)?;
+    let mut swap = swaps().load(deps.storage, &swap_id)?;
 
     // Verify only initiator can refund
     if info.sender != swap.initiator {
@@ -359,7 +764,8 @@ pub fn execute_refund_swap(
     // Update swap state
     swap.state = SwapState::Refunded;
     swap.completed_at = Some(current_time);
-    SWAPS.save(deps.storage, &swap_id, &swap)?;
+    swaps().save(deps.storage, &swap_id, &swap)?;
+    release_active(deps.storage, &swap.initiator, &swap.participant)?;
 
     // Update stats
     let mut stats = SWAP_STATS.load(deps.storage)?;
@@ -370,36 +776,74 @@ pub fn execute_refund_swap(
     let mut messages = vec![];
 
     // Return tokens to initiator
-    messages.push(BankMsg::Send {
-        to_address: swap.initiator.to_string(),
-        amount: coins(swap.amount.amount.u128(), &swap.amount.denom),
-    });
+    messages.extend(payout(
+        deps.storage,
+        &swap_id,
+        &swap.initiator,
+        swap.amount.clone(),
+    )?);
 
     // Return safety deposit to initiator
-    if !swap.safety_deposit.is_zero() {
-        messages.push(BankMsg::Send {
-            to_address: swap.initiator.to_string(),
-            amount: coins(swap.safety_deposit.u128(), &swap.amount.denom),
-        });
-    }
+    messages.extend(payout(
+        deps.storage,
+        &swap_id,
+        &swap.initiator,
+        coin(swap.safety_deposit.u128(), &swap.amount.denom),
+    )?);
+
+    let refunded = notify_counterpart(
+        deps.storage,
+        &env,
+        &swap.counterpart.chain_id,
+        &SwapPacket::Refunded {
+            swap_id: swap_id.clone(),
+            hashlock: swap.hashlock.clone(),
+        },
+    )?;
+    let hooks = hook_messages(
+        deps.storage,
+        &swap_id,
+        SwapHookMsg::Refunded {
+            swap_id: swap_id.clone(),
+            initiator: swap.initiator.clone(),
+            amount: swap.amount.clone(),
+        },
+    )?;
 
     Ok(Response::new()
-        .add_messages(messages)
+        .add_event(SwapEvent::refunded(&swap, RefundReason::Expired).to_event())
+        .add_submessages(messages)
+        .add_messages(refunded)
+        .add_submessages(hooks)
         .add_attribute("method", "refund_swap")
         .add_attribute("swap_id", swap_id)
         .add_attribute("refunded_to", info.sender))
 }
 
+// Fields of UpdateConfig, unset ones keep their current value
+pub struct ConfigUpdate {
+    pub admin: Option<String>,
+    pub minimum_safety_deposit: Option<Uint128>,
+    pub safety_deposit_ceiling: Option<Uint128>,
+    pub min_timelock_duration: Option<u64>,
+    pub max_timelock_duration: Option<u64>,
+    pub paused: Option<bool>,
+}
+
 pub fn execute_update_config(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    admin: Option<String>,
-    minimum_safety_deposit: Option<Uint128>,
-    min_timelock_duration: Option<u64>,
-    max_timelock_duration: Option<u64>,
-    paused: Option<bool>,
+    update: ConfigUpdate,
 ) -> Result<Response, ContractError> {
+    let ConfigUpdate {
+        admin,
+        minimum_safety_deposit,
+        safety_deposit_ceiling,
+        min_timelock_duration,
+        max_timelock_duration,
+        paused,
+    } = update;
     let mut config = CONFIG.load(deps.storage)?;
 
     if info.sender != config.admin {
@@ -409,7 +853,7 @@ pub fn execute_update_config(
     let mut response = Response::new().add_attribute("method", "update_config");
 
     if let Some(admin_addr) = admin {
-        let new_admin = deps.api.addr_validate(&admin_addr)?;
+        let new_admin = validate_admin(deps.api, &admin_addr, &env.contract.address)?;
         config.admin = new_admin.clone();
         response = response.add_attribute("new_admin", new_admin);
     }
@@ -419,6 +863,11 @@ pub fn execute_update_config(
         response = response.add_attribute("new_minimum_safety_deposit", deposit.to_string());
     }
 
+    if let Some(ceiling) = safety_deposit_ceiling {
+        config.safety_deposit_ceiling = Some(ceiling);
+        response = response.add_attribute("new_safety_deposit_ceiling", ceiling.to_string());
+    }
+
     if let Some(min_duration) = min_timelock_duration {
         config.min_timelock_duration = min_duration;
         response = response.add_attribute("new_min_timelock_duration", min_duration.to_string());
@@ -434,6 +883,7 @@ pub fn execute_update_config(
         response = response.add_attribute("paused", pause_state.to_string());
     }
 
+    validate_config(&config)?;
     CONFIG.save(deps.storage, &config)?;
 
     Ok(response)
@@ -451,7 +901,8 @@ pub fn execute_emergency_refund(
         return Err(ContractError::Unauthorized {});
     }
 
-    let mut swap = SWAPS.load(deps.storage, &swap_id)?;
+    // Refunds stay open while the breaker is tripped
+    let mut swap = swaps().load(deps.storage, &swap_id)?;
 
     // Check if swap is in initiated state
     match swap.state {
@@ -464,7 +915,19 @@ pub fn execute_emergency_refund(
     let current_time = env.block.time.seconds();
     swap.state = SwapState::Refunded;
     swap.completed_at = Some(current_time);
-    SWAPS.save(deps.storage, &swap_id, &swap)?;
+    swaps().save(deps.storage, &swap_id, &swap)?;
+    release_active(deps.storage, &swap.initiator, &swap.participant)?;
+
+    // Enforce the global outflow cap
+    let outflow_limits = OUTFLOW_LIMITS.load(deps.storage)?;
+    record_outflow(
+        deps.storage,
+        &outflow_limits,
+        &[released_from_escrow(&swap)],
+        current_time,
+    )?;
+    let breaker = CIRCUIT_BREAKER_CONFIG.load(deps.storage)?;
+    let tripped = record_emergency_refund(deps.storage, &breaker, current_time)?;
 
     // Update stats
     let mut stats = SWAP_STATS.load(deps.storage)?;
@@ -475,154 +938,998 @@ pub fn execute_emergency_refund(
     let mut messages = vec![];
 
     // Return tokens to initiator
-    messages.push(BankMsg::Send {
-        to_address: swap.initiator.to_string(),
-        amount: coins(swap.amount.amount.u128(), &swap.amount.denom),
-    });
+    messages.extend(payout(
+        deps.storage,
+        &swap_id,
+        &swap.initiator,
+        swap.amount.clone(),
+    )?);
 
     // Return safety deposit to initiator
-    if !swap.safety_deposit.is_zero() {
-        messages.push(BankMsg::Send {
-            to_address: swap.initiator.to_string(),
-            amount: coins(swap.safety_deposit.u128(), &swap.amount.denom),
-        });
-    }
+    messages.extend(payout(
+        deps.storage,
+        &swap_id,
+        &swap.initiator,
+        coin(swap.safety_deposit.u128(), &swap.amount.denom),
+    )?);
+
+    let refunded = notify_counterpart(
+        deps.storage,
+        &env,
+        &swap.counterpart.chain_id,
+        &SwapPacket::Refunded {
+            swap_id: swap_id.clone(),
+            hashlock: swap.hashlock.clone(),
+        },
+    )?;
+    let hooks = hook_messages(
+        deps.storage,
+        &swap_id,
+        SwapHookMsg::Refunded {
+            swap_id: swap_id.clone(),
+            initiator: swap.initiator.clone(),
+            amount: swap.amount.clone(),
+        },
+    )?;
 
     Ok(Response::new()
-        .add_messages(messages)
+        .add_event(SwapEvent::refunded(&swap, RefundReason::Emergency).to_event())
+        .add_submessages(messages)
+        .add_messages(refunded)
+        .add_submessages(hooks)
+        .add_events(tripped)
         .add_attribute("method", "emergency_refund")
         .add_attribute("swap_id", swap_id)
         .add_attribute("admin", info.sender))
 }
 
-#[cfg_attr(not(feature = "imported"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
-        QueryMsg::Swap { swap_id } => to_json_binary(&query_swap(deps, swap_id)?),
-        QueryMsg::SwapsByInitiator {
-            initiator,
-            start_after,
-            limit,
-        } => to_json_binary(&query_swaps_by_initiator(deps, initiator, start_after, limit)?),
-        QueryMsg::SwapsByParticipant {
-            participant,
-            start_after,
-            limit,
-        } => to_json_binary(&query_swaps_by_participant(
-            deps,
-            participant,
-            start_after,
-            limit,
-        )?),
-        QueryMsg::SwapsByHashlock {
-            hashlock,
-            start_after,
-            limit,
-        } => to_json_binary(&query_swaps_by_hashlock(deps, hashlock, start_after, limit)?),
-        QueryMsg::SwapStats {} => to_json_binary(&query_swap_stats(deps)?),
+pub fn execute_update_limits(
+    deps: DepsMut,
+    info: MessageInfo,
+    limits: SwapLimits,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
     }
+
+    validate_limits(&limits)?;
+    SWAP_LIMITS.save(deps.storage, &limits)?;
+
+    Ok(Response::new().add_attribute("method", "update_limits"))
 }
 
-pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+pub fn execute_update_outflow_limits(
+    deps: DepsMut,
+    info: MessageInfo,
+    limits: OutflowLimits,
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    Ok(ConfigResponse {
-        admin: config.admin,
-        minimum_safety_deposit: config.minimum_safety_deposit,
-        min_timelock_duration: config.min_timelock_duration,
-        max_timelock_duration: config.max_timelock_duration,
-        paused: config.paused,
-    })
-}
 
-pub fn query_swap(deps: Deps, swap_id: String) -> StdResult<SwapResponse> {
-    let swap = SWAPS.load(deps.storage, &swap_id)?;
-    Ok(SwapResponse {
-        swap_id: swap.swap_id,
-        initiator: swap.initiator,
-        participant: swap.participant,
-        amount: swap.amount,
-        hashlock: swap.hashlock,
-        timelock: swap.timelock,
-        state: swap.state,
-        secret: swap.secret,
-        ethereum_recipient: swap.ethereum_recipient,
-        ethereum_chain_id: swap.ethereum_chain_id,
-        created_at: swap.created_at,
-        completed_at: swap.completed_at,
-    })
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    validate_outflow_limits(&limits)?;
+    OUTFLOW_LIMITS.save(deps.storage, &limits)?;
+
+    Ok(Response::new().add_attribute("method", "update_outflow_limits"))
 }
 
-pub fn query_swaps_by_initiator(
-    deps: Deps,
-    initiator: String,
-    start_after: Option<String>,
-    limit: Option<u32>,
-) -> StdResult<SwapsResponse> {
-    let limit = limit.unwrap_or(10).min(30) as usize;
-    let start = start_after.as_deref();
+pub fn execute_update_circuit_breaker(
+    deps: DepsMut,
+    info: MessageInfo,
+    breaker: CircuitBreakerConfig,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
 
-    let swaps: StdResult<Vec<_>> = SWAPS_BY_INITIATOR
-        .prefix(&initiator)
-        .range(deps.storage, start.map(Bound::exclusive), None, Order::Ascending)
-        .take(limit)
-        .map(|item| {
-            let (swap_id, _) = item?;
-            let swap = SWAPS.load(deps.storage, &swap_id)?;
-            Ok(SwapResponse {
-                swap_id: swap.swap_id,
-                initiator: swap.initiator,
-                participant: swap.participant,
-                amount: swap.amount,
-                hashlock: swap.hashlock,
-                timelock: swap.timelock,
-                state: swap.state,
-                secret: swap.secret,
-                ethereum_recipient: swap.ethereum_recipient,
-                ethereum_chain_id: swap.ethereum_chain_id,
-                created_at: swap.created_at,
-                completed_at: swap.completed_at,
-            })
-        })
-        .collect();
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    Ok(SwapsResponse { swaps: swaps? })
+    validate_circuit_breaker_config(&breaker)?;
+    CIRCUIT_BREAKER_CONFIG.save(deps.storage, &breaker)?;
+
+    Ok(Response::new().add_attribute("method", "update_circuit_breaker"))
 }
 
-pub fn query_swaps_by_participant(
-    deps: Deps,
-    participant: String,
-    start_after: Option<String>,
-    limit: Option<u32>,
-) -> StdResult<SwapsResponse> {
-    let limit = limit.unwrap_or(10).min(30) as usize;
-    let start = start_after.as_deref();
+pub fn execute_reset_circuit_breaker(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
 
-    let swaps: StdResult<Vec<_>> = SWAPS_BY_PARTICIPANT
-        .prefix(&participant)
-        .range(deps.storage, start.map(Bound::exclusive), None, Order::Ascending)
-        .take(limit)
-        .map(|item| {
-            let (swap_id, _) = item?;
-            let swap = SWAPS.load(deps.storage, &swap_id)?;
-            Ok(SwapResponse {
-                swap_id: swap.swap_id,
-                initiator: swap.initiator,
-                participant: swap.participant,
-                amount: swap.amount,
-                hashlock: swap.hashlock,
-                timelock: swap.timelock,
-                state: swap.state,
-                secret: swap.secret,
-                ethereum_recipient: swap.ethereum_recipient,
-                ethereum_chain_id: swap.ethereum_chain_id,
-                created_at: swap.created_at,
-                completed_at: swap.completed_at,
-            })
-        })
-        .collect();
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let event = reset(deps.storage, env.block.time.seconds())?;
 
-    Ok(SwapsResponse { swaps: swaps? })
+    Ok(Response::new()
+        .add_event(event)
+        .add_attribute("method", "reset_circuit_breaker")
+        .add_attribute("admin", info.sender))
+}
+
+pub fn execute_update_denylist(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in &add {
+        let addr = deps.api.addr_validate(address)?;
+        DENYLIST.save(deps.storage, &addr, &true)?;
+    }
+    for address in &remove {
+        let addr = deps.api.addr_validate(address)?;
+        DENYLIST.remove(deps.storage, &addr);
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "update_denylist")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+pub fn execute_update_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in &add {
+        let addr = deps.api.addr_validate(address)?;
+        ALLOWLIST.save(deps.storage, &addr, &true)?;
+    }
+    for address in &remove {
+        let addr = deps.api.addr_validate(address)?;
+        ALLOWLIST.remove(deps.storage, &addr);
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "update_allowlist")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+pub fn execute_set_allowlist_only(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut access = ACCESS_CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    access.allowlist_only = enabled;
+    ACCESS_CONFIG.save(deps.storage, &access)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_allowlist_only")
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+pub fn execute_claim(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    // Held funds stay put for as long as the address is denied
+    if is_denied(deps.storage, &info.sender) {
+        return Err(ContractError::AddressDenied {
+            address: info.sender.to_string(),
+        });
+    }
+
+    let balances = take_claimable(deps.storage, &info.sender)?;
+    if balances.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: balances,
+        })
+        .add_attribute("method", "claim")
+        .add_attribute("claimed_by", info.sender))
+}
+
+pub fn execute_upsert_counterpart_chain(
+    deps: DepsMut,
+    info: MessageInfo,
+    chain: CounterpartChain,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    validate_counterpart_chain(&chain)?;
+    COUNTERPART_CHAINS.save(deps.storage, &chain.chain_id, &chain)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "upsert_counterpart_chain")
+        .add_attribute("chain_id", chain.chain_id)
+        .add_attribute("enabled", chain.enabled.to_string()))
+}
+
+pub fn execute_remove_counterpart_chain(
+    deps: DepsMut,
+    info: MessageInfo,
+    chain_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Existing swaps keep their chain id, only new swaps are affected
+    COUNTERPART_CHAINS.remove(deps.storage, &chain_id);
+
+    Ok(Response::new()
+        .add_attribute("method", "remove_counterpart_chain")
+        .add_attribute("chain_id", chain_id))
+}
+
+pub fn execute_update_relayers(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in &add {
+        let addr = deps.api.addr_validate(address)?;
+        RELAYERS.save(deps.storage, &addr, &true)?;
+    }
+    for address in &remove {
+        let addr = deps.api.addr_validate(address)?;
+        RELAYERS.remove(deps.storage, &addr);
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "update_relayers")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+pub fn execute_update_hooks(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in &add {
+        let addr = deps.api.addr_validate(address)?;
+        HOOKS.save(deps.storage, &addr, &true)?;
+    }
+    for address in &remove {
+        let addr = deps.api.addr_validate(address)?;
+        HOOKS.remove(deps.storage, &addr);
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "update_hooks")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+pub fn execute_update_action_contracts(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in &add {
+        let addr = deps.api.addr_validate(address)?;
+        ACTION_CONTRACTS.save(deps.storage, &addr, &true)?;
+    }
+    for address in &remove {
+        let addr = deps.api.addr_validate(address)?;
+        ACTION_CONTRACTS.remove(deps.storage, &addr);
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "update_action_contracts")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+pub fn execute_update_swap_hooks(
+    deps: DepsMut,
+    info: MessageInfo,
+    swap_id: String,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let swap = swaps().load(deps.storage, &swap_id)?;
+
+    if info.sender != swap.initiator && info.sender != swap.participant {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match swap.state {
+        SwapState::Initiated => {}
+        SwapState::Completed => return Err(ContractError::SwapAlreadyCompleted {}),
+        SwapState::Refunded => return Err(ContractError::SwapAlreadyRefunded {}),
+    }
+
+    // Each party only manages the hooks run on its own side of the swap
+    for address in &remove {
+        let addr = deps.api.addr_validate(address)?;
+        SWAP_HOOKS.remove(deps.storage, (&swap_id, &info.sender, &addr));
+    }
+    for address in &add {
+        let addr = deps.api.addr_validate(address)?;
+        SWAP_HOOKS.save(deps.storage, (&swap_id, &info.sender, &addr), &true)?;
+    }
+
+    // Every hook runs on settlement, so their number stays bounded
+    if party_hooks(deps.storage, &swap_id, &info.sender)?.len() > MAX_SWAP_HOOKS {
+        return Err(ContractError::TooManyHooks {
+            swap_id,
+            limit: MAX_SWAP_HOOKS,
+        });
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "update_swap_hooks")
+        .add_attribute("swap_id", swap_id)
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+pub fn execute_link_counterpart(
+    deps: DepsMut,
+    info: MessageInfo,
+    swap_id: String,
+    counterpart_swap_id: String,
+    lock_tx_hash: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut swap = swaps().load(deps.storage, &swap_id)?;
+
+    // Either party of the swap or an authorized relayer can link
+    if info.sender != swap.initiator
+        && info.sender != swap.participant
+        && !RELAYERS.has(deps.storage, &info.sender)
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Settled swaps keep the link they settled with
+    match swap.state {
+        SwapState::Initiated => {}
+        SwapState::Completed => return Err(ContractError::SwapAlreadyCompleted {}),
+        SwapState::Refunded => return Err(ContractError::SwapAlreadyRefunded {}),
+    }
+
+    let family = swap.counterpart.chain_family.clone();
+    let counterpart_swap_id =
+        normalize_counterpart_reference(&family, "counterpart_swap_id", &counterpart_swap_id)?;
+
+    link_counterpart_swap_id(deps.storage, &mut swap, counterpart_swap_id.clone())?;
+
+    let mut response = Response::new()
+        .add_attribute("method", "link_counterpart")
+        .add_attribute("swap_id", &swap_id)
+        .add_attribute("counterpart_swap_id", &counterpart_swap_id)
+        .add_attribute("linked_by", &info.sender);
+
+    if let Some(lock_tx_hash) = lock_tx_hash {
+        let lock_tx_hash = normalize_counterpart_reference(&family, "lock_tx_hash", &lock_tx_hash)?;
+        response = response.add_attribute("lock_tx_hash", &lock_tx_hash);
+        swap.counterpart.lock_tx_hash = Some(lock_tx_hash);
+    }
+    swaps().save(deps.storage, &swap_id, &swap)?;
+
+    Ok(response)
+}
+
+pub fn execute_update_attestors(
+    deps: DepsMut,
+    info: MessageInfo,
+    attestors: Vec<String>,
+    threshold: u32,
+    require_lock_attestation: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let attestors = attestors
+        .iter()
+        .map(|address| deps.api.addr_validate(address))
+        .collect::<StdResult<Vec<_>>>()?;
+    let attestor_config = AttestorConfig {
+        attestors,
+        threshold,
+        require_lock_attestation,
+    };
+    validate_attestor_config(&attestor_config)?;
+    ATTESTOR_CONFIG.save(deps.storage, &attestor_config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_attestors")
+        .add_attribute("attestors", attestor_config.attestors.len().to_string())
+        .add_attribute("threshold", threshold.to_string()))
+}
+
+pub fn execute_attest_counterpart_lock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    swap_id: String,
+) -> Result<Response, ContractError> {
+    let attestors = load_attestor(deps.storage, &info.sender)?;
+    let mut swap = swaps().load(deps.storage, &swap_id)?;
+
+    let attested = attest_lock(
+        &mut swap,
+        &attestors,
+        &info.sender,
+        env.block.time.seconds(),
+    )?;
+    swaps().save(deps.storage, &swap_id, &swap)?;
+
+    Ok(Response::new()
+        .add_events(attested)
+        .add_attribute("method", "attest_counterpart_lock")
+        .add_attribute("swap_id", swap_id)
+        .add_attribute("attestor", info.sender))
+}
+
+pub fn execute_attest_counterpart_refund(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    swap_id: String,
+) -> Result<Response, ContractError> {
+    let attestors = load_attestor(deps.storage, &info.sender)?;
+    let mut swap = swaps().load(deps.storage, &swap_id)?;
+
+    let attested = attest_refund(
+        &mut swap,
+        &attestors,
+        &info.sender,
+        env.block.time.seconds(),
+    )?;
+    swaps().save(deps.storage, &swap_id, &swap)?;
+
+    Ok(Response::new()
+        .add_events(attested)
+        .add_attribute("method", "attest_counterpart_refund")
+        .add_attribute("swap_id", swap_id)
+        .add_attribute("attestor", info.sender))
+}
+
+pub fn execute_submit_counterpart_header(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    chain_id: String,
+    header_rlp: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let chain = COUNTERPART_CHAINS
+        .may_load(deps.storage, &chain_id)?
+        .ok_or_else(|| ContractError::UnknownCounterpartChain {
+            chain_id: chain_id.clone(),
+        })?;
+
+    let trusted = submit_header(
+        deps.storage,
+        &chain,
+        header_rlp.as_slice(),
+        &info.sender,
+        &config.admin,
+        env.block.time.seconds(),
+    )?;
+
+    let mut response = Response::new()
+        .add_attribute("method", "submit_counterpart_header")
+        .add_attribute("chain_id", chain_id)
+        .add_attribute("submitter", info.sender);
+    if let Some(header) = trusted {
+        response = response.add_event(
+            Event::new("counterpart_header_trusted")
+                .add_attribute("chain_id", &header.chain_id)
+                .add_attribute("number", header.number.to_string())
+                .add_attribute("hash", &header.hash),
+        );
+    }
+
+    Ok(response)
+}
+
+// Permissionless, the proof itself is the evidence of the counterpart lock
+pub fn execute_prove_counterpart_lock(
+    deps: DepsMut,
+    env: Env,
+    swap_id: String,
+    block_number: u64,
+    tx_index: u64,
+    log_index: u32,
+    receipt_proof: Vec<Binary>,
+) -> Result<Response, ContractError> {
+    let mut swap = swaps().load(deps.storage, &swap_id)?;
+
+    match swap.state {
+        SwapState::Initiated => {}
+        SwapState::Completed => return Err(ContractError::SwapAlreadyCompleted {}),
+        SwapState::Refunded => return Err(ContractError::SwapAlreadyRefunded {}),
+    }
+    if swap.attestation.status == AttestationStatus::RefundAttested {
+        return Err(ContractError::AttestationConflict { swap_id });
+    }
+
+    let chain = COUNTERPART_CHAINS.load(deps.storage, &swap.counterpart.chain_id)?;
+    let receipt_proof: Vec<Vec<u8>> = receipt_proof.into_iter().map(Vec::from).collect();
+    let counterpart_swap_id = verify_lock_proof(
+        deps.storage,
+        &swap,
+        &chain,
+        block_number,
+        tx_index,
+        log_index,
+        &receipt_proof,
+    )?;
+    link_counterpart_swap_id(deps.storage, &mut swap, counterpart_swap_id.clone())?;
+
+    let current_time = env.block.time.seconds();
+    swap.attestation.status = AttestationStatus::LockAttested;
+    swap.attestation.attested_at = Some(current_time);
+    swap.attestation.proven_at_block = Some(block_number);
+    swaps().save(deps.storage, &swap_id, &swap)?;
+
+    Ok(Response::new()
+        .add_event(
+            Event::new("counterpart_lock_proven")
+                .add_attribute("swap_id", &swap_id)
+                .add_attribute("counterpart_swap_id", &counterpart_swap_id)
+                .add_attribute("block_number", block_number.to_string()),
+        )
+        .add_attribute("method", "prove_counterpart_lock")
+        .add_attribute("swap_id", swap_id))
+}
+
+pub fn execute_register_ica(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut account: IcaAccount,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let chain = COUNTERPART_CHAINS
+        .may_load(deps.storage, &account.chain_id)?
+        .ok_or_else(|| ContractError::UnknownCounterpartChain {
+            chain_id: account.chain_id.clone(),
+        })?;
+    if chain.address_format.family() != ChainFamily::Cosmos {
+        return Err(ContractError::InvalidIcaAccount {
+            reason: "interchain accounts need a Cosmos counterpart chain".to_string(),
+        });
+    }
+    validate_ica_account(&account)?;
+
+    // Registering again reopens the account after its ordered channel closed
+    if let Some(previous) = ICA_ACCOUNTS.may_load(deps.storage, &account.ica_id)? {
+        account.channel_id = previous.channel_id;
+        account.address = previous.address;
+    }
+    ICA_ACCOUNTS.save(deps.storage, &account.ica_id, &account)?;
+
+    Ok(Response::new()
+        .add_message(register_msg(&env, &account, &info.funds))
+        .add_attribute("method", "register_ica")
+        .add_attribute("ica_id", account.ica_id)
+        .add_attribute("connection_id", account.connection_id))
+}
+
+// What the interchain account locks on the counterpart chain
+pub struct RemoteLockTerms {
+    pub amount: Coin,
+    pub safety_deposit: Uint128,
+    pub timelock: u64,
+}
+
+pub fn execute_ica_lock_counterpart(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    swap_id: String,
+    ica_id: String,
+    terms: RemoteLockTerms,
+) -> Result<Response, ContractError> {
+    let RemoteLockTerms {
+        amount,
+        safety_deposit,
+        timelock,
+    } = terms;
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let swap = swaps().load(deps.storage, &swap_id)?;
+    match swap.state {
+        SwapState::Initiated => {}
+        SwapState::Completed => return Err(ContractError::SwapAlreadyCompleted {}),
+        SwapState::Refunded => return Err(ContractError::SwapAlreadyRefunded {}),
+    }
+
+    let account = load_swap_ica(deps.storage, &swap, &ica_id)?;
+    ensure_action_allowed(
+        REMOTE_LEGS.may_load(deps.storage, &swap_id)?.as_ref(),
+        &RemoteAction::Lock,
+    )?;
+
+    // The remote leg takes the other side of the pair the local swap declared
+    let chain = COUNTERPART_CHAINS.load(deps.storage, &account.chain_id)?;
+    validate_timelock_ordering(
+        &chain,
+        &swap.counterpart.role,
+        swap.timelock,
+        Some(timelock),
+    )?;
+    let remote_role = match swap.counterpart.role {
+        SwapRole::InitiatorLeg => SwapRole::ResponderLeg,
+        SwapRole::ResponderLeg => SwapRole::InitiatorLeg,
+    };
+
+    let remote_msg = ExecuteMsg::InitiateSwap {
+        participant: swap.counterpart.recipient.clone(),
+        amount: amount.clone(),
+        hashlock: swap.hashlock.clone(),
+        timelock,
+        counterpart: Some(CounterpartLegMsg {
+            chain_id: env.block.chain_id.clone(),
+            recipient: swap.participant.to_string(),
+            asset: Some(swap.amount.denom.clone()),
+            expected_amount: Some(swap.amount.amount),
+            asset_decimals: None,
+            timelock: Some(swap.timelock),
+            role: Some(remote_role),
+        }),
+        ethereum_recipient: None,
+        ethereum_chain_id: None,
+    };
+    let leg = RemoteLeg {
+        ica_id,
+        remote_swap_id: None,
+        action: RemoteAction::Lock,
+        status: RemoteLegStatus::Pending,
+        error: None,
+    };
+
+    // InitiateSwap escrows the amount and takes the rest as the safety deposit
+    let funds = amount
+        .amount
+        .checked_add(safety_deposit)
+        .map_err(StdError::from)?;
+    let msg = submit_remote(
+        deps.storage,
+        &env,
+        &account,
+        leg,
+        &swap_id,
+        &remote_msg,
+        &[coin(funds.u128(), &amount.denom)],
+    )?;
+
+    Ok(Response::new()
+        .add_submessage(msg)
+        .add_attribute("method", "ica_lock_counterpart")
+        .add_attribute("swap_id", swap_id)
+        .add_attribute("amount", amount.to_string()))
+}
+
+pub fn execute_ica_complete_counterpart(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    swap_id: String,
+    ica_id: String,
+    remote_swap_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let swap = swaps().load(deps.storage, &swap_id)?;
+    let secret = swap
+        .secret
+        .clone()
+        .ok_or_else(|| ContractError::InvalidRemoteLeg {
+            reason: "the local swap has not revealed its secret".to_string(),
+        })?;
+
+    let account = load_swap_ica(deps.storage, &swap, &ica_id)?;
+    ensure_action_allowed(
+        REMOTE_LEGS.may_load(deps.storage, &swap_id)?.as_ref(),
+        &RemoteAction::Complete,
+    )?;
+
+    let remote_msg = ExecuteMsg::CompleteSwap {
+        swap_id: remote_swap_id.clone(),
+        secret,
+        delivery: None,
+        action: None,
+    };
+    let leg = RemoteLeg {
+        ica_id,
+        remote_swap_id: Some(remote_swap_id.clone()),
+        action: RemoteAction::Complete,
+        status: RemoteLegStatus::Pending,
+        error: None,
+    };
+    let msg = submit_remote(
+        deps.storage,
+        &env,
+        &account,
+        leg,
+        &swap_id,
+        &remote_msg,
+        &[],
+    )?;
+
+    Ok(Response::new()
+        .add_submessage(msg)
+        .add_attribute("method", "ica_complete_counterpart")
+        .add_attribute("swap_id", swap_id)
+        .add_attribute("remote_swap_id", remote_swap_id))
+}
+
+pub fn execute_ica_refund_counterpart(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    swap_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let leg = REMOTE_LEGS.may_load(deps.storage, &swap_id)?;
+    ensure_action_allowed(leg.as_ref(), &RemoteAction::Refund)?;
+    let mut leg = leg.ok_or_else(|| ContractError::InvalidRemoteLeg {
+        reason: "no remote leg".to_string(),
+    })?;
+    let remote_swap_id =
+        leg.remote_swap_id
+            .clone()
+            .ok_or_else(|| ContractError::InvalidRemoteLeg {
+                reason: "the remote swap id is unknown".to_string(),
+            })?;
+    let account = ICA_ACCOUNTS.load(deps.storage, &leg.ica_id)?;
+
+    leg.action = RemoteAction::Refund;
+    leg.status = RemoteLegStatus::Pending;
+    leg.error = None;
+    let remote_msg = ExecuteMsg::RefundSwap {
+        swap_id: remote_swap_id.clone(),
+    };
+    let msg = submit_remote(
+        deps.storage,
+        &env,
+        &account,
+        leg,
+        &swap_id,
+        &remote_msg,
+        &[],
+    )?;
+
+    Ok(Response::new()
+        .add_submessage(msg)
+        .add_attribute("method", "ica_refund_counterpart")
+        .add_attribute("swap_id", swap_id)
+        .add_attribute("remote_swap_id", remote_swap_id))
+}
+
+#[cfg_attr(not(feature = "imported"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Swap { swap_id } => to_json_binary(&query_swap(deps, swap_id)?),
+        QueryMsg::SwapsByInitiator {
+            initiator,
+            start_after,
+            limit,
+        } => to_json_binary(&query_swaps_by_initiator(
+            deps,
+            initiator,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::SwapsByParticipant {
+            participant,
+            start_after,
+            limit,
+        } => to_json_binary(&query_swaps_by_participant(
+            deps,
+            participant,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::SwapsByHashlock {
+            hashlock,
+            start_after,
+            limit,
+        } => to_json_binary(&query_swaps_by_hashlock(
+            deps,
+            hashlock,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Swaps {
+            filter,
+            start_after,
+            limit,
+            order,
+        } => to_json_binary(&query_swaps(deps, filter, start_after, limit, order)?),
+        QueryMsg::ExpiringSwaps {
+            before,
+            start_after,
+            limit,
+        } => to_json_binary(&query_expiring_swaps(
+            deps,
+            env,
+            before,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::SwapStats {} => to_json_binary(&query_swap_stats(deps)?),
+        QueryMsg::Limits {} => to_json_binary(&SWAP_LIMITS.load(deps.storage)?),
+        QueryMsg::Allowance { address, denom } => {
+            to_json_binary(&query_allowance(deps, env, address, denom)?)
+        }
+        QueryMsg::OutflowLimits {} => to_json_binary(&OUTFLOW_LIMITS.load(deps.storage)?),
+        QueryMsg::OutflowUsage { denom } => to_json_binary(&query_outflow_usage(deps, env, denom)?),
+        QueryMsg::CircuitBreaker {} => to_json_binary(&query_circuit_breaker(deps, env)?),
+        QueryMsg::Denylist { start_after, limit } => {
+            to_json_binary(&query_denylist(deps, start_after, limit)?)
+        }
+        QueryMsg::Allowlist { start_after, limit } => {
+            to_json_binary(&query_allowlist(deps, start_after, limit)?)
+        }
+        QueryMsg::AccessStatus { address } => to_json_binary(&query_access_status(deps, address)?),
+        QueryMsg::Claimable { address } => to_json_binary(&query_claimable(deps, address)?),
+        QueryMsg::CounterpartChain { chain_id } => {
+            to_json_binary(&COUNTERPART_CHAINS.load(deps.storage, &chain_id)?)
+        }
+        QueryMsg::CounterpartChains { start_after, limit } => {
+            to_json_binary(&query_counterpart_chains(deps, start_after, limit)?)
+        }
+        QueryMsg::SwapByCounterpartId {
+            chain_id,
+            counterpart_swap_id,
+        } => to_json_binary(&query_swap_by_counterpart_id(
+            deps,
+            chain_id,
+            counterpart_swap_id,
+        )?),
+        QueryMsg::Relayers { start_after, limit } => {
+            to_json_binary(&query_relayers(deps, start_after, limit)?)
+        }
+        QueryMsg::Hooks { start_after, limit } => {
+            to_json_binary(&query_hooks(deps, start_after, limit)?)
+        }
+        QueryMsg::ActionContracts { start_after, limit } => {
+            to_json_binary(&query_action_contracts(deps, start_after, limit)?)
+        }
+        QueryMsg::SwapHooks { swap_id } => to_json_binary(&AddressesResponse {
+            addresses: swap_hooks(deps.storage, &swap_id)?,
+        }),
+        QueryMsg::Attestors {} => to_json_binary(&ATTESTOR_CONFIG.load(deps.storage)?),
+        QueryMsg::IbcChannels {} => to_json_binary(&query_ibc_channels(deps)?),
+        QueryMsg::IcaAccount { ica_id } => {
+            to_json_binary(&ICA_ACCOUNTS.load(deps.storage, &ica_id)?)
+        }
+        QueryMsg::RemoteLeg { swap_id } => {
+            to_json_binary(&REMOTE_LEGS.load(deps.storage, &swap_id)?)
+        }
+        QueryMsg::RevealedSecret { hashlock } => {
+            to_json_binary(&REVEALED_SECRETS.load(deps.storage, &hashlock)?)
+        }
+        QueryMsg::IbcTransfer {
+            channel_id,
+            sequence,
+        } => to_json_binary(&IBC_TRANSFERS.load(deps.storage, (&channel_id, sequence))?),
+        QueryMsg::RemoteLock {
+            channel_id,
+            hashlock,
+        } => to_json_binary(&REMOTE_LOCKS.load(deps.storage, (&channel_id, &hashlock))?),
+        QueryMsg::CounterpartHeader { chain_id, number } => {
+            to_json_binary(&COUNTERPART_HEADERS.load(deps.storage, (&chain_id, number))?)
+        }
+        QueryMsg::ExchangeRate {
+            swap_id,
+            local_decimals,
+        } => to_json_binary(&query_exchange_rate(deps, swap_id, local_decimals)?),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        safety_deposit_ceiling: safety_deposit_ceiling(&config),
+        admin: config.admin,
+        minimum_safety_deposit: config.minimum_safety_deposit,
+        min_timelock_duration: config.min_timelock_duration,
+        max_timelock_duration: config.max_timelock_duration,
+        paused: config.paused,
+    })
+}
+
+pub fn query_swap(deps: Deps, swap_id: String) -> StdResult<SwapResponse> {
+    Ok(swaps().load(deps.storage, &swap_id)?.into())
+}
+
+pub fn query_swaps_by_initiator(
+    deps: Deps,
+    initiator: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SwapsResponse> {
+    let initiator = deps.api.addr_validate(&initiator)?;
+    let prefix = swaps().idx.initiator.prefix(initiator);
+    swaps_page(deps.storage, prefix, start_after, limit)
+}
+
+pub fn query_swaps_by_participant(
+    deps: Deps,
+    participant: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SwapsResponse> {
+    let participant = deps.api.addr_validate(&participant)?;
+    let prefix = swaps().idx.participant.prefix(participant);
+    swaps_page(deps.storage, prefix, start_after, limit)
 }
 
 pub fn query_swaps_by_hashlock(
@@ -630,35 +1937,147 @@ pub fn query_swaps_by_hashlock(
     hashlock: String,
     start_after: Option<String>,
     limit: Option<u32>,
+) -> StdResult<SwapsResponse> {
+    let prefix = swaps().idx.hashlock.prefix(hashlock);
+    swaps_page(deps.storage, prefix, start_after, limit)
+}
+
+// One page of swaps under an index prefix, ordered by swap id
+fn swaps_page(
+    storage: &dyn Storage,
+    prefix: Prefix<String, AtomicSwap, String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
 ) -> StdResult<SwapsResponse> {
     let limit = limit.unwrap_or(10).min(30) as usize;
-    let start = start_after.as_deref();
 
-    let swaps: StdResult<Vec<_>> = SWAPS_BY_HASHLOCK
-        .prefix(&hashlock)
-        .range(deps.storage, start.map(Bound::exclusive), None, Order::Ascending)
+    let swaps = prefix
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, swap)| SwapResponse::from(swap)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let next_start_after = match swaps.last() {
+        Some(last) if swaps.len() == limit => Some(last.swap_id.clone()),
+        _ => None,
+    };
+    Ok(SwapsResponse {
+        swaps,
+        next_start_after,
+    })
+}
+
+// Lists swaps by swap id. A state filter walks the state index, the other
+// filters are checked per swap. A page stops after MAX_SWAPS_SCANNED swaps, so
+// sparse filters can return fewer swaps than the limit along with a cursor.
+pub fn query_swaps(
+    deps: Deps,
+    filter: Option<SwapFilter>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order: Option<SortOrder>,
+) -> StdResult<SwapsResponse> {
+    let filter = filter.unwrap_or_default();
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let order = match order.unwrap_or(SortOrder::Ascending) {
+        SortOrder::Ascending => Order::Ascending,
+        SortOrder::Descending => Order::Descending,
+    };
+
+    let swaps_map = swaps();
+    let candidates: Box<dyn Iterator<Item = StdResult<(String, AtomicSwap)>>> = match &filter.state
+    {
+        Some(state) => {
+            let start = start_after.clone().map(Bound::exclusive);
+            let (min, max) = match order {
+                Order::Ascending => (start, None),
+                Order::Descending => (None, start),
+            };
+            swaps_map
+                .idx
+                .state
+                .prefix(state_key(state))
+                .range(deps.storage, min, max, order)
+        }
+        None => {
+            let start = start_after.as_deref().map(Bound::exclusive);
+            let (min, max) = match order {
+                Order::Ascending => (start, None),
+                Order::Descending => (None, start),
+            };
+            swaps_map.range(deps.storage, min, max, order)
+        }
+    };
+
+    let mut swaps = vec![];
+    let mut last_scanned = None;
+    let mut exhausted = true;
+    for (scanned, item) in candidates.enumerate() {
+        if swaps.len() == limit || scanned == MAX_SWAPS_SCANNED {
+            exhausted = false;
+            break;
+        }
+        let (swap_id, swap) = item?;
+        if matches_filter(&swap, &filter) {
+            swaps.push(SwapResponse::from(swap));
+        }
+        last_scanned = Some(swap_id);
+    }
+
+    Ok(SwapsResponse {
+        swaps,
+        next_start_after: if exhausted { None } else { last_scanned },
+    })
+}
+
+pub fn query_expiring_swaps(
+    deps: Deps,
+    env: Env,
+    before: u64,
+    start_after: Option<ExpiryCursor>,
+    limit: Option<u32>,
+) -> StdResult<ExpiringSwapsResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let current_time = env.block.time.seconds();
+
+    // Index keys run (timelock, swap id), an empty swap id sorts before any swap at `before`
+    let start = start_after
+        .as_ref()
+        .map(|cursor| Bound::exclusive((cursor.timelock, cursor.swap_id.as_str())));
+    let end = Bound::exclusive((before, ""));
+
+    let swaps = swaps()
+        .idx
+        .expiry
+        .open_swaps()
+        .keys(deps.storage, start, Some(end), Order::Ascending)
         .take(limit)
         .map(|item| {
-            let (swap_id, _) = item?;
-            let swap = SWAPS.load(deps.storage, &swap_id)?;
-            Ok(SwapResponse {
-                swap_id: swap.swap_id,
-                initiator: swap.initiator,
-                participant: swap.participant,
-                amount: swap.amount,
-                hashlock: swap.hashlock,
-                timelock: swap.timelock,
-                state: swap.state,
-                secret: swap.secret,
-                ethereum_recipient: swap.ethereum_recipient,
-                ethereum_chain_id: swap.ethereum_chain_id,
-                created_at: swap.created_at,
-                completed_at: swap.completed_at,
+            let (_, swap_id) = item?;
+            let swap = swaps().load(deps.storage, &swap_id)?;
+            Ok(ExpiringSwap {
+                expired: current_time >= swap.timelock,
+                swap: swap.into(),
             })
         })
-        .collect();
-
-    Ok(SwapsResponse { swaps: swaps? })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let next_start_after = match swaps.last() {
+        Some(last) if swaps.len() == limit => Some(ExpiryCursor {
+            timelock: last.swap.timelock,
+            swap_id: last.swap.swap_id.clone(),
+        }),
+        _ => None,
+    };
+    Ok(ExpiringSwapsResponse {
+        swaps,
+        next_start_after,
+    })
 }
 
 pub fn query_swap_stats(deps: Deps) -> StdResult<SwapStatsResponse> {
@@ -674,13 +2093,684 @@ pub fn query_swap_stats(deps: Deps) -> StdResult<SwapStatsResponse> {
     })
 }
 
+pub fn query_allowance(
+    deps: Deps,
+    env: Env,
+    address: String,
+    denom: String,
+) -> StdResult<AllowanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let limits = SWAP_LIMITS.load(deps.storage)?;
+    let current_time = env.block.time.seconds();
+
+    let active_swaps_as_initiator = ACTIVE_SWAPS_BY_INITIATOR
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let active_swaps_as_participant = ACTIVE_SWAPS_BY_PARTICIPANT
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let window = current_window(deps.storage, &limits, &address, &denom, current_time)?;
+    let volume_in_window = window_volume(&window);
+
+    Ok(AllowanceResponse {
+        remaining_swaps_as_initiator: limits
+            .max_active_swaps_per_initiator
+            .map(|limit| limit.saturating_sub(active_swaps_as_initiator)),
+        remaining_swaps_as_participant: limits
+            .max_active_swaps_per_participant
+            .map(|limit| limit.saturating_sub(active_swaps_as_participant)),
+        remaining_volume: find_amount(&limits.max_volume_per_window, &denom)
+            .map(|cap| cap.saturating_sub(volume_in_window)),
+        volume_frees_at: volume_frees_at(&limits, &window),
+        volume_in_window,
+        min_swap_amount: find_amount(&limits.min_swap_amount, &denom),
+        address,
+        denom,
+        active_swaps_as_initiator,
+        active_swaps_as_participant,
+    })
+}
+
+pub fn query_outflow_usage(deps: Deps, env: Env, denom: String) -> StdResult<OutflowUsageResponse> {
+    let limits = OUTFLOW_LIMITS.load(deps.storage)?;
+    let usage = current_epoch(deps.storage, &limits, &denom, env.block.time.seconds())?;
+    let cap = find_amount(&limits.caps, &denom);
+
+    Ok(OutflowUsageResponse {
+        epoch: usage.epoch,
+        epoch_start: usage.epoch * limits.epoch_seconds,
+        epoch_end: (usage.epoch + 1) * limits.epoch_seconds,
+        released: usage.released,
+        remaining: cap.map(|cap| cap.saturating_sub(usage.released)),
+        cap,
+        denom,
+    })
+}
+
+pub fn query_circuit_breaker(deps: Deps, env: Env) -> StdResult<CircuitBreakerResponse> {
+    let config = CIRCUIT_BREAKER_CONFIG.load(deps.storage)?;
+    let state = current_state(deps.storage, &config, env.block.time.seconds())?;
+
+    Ok(CircuitBreakerResponse {
+        config,
+        tripped: state.tripped,
+        tripped_at: state.tripped_at,
+        reason: state.reason,
+        emergency_refunds: state.emergency_refunds,
+    })
+}
+
+pub fn query_denylist(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AddressesResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after
+        .map(|s| deps.api.addr_validate(&s))
+        .transpose()?;
+
+    let addresses = DENYLIST
+        .keys(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AddressesResponse { addresses })
+}
+
+pub fn query_allowlist(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AddressesResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after
+        .map(|s| deps.api.addr_validate(&s))
+        .transpose()?;
+
+    let addresses = ALLOWLIST
+        .keys(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AddressesResponse { addresses })
+}
+
+pub fn query_access_status(deps: Deps, address: String) -> StdResult<AccessStatusResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let access = ACCESS_CONFIG.may_load(deps.storage)?.unwrap_or_default();
+
+    Ok(AccessStatusResponse {
+        denied: DENYLIST.has(deps.storage, &address),
+        allowlisted: ALLOWLIST.has(deps.storage, &address),
+        allowlist_only: access.allowlist_only,
+        address,
+    })
+}
+
+pub fn query_claimable(deps: Deps, address: String) -> StdResult<ClaimableResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let balances = claimable_balances(deps.storage, &address)?;
+
+    Ok(ClaimableResponse { address, balances })
+}
+
+pub fn query_counterpart_chains(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CounterpartChainsResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after.as_deref();
+
+    let chains = COUNTERPART_CHAINS
+        .range(
+            deps.storage,
+            start.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, chain)| chain))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(CounterpartChainsResponse { chains })
+}
+
+pub fn query_swap_by_counterpart_id(
+    deps: Deps,
+    chain_id: String,
+    counterpart_swap_id: String,
+) -> StdResult<SwapResponse> {
+    // EVM links are stored lowercased, so accept checksummed input too
+    let counterpart_swap_id = match counterpart_swap_id.strip_prefix("0x") {
+        Some(_) => counterpart_swap_id.to_ascii_lowercase(),
+        None => counterpart_swap_id,
+    };
+    let swap_id =
+        SWAPS_BY_COUNTERPART_ID.load(deps.storage, (&chain_id, counterpart_swap_id.trim()))?;
+    query_swap(deps, swap_id)
+}
+
+pub fn query_relayers(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AddressesResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after
+        .map(|s| deps.api.addr_validate(&s))
+        .transpose()?;
+
+    let addresses = RELAYERS
+        .keys(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AddressesResponse { addresses })
+}
+
+pub fn query_hooks(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AddressesResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after
+        .map(|s| deps.api.addr_validate(&s))
+        .transpose()?;
+
+    let addresses = HOOKS
+        .keys(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AddressesResponse { addresses })
+}
+
+pub fn query_action_contracts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AddressesResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after
+        .map(|s| deps.api.addr_validate(&s))
+        .transpose()?;
+
+    let addresses = ACTION_CONTRACTS
+        .keys(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AddressesResponse { addresses })
+}
+
+pub fn query_ibc_channels(deps: Deps) -> StdResult<IbcChannelsResponse> {
+    let channels = IBC_CHANNELS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, channel)| channel))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(IbcChannelsResponse { channels })
+}
+
+pub fn query_exchange_rate(
+    deps: Deps,
+    swap_id: String,
+    local_decimals: Option<u8>,
+) -> StdResult<ExchangeRateResponse> {
+    let swap = swaps().load(deps.storage, &swap_id)?;
+    let expected_amount = swap.counterpart.expected_amount.ok_or_else(|| {
+        StdError::generic_err("Swap does not record an expected counterpart amount")
+    })?;
+
+    let rate = Decimal::checked_from_ratio(expected_amount, swap.amount.amount)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    // expected / 10^counterpart_decimals over locked / 10^local_decimals
+    let normalized_rate = match (swap.counterpart.asset_decimals, local_decimals) {
+        (Some(counterpart_decimals), Some(local_decimals)) => {
+            let numerator = expected_amount.checked_mul(pow10(local_decimals)?)?;
+            let denominator = swap
+                .amount
+                .amount
+                .checked_mul(pow10(counterpart_decimals)?)?;
+            Some(
+                Decimal::checked_from_ratio(numerator, denominator)
+                    .map_err(|e| StdError::generic_err(e.to_string()))?,
+            )
+        }
+        _ => None,
+    };
+
+    Ok(ExchangeRateResponse {
+        swap_id: swap.swap_id,
+        locked: swap.amount,
+        counterpart_asset: swap.counterpart.asset,
+        expected_amount,
+        rate,
+        normalized_rate,
+    })
+}
+
+#[cfg_attr(not(feature = "imported"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        IBC_TRANSFER_REPLY_ID => match msg.result.into_result() {
+            Ok(response) => {
+                let transfer = record_transfer_sequence(deps.storage, response)?;
+
+                Ok(Response::new()
+                    .add_attribute("method", "ibc_transfer_sent")
+                    .add_attribute("swap_id", transfer.swap_id)
+                    .add_attribute("channel_id", transfer.channel_id))
+            }
+            Err(error) => {
+                let transfer = fail_staged_transfer(deps.storage)?;
+
+                Ok(Response::new()
+                    .add_attribute("method", "ibc_transfer_failed")
+                    .add_attribute("swap_id", transfer.swap_id)
+                    .add_attribute("channel_id", transfer.channel_id)
+                    .add_attribute("error", error))
+            }
+        },
+        ICA_SUBMIT_REPLY_ID => {
+            let response = msg.result.into_result().map_err(StdError::generic_err)?;
+            let tx = record_submission(deps.storage, response)?;
+
+            Ok(Response::new()
+                .add_attribute("method", "ica_tx_submitted")
+                .add_attribute("swap_id", tx.swap_id))
+        }
+        // Only failed hooks reply, their state changes are already reverted
+        HOOK_REPLY_ID => Ok(Response::new()
+            .add_attribute("method", "hook_failed")
+            .add_attribute("error", msg.result.into_result().err().unwrap_or_default())),
+        // The funds a failed payout carried are back in the contract
+        id if id >= PAYOUT_REPLY_ID_BASE => {
+            let failed = take_staged_payout(deps.storage, id)?;
+            // Delivered payouts only need unstaging
+            let error = match msg.result.into_result() {
+                Ok(_) => return Ok(Response::new()),
+                Err(error) => error,
+            };
+            let response = Response::new()
+                .add_attribute("swap_id", &failed.swap_id)
+                .add_attribute("recipient", &failed.recipient)
+                .add_attribute("amount", failed.amount.to_string())
+                .add_attribute("error", error);
+
+            match failed.route {
+                PayoutRoute::Action => {
+                    let fallback = payout(
+                        deps.storage,
+                        &failed.swap_id,
+                        &failed.recipient,
+                        failed.amount,
+                    )?;
+                    Ok(response
+                        .add_submessages(fallback)
+                        .add_attribute("method", "completion_action_failed"))
+                }
+                PayoutRoute::Bank => {
+                    credit_claimable(deps.storage, &failed.recipient, &failed.amount)?;
+                    Ok(response.add_attribute("method", "payout_failed"))
+                }
+            }
+        }
+        id => Err(StdError::generic_err(format!("Unknown reply id: {}", id)).into()),
+    }
+}
+
+#[cfg_attr(not(feature = "imported"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck {
+            channel,
+            sequence,
+            success,
+            ..
+        }) => sudo_transfer_settled(deps, channel, sequence, success),
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout { channel, sequence }) => {
+            sudo_transfer_settled(deps, channel, sequence, false)
+        }
+        SudoMsg::Response { request, data } => match transfer_packet(&request) {
+            Some((channel, sequence)) => sudo_transfer_settled(deps, channel, sequence, true),
+            None => sudo_ica_tx_settled(deps, request, Ok(&data)),
+        },
+        SudoMsg::Error { request, details } => match transfer_packet(&request) {
+            Some((channel, sequence)) => sudo_transfer_settled(deps, channel, sequence, false),
+            None => sudo_ica_tx_settled(deps, request, Err(details)),
+        },
+        SudoMsg::Timeout { request } => match transfer_packet(&request) {
+            Some((channel, sequence)) => sudo_transfer_settled(deps, channel, sequence, false),
+            None => sudo_ica_tx_settled(deps, request, Err("timeout".to_string())),
+        },
+        SudoMsg::OpenAck {
+            port_id,
+            channel_id,
+            counterparty_version,
+            ..
+        } => {
+            let account = open_account(deps.storage, &port_id, &channel_id, &counterparty_version)?;
+
+            Ok(Response::new()
+                .add_attribute("method", "ica_opened")
+                .add_attribute("ica_id", account.ica_id)
+                .add_attribute("channel_id", channel_id))
+        }
+    }
+}
+
+fn sudo_transfer_settled(
+    deps: DepsMut,
+    channel: String,
+    sequence: u64,
+    success: bool,
+) -> Result<Response, ContractError> {
+    let mut response = Response::new()
+        .add_attribute("method", "ibc_transfer_settled")
+        .add_attribute("channel_id", &channel)
+        .add_attribute("sequence", sequence.to_string())
+        .add_attribute("success", success.to_string());
+    if let Some(transfer) = settle_transfer(deps.storage, &channel, sequence, success)? {
+        response = response.add_attribute("swap_id", transfer.swap_id);
+    }
+
+    Ok(response)
+}
+
+fn sudo_ica_tx_settled(
+    deps: DepsMut,
+    request: RequestPacket,
+    result: Result<&Binary, String>,
+) -> Result<Response, ContractError> {
+    let mut response = Response::new()
+        .add_attribute("method", "ica_tx_settled")
+        .add_attribute("success", result.is_ok().to_string());
+    if let Some((tx, leg)) = settle_ica_tx(deps.storage, &request, result)? {
+        response = response
+            .add_attribute("swap_id", tx.swap_id)
+            .add_attribute("status", format!("{:?}", leg.status));
+    }
+
+    Ok(response)
+}
+
 #[cfg_attr(not(feature = "imported"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let version = get_contract_version(deps.storage)?;
+    if version.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigration {
+            contract: version.contract,
+        });
+    }
+
+    // Refuse to carry an unusable config over into the new code
+    let config = CONFIG.load(deps.storage)?;
+    validate_config(&config)?;
+
+    // Deployments from before rate limiting start out unlimited
+    if SWAP_LIMITS.may_load(deps.storage)?.is_none() {
+        SWAP_LIMITS.save(deps.storage, &default_limits())?;
+    }
+    if OUTFLOW_LIMITS.may_load(deps.storage)?.is_none() {
+        OUTFLOW_LIMITS.save(deps.storage, &default_outflow_limits())?;
+    }
+    if CIRCUIT_BREAKER_CONFIG.may_load(deps.storage)?.is_none() {
+        CIRCUIT_BREAKER_CONFIG.save(deps.storage, &default_circuit_breaker_config())?;
+        CIRCUIT_BREAKER.save(deps.storage, &initial_state())?;
+    }
+    for chain in msg.counterpart_chains.unwrap_or_default() {
+        validate_counterpart_chain(&chain)?;
+        COUNTERPART_CHAINS.save(deps.storage, &chain.chain_id, &chain)?;
+    }
+
+    if ATTESTOR_CONFIG.may_load(deps.storage)?.is_none() {
+        ATTESTOR_CONFIG.save(deps.storage, &default_attestor_config())?;
+    }
+
+    // Move swaps stored with the Ethereum-only fields over to counterpart legs
+    // and index them, all before any of them is served again
+    let migrated = migrate_legacy_swaps(deps.storage)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", version.version)
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_attribute("migrated_swaps", migrated.migrated.to_string())
+        .add_attribute("indexed_swaps", migrated.indexed.to_string()))
 }
 
 // Helper functions
 
+// Checks shared by every completion path, before the secret is verified
+pub(crate) fn ensure_completable(
+    storage: &dyn Storage,
+    swap: &AtomicSwap,
+    current_time: u64,
+) -> Result<(), ContractError> {
+    // Check if swap is in initiated state
+    match swap.state {
+        SwapState::Initiated => {}
+        SwapState::Completed => return Err(ContractError::SwapAlreadyCompleted {}),
+        SwapState::Refunded => return Err(ContractError::SwapAlreadyRefunded {}),
+    }
+
+    // Optionally require relayer evidence that the counterpart leg is locked
+    let attestors = ATTESTOR_CONFIG.load(storage)?;
+    if attestors.require_lock_attestation
+        && swap.attestation.status != AttestationStatus::LockAttested
+    {
+        return Err(ContractError::CounterpartLockNotAttested {
+            swap_id: swap.swap_id.clone(),
+        });
+    }
+
+    // Check if swap has not expired
+    if current_time >= swap.timelock {
+        return Err(ContractError::SwapExpired {
+            current_time,
+            expiry: swap.timelock,
+        });
+    }
+
+    Ok(())
+}
+
+// Marks the swap completed and pays it out, the secret must already be verified
+pub(crate) fn settle_completion(
+    storage: &mut dyn Storage,
+    env: &Env,
+    breaker: &CircuitBreakerConfig,
+    swap: &mut AtomicSwap,
+    secret: String,
+    delivery: Option<IbcDelivery>,
+    action: Option<CompletionAction>,
+) -> Result<(Vec<SubMsg>, Vec<Event>), ContractError> {
+    let current_time = env.block.time.seconds();
+
+    // Update swap state
+    swap.state = SwapState::Completed;
+    swap.secret = Some(secret.clone());
+    swap.completed_at = Some(current_time);
+    swaps().save(storage, &swap.swap_id, swap)?;
+
+    // Later swaps on the same hashlock reveal nothing new
+    if !REVEALED_SECRETS.has(storage, &swap.hashlock) {
+        let revealed = RevealedSecret {
+            secret,
+            swap_id: swap.swap_id.clone(),
+            block_height: env.block.height,
+            revealed_at: current_time,
+        };
+        REVEALED_SECRETS.save(storage, &swap.hashlock, &revealed)?;
+    }
+    release_active(storage, &swap.initiator, &swap.participant)?;
+
+    // Enforce the global outflow cap
+    let outflow_limits = OUTFLOW_LIMITS.load(storage)?;
+    record_outflow(
+        storage,
+        &outflow_limits,
+        &[released_from_escrow(swap)],
+        current_time,
+    )?;
+    let mut events = vec![SwapEvent::completed(swap).to_event()];
+    // Funds a party sends to itself are no signal of an anomaly, and counting
+    // them would let anyone trip the breaker with their own capital
+    if swap.initiator != swap.participant {
+        events.extend(record_completion(
+            storage,
+            breaker,
+            &swap.amount,
+            current_time,
+        )?);
+    }
+
+    // Update stats
+    let mut stats = SWAP_STATS.load(storage)?;
+    stats.completed_swaps += 1;
+    SWAP_STATS.save(storage, &stats)?;
+
+    // Prepare messages
+    let mut messages = vec![];
+
+    // A route given at completion replaces the one set at initiation
+    let (delivery, action) = if delivery.is_some() || action.is_some() {
+        (delivery, action)
+    } else {
+        (swap.delivery.clone(), swap.action.clone())
+    };
+
+    // Transfer tokens to participant, over IBC or through a contract call when
+    // one is set. Denied participants still have their tokens held back by payout.
+    let denied = is_denied(storage, &swap.participant);
+    match (delivery, action) {
+        (Some(delivery), _) if !denied => messages.push(deliver(
+            storage,
+            env,
+            &delivery,
+            &swap.swap_id,
+            &swap.participant,
+            swap.amount.clone(),
+        )?),
+        (_, Some(action)) if !denied && is_allowed_action(storage, &action) => {
+            messages.push(run_action(
+                storage,
+                &action,
+                &swap.swap_id,
+                &swap.participant,
+                swap.amount.clone(),
+            )?)
+        }
+        _ => messages.extend(payout(
+            storage,
+            &swap.swap_id,
+            &swap.participant,
+            swap.amount.clone(),
+        )?),
+    }
+
+    // Return safety deposit to initiator
+    messages.extend(payout(
+        storage,
+        &swap.swap_id,
+        &swap.initiator,
+        coin(swap.safety_deposit.u128(), &swap.amount.denom),
+    )?);
+
+    messages.extend(hook_messages(
+        storage,
+        &swap.swap_id,
+        SwapHookMsg::Completed {
+            swap_id: swap.swap_id.clone(),
+            participant: swap.participant.clone(),
+            amount: swap.amount.clone(),
+            secret: swap.secret.clone().unwrap_or_default(),
+        },
+    )?);
+
+    Ok((messages, events))
+}
+
+fn matches_filter(swap: &AtomicSwap, filter: &SwapFilter) -> bool {
+    filter
+        .state
+        .as_ref()
+        .is_none_or(|state| swap.state == *state)
+        && filter
+            .denom
+            .as_ref()
+            .is_none_or(|denom| swap.amount.denom == *denom)
+        && filter
+            .counterpart_chain_id
+            .as_ref()
+            .is_none_or(|chain_id| swap.counterpart.chain_id == *chain_id)
+        && filter
+            .created_after
+            .is_none_or(|time| swap.created_at >= time)
+        && filter
+            .created_before
+            .is_none_or(|time| swap.created_at <= time)
+        && filter
+            .expires_after
+            .is_none_or(|time| swap.timelock >= time)
+        && filter
+            .expires_before
+            .is_none_or(|time| swap.timelock <= time)
+        && filter
+            .min_amount
+            .is_none_or(|amount| swap.amount.amount >= amount)
+}
+
+// Loads an interchain account living on the swap's counterpart chain
+fn load_swap_ica(
+    storage: &dyn Storage,
+    swap: &AtomicSwap,
+    ica_id: &str,
+) -> Result<IcaAccount, ContractError> {
+    let account = ICA_ACCOUNTS.load(storage, ica_id)?;
+    if account.chain_id != swap.counterpart.chain_id {
+        return Err(ContractError::InvalidRemoteLeg {
+            reason: format!(
+                "interchain account {} is not on chain {}",
+                ica_id, swap.counterpart.chain_id
+            ),
+        });
+    }
+    Ok(account)
+}
+
 fn generate_swap_id(
     initiator: &Addr,
     participant: &Addr,
@@ -697,11 +2787,25 @@ fn generate_swap_id(
     hex::encode(hasher.finalize())
 }
 
-fn verify_secret(hashlock: &str, secret: &str) -> Result<bool, ContractError> {
+pub(crate) fn verify_secret(hashlock: &str, secret: &str) -> Result<bool, ContractError> {
     let hash = hex::encode(Sha256::digest(secret.as_bytes()));
     Ok(hash == hashlock.to_lowercase())
 }
 
+// Everything a swap pays out of escrow: the locked amount plus the safety deposit
+fn released_from_escrow(swap: &AtomicSwap) -> Coin {
+    Coin {
+        denom: swap.amount.denom.clone(),
+        amount: swap.amount.amount + swap.safety_deposit,
+    }
+}
+
+fn pow10(exponent: u8) -> StdResult<Uint128> {
+    Uint128::new(10)
+        .checked_pow(exponent.into())
+        .map_err(StdError::from)
+}
+
 fn add_to_volume(volume: &mut Vec<Coin>, amount: &Coin) {
     if let Some(existing) = volume.iter_mut().find(|coin| coin.denom == amount.denom) {
         existing.amount += amount.amount;
@@ -709,4 +2813,3 @@ fn add_to_volume(volume: &mut Vec<Coin>, amount: &Coin) {
         volume.push(amount.clone());
     }
 }
-
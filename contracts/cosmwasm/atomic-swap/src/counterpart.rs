@@ -0,0 +1,337 @@
+use cosmwasm_std::Storage;
+
+use crate::error::ContractError;
+use crate::msg::CounterpartLegMsg;
+use crate::recipient::is_valid_evm_address;
+use crate::state::{
+    AtomicSwap, ChainFamily, CounterpartChain, CounterpartLeg, SwapRole, COUNTERPART_CHAINS,
+    SWAPS_BY_COUNTERPART_ID,
+};
+
+// Largest decimals a Uint128 amount can meaningfully carry
+pub const MAX_ASSET_DECIMALS: u8 = 38;
+
+pub fn validate_counterpart_chain(chain: &CounterpartChain) -> Result<(), ContractError> {
+    if chain.chain_id.trim().is_empty() || chain.chain_id.trim() != chain.chain_id {
+        return Err(ContractError::InvalidCounterpartChain {
+            reason: "chain_id must be non-empty without surrounding whitespace".to_string(),
+        });
+    }
+    if chain.name.trim().is_empty() {
+        return Err(ContractError::InvalidCounterpartChain {
+            reason: "name must be non-empty".to_string(),
+        });
+    }
+    if let Some(htlc_contract) = &chain.htlc_contract {
+        if chain.address_format.family() != ChainFamily::Evm || !is_valid_evm_address(htlc_contract)
+        {
+            return Err(ContractError::InvalidCounterpartChain {
+                reason: "htlc_contract must be an EVM address on an EVM chain".to_string(),
+            });
+        }
+    }
+    if let Some(ibc_channel) = &chain.ibc_channel {
+        if chain.address_format.family() != ChainFamily::Cosmos || ibc_channel.trim().is_empty() {
+            return Err(ContractError::InvalidCounterpartChain {
+                reason: "ibc_channel must be a channel id on a Cosmos chain".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+// Loads a registered chain that is open for new swaps
+pub fn load_enabled_chain(
+    storage: &dyn Storage,
+    chain_id: &str,
+) -> Result<CounterpartChain, ContractError> {
+    let chain = COUNTERPART_CHAINS
+        .may_load(storage, chain_id)?
+        .ok_or_else(|| ContractError::UnknownCounterpartChain {
+            chain_id: chain_id.to_string(),
+        })?;
+
+    if !chain.enabled {
+        return Err(ContractError::CounterpartChainDisabled {
+            chain_id: chain_id.to_string(),
+        });
+    }
+
+    Ok(chain)
+}
+
+// Picks the counterpart leg from an InitiateSwap, falling back to the legacy Ethereum fields
+pub fn resolve_counterpart_msg(
+    counterpart: Option<CounterpartLegMsg>,
+    ethereum_recipient: Option<String>,
+    ethereum_chain_id: Option<String>,
+) -> Result<CounterpartLegMsg, ContractError> {
+    match (counterpart, ethereum_recipient, ethereum_chain_id) {
+        (Some(counterpart), None, None) => Ok(counterpart),
+        (None, Some(recipient), Some(chain_id)) => Ok(CounterpartLegMsg {
+            chain_id,
+            recipient,
+            asset: None,
+            expected_amount: None,
+            asset_decimals: None,
+            timelock: None,
+            role: None,
+        }),
+        (Some(_), _, _) => Err(ContractError::InvalidCounterpartLeg {
+            reason: "counterpart cannot be combined with ethereum_recipient or ethereum_chain_id"
+                .to_string(),
+        }),
+        _ => Err(ContractError::InvalidCounterpartLeg {
+            reason:
+                "either counterpart or both ethereum_recipient and ethereum_chain_id are required"
+                    .to_string(),
+        }),
+    }
+}
+
+pub fn build_counterpart_leg(
+    chain: &CounterpartChain,
+    msg: CounterpartLegMsg,
+    timelock: u64,
+) -> Result<CounterpartLeg, ContractError> {
+    if msg.expected_amount.is_some_and(|amount| amount.is_zero()) {
+        return Err(ContractError::InvalidCounterpartLeg {
+            reason: "expected_amount must be greater than 0".to_string(),
+        });
+    }
+    if msg
+        .asset_decimals
+        .is_some_and(|decimals| decimals > MAX_ASSET_DECIMALS)
+    {
+        return Err(ContractError::InvalidCounterpartLeg {
+            reason: format!("asset_decimals cannot exceed {}", MAX_ASSET_DECIMALS),
+        });
+    }
+    if msg
+        .asset
+        .as_deref()
+        .is_some_and(|asset| asset.trim().is_empty())
+    {
+        return Err(ContractError::InvalidCounterpartLeg {
+            reason: "asset must be non-empty".to_string(),
+        });
+    }
+
+    let role = msg.role.unwrap_or(SwapRole::InitiatorLeg);
+    validate_timelock_ordering(chain, &role, timelock, msg.timelock)?;
+
+    Ok(CounterpartLeg {
+        chain_family: chain.address_format.family(),
+        chain_id: chain.chain_id.clone(),
+        recipient: msg.recipient,
+        asset: msg.asset,
+        expected_amount: msg.expected_amount,
+        asset_decimals: msg.asset_decimals,
+        timelock: msg.timelock,
+        role,
+        counterpart_swap_id: None,
+        lock_tx_hash: None,
+    })
+}
+
+// The initiator leg must expire at least the chain's margin after the responder
+// leg, so the responder always has time to claim with the revealed secret
+pub fn validate_timelock_ordering(
+    chain: &CounterpartChain,
+    role: &SwapRole,
+    timelock: u64,
+    counterpart_timelock: Option<u64>,
+) -> Result<(), ContractError> {
+    let counterpart_timelock = match (role, counterpart_timelock) {
+        (_, Some(counterpart_timelock)) => counterpart_timelock,
+        (SwapRole::InitiatorLeg, None) => return Ok(()),
+        // A responder cannot judge its exposure without the initiator's expiry
+        (SwapRole::ResponderLeg, None) => {
+            return Err(ContractError::InvalidCounterpartLeg {
+                reason: "responder legs must declare the counterpart timelock".to_string(),
+            })
+        }
+    };
+
+    let (initiator_expiry, responder_expiry) = match role {
+        SwapRole::InitiatorLeg => (timelock, counterpart_timelock),
+        SwapRole::ResponderLeg => (counterpart_timelock, timelock),
+    };
+
+    if initiator_expiry < responder_expiry.saturating_add(chain.min_timelock_margin) {
+        return Err(ContractError::UnsafeTimelockOrdering {
+            timelock,
+            counterpart_timelock,
+            min_margin: chain.min_timelock_margin,
+        });
+    }
+
+    Ok(())
+}
+
+// EVM swap ids and transaction hashes are 32 byte hex strings, stored lowercased.
+// Other chains have no shared format, so the reference is only trimmed.
+pub fn normalize_counterpart_reference(
+    family: &ChainFamily,
+    field: &str,
+    value: &str,
+) -> Result<String, ContractError> {
+    let value = value.trim();
+    let valid = match family {
+        ChainFamily::Evm => {
+            value.len() == 66
+                && value.starts_with("0x")
+                && value[2..].chars().all(|c| c.is_ascii_hexdigit())
+        }
+        _ => !value.is_empty(),
+    };
+
+    if !valid {
+        return Err(ContractError::InvalidCounterpartLeg {
+            reason: format!("invalid {}: {}", field, value),
+        });
+    }
+
+    Ok(match family {
+        ChainFamily::Evm => value.to_ascii_lowercase(),
+        _ => value.to_string(),
+    })
+}
+
+// Links a counterpart swap id to the swap and indexes it. A link is permanent,
+// relinking is only accepted for the same counterpart swap.
+pub fn link_counterpart_swap_id(
+    storage: &mut dyn Storage,
+    swap: &mut AtomicSwap,
+    counterpart_swap_id: String,
+) -> Result<(), ContractError> {
+    if let Some(linked) = &swap.counterpart.counterpart_swap_id {
+        if *linked != counterpart_swap_id {
+            return Err(ContractError::CounterpartAlreadyLinked {
+                swap_id: swap.swap_id.clone(),
+                counterpart_swap_id: linked.clone(),
+            });
+        }
+    }
+
+    let index_key = (
+        swap.counterpart.chain_id.as_str(),
+        counterpart_swap_id.as_str(),
+    );
+    if let Some(existing) = SWAPS_BY_COUNTERPART_ID.may_load(storage, index_key)? {
+        if existing != swap.swap_id {
+            return Err(ContractError::CounterpartAlreadyLinked {
+                swap_id: existing,
+                counterpart_swap_id,
+            });
+        }
+    }
+    SWAPS_BY_COUNTERPART_ID.save(storage, index_key, &swap.swap_id)?;
+    swap.counterpart.counterpart_swap_id = Some(counterpart_swap_id);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AddressFormat;
+    use cosmwasm_std::testing::MockStorage;
+
+    fn sepolia() -> CounterpartChain {
+        CounterpartChain {
+            chain_id: "11155111".to_string(),
+            name: "Sepolia".to_string(),
+            address_format: AddressFormat::Evm {},
+            min_timelock_margin: 600,
+            enabled: true,
+            htlc_contract: Some("0x52908400098527886E0F7030069857D2E4169EE7".to_string()),
+            ibc_channel: None,
+        }
+    }
+
+    fn invalid(chain: &CounterpartChain) -> bool {
+        matches!(
+            validate_counterpart_chain(chain),
+            Err(ContractError::InvalidCounterpartChain { .. })
+        )
+    }
+
+    #[test]
+    fn chains_need_an_id_and_a_name() {
+        assert!(validate_counterpart_chain(&sepolia()).is_ok());
+
+        for chain_id in ["", " ", " 11155111", "11155111\n"] {
+            let mut chain = sepolia();
+            chain.chain_id = chain_id.to_string();
+            assert!(invalid(&chain), "{:?}", chain_id);
+        }
+
+        let mut chain = sepolia();
+        chain.name = " ".to_string();
+        assert!(invalid(&chain));
+    }
+
+    #[test]
+    fn htlc_contracts_and_channels_match_the_chain_family() {
+        let mut chain = sepolia();
+        chain.htlc_contract = Some("0x1234".to_string());
+        assert!(invalid(&chain));
+
+        let mut chain = sepolia();
+        chain.ibc_channel = Some("channel-0".to_string());
+        assert!(invalid(&chain));
+
+        let mut hub = sepolia();
+        hub.address_format = AddressFormat::Bech32 {
+            prefix: "cosmos".to_string(),
+        };
+        assert!(invalid(&hub));
+        hub.htlc_contract = None;
+        hub.ibc_channel = Some(" ".to_string());
+        assert!(invalid(&hub));
+        hub.ibc_channel = Some("channel-0".to_string());
+        assert!(validate_counterpart_chain(&hub).is_ok());
+    }
+
+    #[test]
+    fn legs_are_ordered_by_at_least_the_margin() {
+        let chain = sepolia();
+        let ordered = |role, timelock, counterpart_timelock| {
+            validate_timelock_ordering(&chain, &role, timelock, Some(counterpart_timelock)).is_ok()
+        };
+
+        assert!(ordered(SwapRole::InitiatorLeg, 10_600, 10_000));
+        assert!(!ordered(SwapRole::InitiatorLeg, 10_599, 10_000));
+        assert!(ordered(SwapRole::ResponderLeg, 10_000, 10_600));
+        assert!(!ordered(SwapRole::ResponderLeg, 10_000, 10_599));
+
+        // Only a responder has to know when the other leg expires
+        assert!(validate_timelock_ordering(&chain, &SwapRole::InitiatorLeg, 10_000, None).is_ok());
+        assert!(validate_timelock_ordering(&chain, &SwapRole::ResponderLeg, 10_000, None).is_err());
+    }
+
+    #[test]
+    fn only_enabled_chains_take_new_swaps() {
+        let mut storage = MockStorage::new();
+        assert!(matches!(
+            load_enabled_chain(&storage, "11155111"),
+            Err(ContractError::UnknownCounterpartChain { .. })
+        ));
+
+        let mut chain = sepolia();
+        COUNTERPART_CHAINS
+            .save(&mut storage, &chain.chain_id, &chain)
+            .unwrap();
+        assert_eq!(load_enabled_chain(&storage, "11155111").unwrap(), chain);
+
+        chain.enabled = false;
+        COUNTERPART_CHAINS
+            .save(&mut storage, &chain.chain_id, &chain)
+            .unwrap();
+        assert!(matches!(
+            load_enabled_chain(&storage, "11155111"),
+            Err(ContractError::CounterpartChainDisabled { .. })
+        ));
+    }
+}
@@ -50,4 +50,140 @@ pub enum ContractError {
 
     #[error("Contract is paused")]
     ContractPaused {},
-}
\ No newline at end of file
+
+    #[error("Invalid config: {field} is {value}, must be between {min} and {max} seconds")]
+    InvalidConfigTimelockDuration {
+        field: String,
+        value: u64,
+        min: u64,
+        max: u64,
+    },
+
+    #[error("Invalid config: min_timelock_duration ({min}) must be less than max_timelock_duration ({max})")]
+    InvalidConfigTimelockRange { min: u64, max: u64 },
+
+    #[error("Invalid config: minimum_safety_deposit is {value}, must be between 1 and {max}")]
+    InvalidConfigSafetyDeposit { value: String, max: String },
+
+    #[error("Invalid config: admin address {address} is not valid")]
+    InvalidConfigAdmin { address: String },
+
+    #[error("Cannot migrate from contract {contract}")]
+    InvalidMigration { contract: String },
+
+    #[error("Too many active swaps for {address}. Limit: {limit}")]
+    TooManyActiveSwaps { address: String, limit: u32 },
+
+    #[error("Volume limit exceeded for {address}. Remaining in current window: {remaining}")]
+    VolumeLimitExceeded { address: String, remaining: String },
+
+    #[error("Swap amount too low. Minimum required: {minimum}")]
+    SwapAmountTooLow { minimum: String },
+
+    #[error(
+        "Invalid config: volume_window_seconds must be greater than 0 when volume limits are set"
+    )]
+    InvalidConfigVolumeWindow {},
+
+    #[error("Outflow limit exceeded for {denom}. Remaining in current epoch: {remaining}")]
+    OutflowLimitExceeded { denom: String, remaining: String },
+
+    #[error("Invalid config: epoch_seconds must be greater than 0 when outflow caps are set")]
+    InvalidConfigOutflowEpoch {},
+
+    #[error("Circuit breaker tripped, only refunds are allowed until an admin resets it")]
+    CircuitBreakerTripped {},
+
+    #[error("Invalid config: circuit breaker windows and multiplier must be greater than 0")]
+    InvalidConfigCircuitBreaker {},
+
+    #[error("Address {address} is denied")]
+    AddressDenied { address: String },
+
+    #[error("Address {address} is not on the allowlist")]
+    AddressNotAllowed { address: String },
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Unknown counterpart chain: {chain_id}")]
+    UnknownCounterpartChain { chain_id: String },
+
+    #[error("Counterpart chain {chain_id} is disabled")]
+    CounterpartChainDisabled { chain_id: String },
+
+    #[error("Invalid counterpart chain: {reason}")]
+    InvalidCounterpartChain { reason: String },
+
+    #[error("Invalid counterpart recipient: {recipient}")]
+    InvalidCounterpartRecipient { recipient: String },
+
+    #[error("Invalid counterpart leg: {reason}")]
+    InvalidCounterpartLeg { reason: String },
+
+    #[error("Unsafe timelock ordering. Timelock {timelock} and counterpart timelock {counterpart_timelock} must be at least {min_margin} seconds apart, with the initiator leg expiring last")]
+    UnsafeTimelockOrdering {
+        timelock: u64,
+        counterpart_timelock: u64,
+        min_margin: u64,
+    },
+
+    #[error("Swap {swap_id} is already linked to counterpart swap {counterpart_swap_id}")]
+    CounterpartAlreadyLinked {
+        swap_id: String,
+        counterpart_swap_id: String,
+    },
+
+    #[error(
+        "Invalid config: attestors must be unique and the threshold between 1 and their count"
+    )]
+    InvalidConfigAttestors {},
+
+    #[error("Already attested for swap {swap_id}")]
+    AlreadyAttested { swap_id: String },
+
+    #[error("Counterpart lock and refund attestations conflict for swap {swap_id}")]
+    AttestationConflict { swap_id: String },
+
+    #[error("Counterpart lock not attested for swap {swap_id}")]
+    CounterpartLockNotAttested { swap_id: String },
+
+    #[error("Invalid proof: {reason}")]
+    InvalidProof { reason: String },
+
+    #[error("Conflicting header for block {number} on chain {chain_id}")]
+    HeaderConflict { chain_id: String, number: u64 },
+
+    #[error("Header for block {number} on chain {chain_id} already voted")]
+    HeaderAlreadyVoted { chain_id: String, number: u64 },
+
+    #[error("Invalid IBC channel: {reason}")]
+    InvalidIbcChannel { reason: String },
+
+    #[error("Invalid IBC delivery: {reason}")]
+    InvalidIbcDelivery { reason: String },
+
+    #[error("Invalid completion action: {reason}")]
+    InvalidCompletionAction { reason: String },
+
+    #[error("Expected a single coin covering the swap amount and safety deposit")]
+    InvalidFunds {},
+
+    #[error("Invalid interchain account: {reason}")]
+    InvalidIcaAccount { reason: String },
+
+    #[error("Invalid remote leg: {reason}")]
+    InvalidRemoteLeg { reason: String },
+
+    #[error("Sender is not the ibc-hooks intermediary of {original_sender} on {channel_id}")]
+    NotIbcHookSender {
+        channel_id: String,
+        original_sender: String,
+    },
+
+    #[error("No swap with hashlock {hashlock} can be completed")]
+    NoCompletableSwap { hashlock: String },
+
+    #[error("Too many hooks on swap {swap_id}. Limit: {limit}")]
+    TooManyHooks { swap_id: String, limit: usize },
+}
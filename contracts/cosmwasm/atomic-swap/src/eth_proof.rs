@@ -0,0 +1,263 @@
+use cosmwasm_std::Uint128;
+use rlp::Rlp;
+use sha3::{Digest, Keccak256};
+
+use crate::error::ContractError;
+
+// Position of the fields we need in an RLP encoded block header
+const HEADER_RECEIPTS_ROOT: usize = 5;
+const HEADER_NUMBER: usize = 8;
+const HEADER_TIMESTAMP: usize = 11;
+
+// Branch nodes carry 16 children plus a value, leaf and extension nodes a path plus a value
+const BRANCH_NODE_ITEMS: usize = 17;
+const SHORT_NODE_ITEMS: usize = 2;
+
+#[derive(Debug, PartialEq)]
+pub struct DecodedHeader {
+    pub number: u64,
+    pub hash: [u8; 32],
+    pub receipts_root: [u8; 32],
+    pub timestamp: u64,
+}
+
+// SwapInitiated(bytes32 indexed swapId, address indexed initiator, address indexed participant,
+//               address token, uint256 amount, bytes32 hashlock, uint256 timelock)
+#[derive(Debug, PartialEq)]
+pub struct SwapInitiatedLog {
+    pub contract: [u8; 20],
+    pub swap_id: [u8; 32],
+    pub initiator: [u8; 20],
+    pub participant: [u8; 20],
+    pub token: [u8; 20],
+    pub amount: Uint128,
+    pub hashlock: [u8; 32],
+    pub timelock: u64,
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+pub fn swap_initiated_topic() -> [u8; 32] {
+    keccak256(b"SwapInitiated(bytes32,address,address,address,uint256,bytes32,uint256)")
+}
+
+pub fn decode_header(header_rlp: &[u8]) -> Result<DecodedHeader, ContractError> {
+    let header = Rlp::new(header_rlp);
+    if !header.is_list() || header.item_count().map_err(invalid)? <= HEADER_TIMESTAMP {
+        return Err(proof_error("header is not a block header list"));
+    }
+
+    Ok(DecodedHeader {
+        number: header.val_at(HEADER_NUMBER).map_err(invalid)?,
+        hash: keccak256(header_rlp),
+        receipts_root: fixed_bytes(
+            header
+                .at(HEADER_RECEIPTS_ROOT)
+                .map_err(invalid)?
+                .data()
+                .map_err(invalid)?,
+        )?,
+        timestamp: header.val_at(HEADER_TIMESTAMP).map_err(invalid)?,
+    })
+}
+
+// Walks a Merkle-Patricia proof from the root down to the value stored under
+// the key. Every node has to hash to the reference held by its parent, nodes
+// shorter than 32 bytes are embedded in their parent instead of hashed.
+pub fn verify_proof(
+    root: &[u8; 32],
+    key: &[u8],
+    proof: &[Vec<u8>],
+) -> Result<Vec<u8>, ContractError> {
+    let path = to_nibbles(key);
+    let mut offset = 0;
+    let mut proof_nodes = proof.iter();
+    let mut next = NodeRef::Hash(*root);
+
+    loop {
+        let node = match next {
+            NodeRef::Hash(hash) => {
+                let node = proof_nodes
+                    .next()
+                    .ok_or_else(|| proof_error("proof ended before reaching the value"))?;
+                if keccak256(node) != hash {
+                    return Err(proof_error("node hash does not match its reference"));
+                }
+                node.clone()
+            }
+            NodeRef::Inline(node) => node,
+        };
+
+        let rlp = Rlp::new(&node);
+        match rlp.item_count().map_err(invalid)? {
+            BRANCH_NODE_ITEMS => {
+                if offset == path.len() {
+                    return non_empty(rlp.at(16).map_err(invalid)?.data().map_err(invalid)?);
+                }
+                next = child_ref(&rlp.at(path[offset] as usize).map_err(invalid)?)?;
+                offset += 1;
+            }
+            SHORT_NODE_ITEMS => {
+                let (is_leaf, node_path) =
+                    decode_compact_path(rlp.at(0).map_err(invalid)?.data().map_err(invalid)?)?;
+                if !path[offset..].starts_with(&node_path) {
+                    return Err(proof_error("key is not present in the trie"));
+                }
+                offset += node_path.len();
+
+                if is_leaf {
+                    if offset != path.len() {
+                        return Err(proof_error("key is not present in the trie"));
+                    }
+                    return non_empty(rlp.at(1).map_err(invalid)?.data().map_err(invalid)?);
+                }
+                next = child_ref(&rlp.at(1).map_err(invalid)?)?;
+            }
+            _ => return Err(proof_error("malformed trie node")),
+        }
+    }
+}
+
+// Proves the receipt at `tx_index` against the header's receipts root
+pub fn verify_receipt(
+    receipts_root: &[u8; 32],
+    tx_index: u64,
+    proof: &[Vec<u8>],
+) -> Result<Vec<u8>, ContractError> {
+    let key = rlp::encode(&tx_index);
+    verify_proof(receipts_root, &key, proof)
+}
+
+// Reads the SwapInitiated log at `log_index` out of a successful receipt
+pub fn decode_swap_initiated(
+    receipt: &[u8],
+    log_index: usize,
+) -> Result<SwapInitiatedLog, ContractError> {
+    // Typed receipts (EIP-2718) carry their type byte in front of the RLP payload
+    let payload = match receipt.first() {
+        Some(byte) if *byte <= 0x7f => &receipt[1..],
+        _ => receipt,
+    };
+
+    let receipt = Rlp::new(payload);
+    if receipt.item_count().map_err(invalid)? != 4 {
+        return Err(proof_error("malformed receipt"));
+    }
+    let status: u64 = receipt.val_at(0).map_err(invalid)?;
+    if status != 1 {
+        return Err(proof_error("transaction reverted"));
+    }
+
+    let log = receipt
+        .at(3)
+        .map_err(invalid)?
+        .at(log_index)
+        .map_err(invalid)?;
+    let contract = fixed_bytes(log.at(0).map_err(invalid)?.data().map_err(invalid)?)?;
+    let topics = log.at(1).map_err(invalid)?;
+    let data = log.at(2).map_err(invalid)?.data().map_err(invalid)?;
+
+    if topics.item_count().map_err(invalid)? != 4 || data.len() != 4 * 32 {
+        return Err(proof_error("log is not a SwapInitiated event"));
+    }
+    let topic = |i: usize| -> Result<[u8; 32], ContractError> {
+        fixed_bytes(topics.at(i).map_err(invalid)?.data().map_err(invalid)?)
+    };
+    if topic(0)? != swap_initiated_topic() {
+        return Err(proof_error("log is not a SwapInitiated event"));
+    }
+
+    Ok(SwapInitiatedLog {
+        contract,
+        swap_id: topic(1)?,
+        initiator: word_to_address(&topic(2)?)?,
+        participant: word_to_address(&topic(3)?)?,
+        token: word_to_address(&data[0..32])?,
+        amount: word_to_uint128(&data[32..64])?,
+        hashlock: fixed_bytes(&data[64..96])?,
+        timelock: word_to_uint128(&data[96..128])?
+            .u128()
+            .try_into()
+            .map_err(|_| proof_error("timelock out of range"))?,
+    })
+}
+
+enum NodeRef {
+    Hash([u8; 32]),
+    Inline(Vec<u8>),
+}
+
+fn child_ref(item: &Rlp) -> Result<NodeRef, ContractError> {
+    if item.is_list() {
+        return Ok(NodeRef::Inline(item.as_raw().to_vec()));
+    }
+    match item.data().map_err(invalid)? {
+        [] => Err(proof_error("key is not present in the trie")),
+        data => Ok(NodeRef::Hash(fixed_bytes(data)?)),
+    }
+}
+
+// Hex-prefix encoding: the high nibble of the first byte flags leaf vs extension
+// and whether the path has an odd number of nibbles
+fn decode_compact_path(encoded: &[u8]) -> Result<(bool, Vec<u8>), ContractError> {
+    let first = *encoded
+        .first()
+        .ok_or_else(|| proof_error("empty node path"))?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err(proof_error("malformed node path"));
+    }
+
+    let mut nibbles = Vec::with_capacity(encoded.len() * 2);
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    }
+    nibbles.extend(to_nibbles(&encoded[1..]));
+    Ok((flag & 2 == 2, nibbles))
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+fn word_to_address(word: &[u8]) -> Result<[u8; 20], ContractError> {
+    if word.len() != 32 || word[..12].iter().any(|byte| *byte != 0) {
+        return Err(proof_error("malformed address word"));
+    }
+    fixed_bytes(&word[12..])
+}
+
+fn word_to_uint128(word: &[u8]) -> Result<Uint128, ContractError> {
+    if word.len() != 32 || word[..16].iter().any(|byte| *byte != 0) {
+        return Err(proof_error("amount out of range"));
+    }
+    let low: [u8; 16] = fixed_bytes(&word[16..])?;
+    Ok(Uint128::new(u128::from_be_bytes(low)))
+}
+
+fn fixed_bytes<const N: usize>(data: &[u8]) -> Result<[u8; N], ContractError> {
+    data.try_into()
+        .map_err(|_| proof_error(&format!("expected {} bytes, got {}", N, data.len())))
+}
+
+fn non_empty(value: &[u8]) -> Result<Vec<u8>, ContractError> {
+    if value.is_empty() {
+        return Err(proof_error("key is not present in the trie"));
+    }
+    Ok(value.to_vec())
+}
+
+fn invalid(err: rlp::DecoderError) -> ContractError {
+    proof_error(&err.to_string())
+}
+
+fn proof_error(reason: &str) -> ContractError {
+    ContractError::InvalidProof {
+        reason: reason.to_string(),
+    }
+}
@@ -0,0 +1,348 @@
+use std::str::FromStr;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Event, StdError, StdResult, Uint128};
+
+use crate::state::{AtomicSwap, SwapRole};
+
+// Schema of the atomic_swap.* events, bumped whenever an attribute is removed
+// or changes meaning. Every event carries it as `schema_version`.
+//
+// atomic_swap.initiated: swap_id, initiator, participant, denom, amount,
+//   safety_deposit, escrowed, hashlock, timelock, counterpart_chain_id,
+//   counterpart_recipient, counterpart_role and, when declared,
+//   counterpart_asset, counterpart_amount, counterpart_timelock
+// atomic_swap.completed: swap_id, initiator, participant, denom, amount,
+//   safety_deposit, participant_payout, initiator_payout, hashlock, secret, counterpart_chain_id, completed_at and,
+//   when linked, counterpart_swap_id
+// atomic_swap.refunded: swap_id, initiator, denom, amount, safety_deposit,
+//   initiator_payout, hashlock, counterpart_chain_id, reason, refunded_at and, when linked,
+//   counterpart_swap_id
+//
+// Settlement: the contract takes no cut, so `escrowed` is amount plus
+// safety_deposit and the payouts of a completed or refunded swap add up to it.
+// On completion the participant is paid the amount and the initiator gets the
+// deposit back, on refund the initiator is paid both.
+pub const EVENT_SCHEMA_VERSION: &str = "1";
+
+const EVENT_PREFIX: &str = "atomic_swap.";
+// wasmd prefixes the types of events emitted by contracts
+const WASM_EVENT_PREFIX: &str = "wasm-";
+
+#[cw_serde]
+pub struct SwapInitiated {
+    pub swap_id: String,
+    pub initiator: Addr,
+    pub participant: Addr,
+    pub denom: String,
+    pub amount: Uint128,
+    pub safety_deposit: Uint128,
+    pub escrowed: Uint128,
+    pub hashlock: String,
+    pub timelock: u64,
+    pub counterpart_chain_id: String,
+    pub counterpart_recipient: String,
+    pub counterpart_role: SwapRole,
+    pub counterpart_asset: Option<String>,
+    pub counterpart_amount: Option<Uint128>,
+    pub counterpart_timelock: Option<u64>,
+}
+
+#[cw_serde]
+pub struct SwapCompleted {
+    pub swap_id: String,
+    pub initiator: Addr,
+    pub participant: Addr,
+    pub denom: String,
+    pub amount: Uint128,
+    pub safety_deposit: Uint128,
+    pub participant_payout: Uint128,
+    pub initiator_payout: Uint128,
+    pub hashlock: String,
+    pub secret: String,
+    pub counterpart_chain_id: String,
+    pub counterpart_swap_id: Option<String>,
+    pub completed_at: u64,
+}
+
+#[cw_serde]
+pub enum RefundReason {
+    Expired,
+    Emergency,
+}
+
+#[cw_serde]
+pub struct SwapRefunded {
+    pub swap_id: String,
+    pub initiator: Addr,
+    pub denom: String,
+    pub amount: Uint128,
+    pub safety_deposit: Uint128,
+    pub initiator_payout: Uint128,
+    pub hashlock: String,
+    pub counterpart_chain_id: String,
+    pub counterpart_swap_id: Option<String>,
+    pub reason: RefundReason,
+    pub refunded_at: u64,
+}
+
+#[cw_serde]
+pub enum SwapEvent {
+    Initiated(SwapInitiated),
+    Completed(SwapCompleted),
+    Refunded(SwapRefunded),
+}
+
+impl SwapEvent {
+    pub fn initiated(swap: &AtomicSwap) -> Self {
+        SwapEvent::Initiated(SwapInitiated {
+            swap_id: swap.swap_id.clone(),
+            initiator: swap.initiator.clone(),
+            participant: swap.participant.clone(),
+            denom: swap.amount.denom.clone(),
+            amount: swap.amount.amount,
+            safety_deposit: swap.safety_deposit,
+            escrowed: swap.amount.amount + swap.safety_deposit,
+            hashlock: swap.hashlock.clone(),
+            timelock: swap.timelock,
+            counterpart_chain_id: swap.counterpart.chain_id.clone(),
+            counterpart_recipient: swap.counterpart.recipient.clone(),
+            counterpart_role: swap.counterpart.role.clone(),
+            counterpart_asset: swap.counterpart.asset.clone(),
+            counterpart_amount: swap.counterpart.expected_amount,
+            counterpart_timelock: swap.counterpart.timelock,
+        })
+    }
+
+    pub fn completed(swap: &AtomicSwap) -> Self {
+        SwapEvent::Completed(SwapCompleted {
+            swap_id: swap.swap_id.clone(),
+            initiator: swap.initiator.clone(),
+            participant: swap.participant.clone(),
+            denom: swap.amount.denom.clone(),
+            amount: swap.amount.amount,
+            safety_deposit: swap.safety_deposit,
+            participant_payout: swap.amount.amount,
+            initiator_payout: swap.safety_deposit,
+            hashlock: swap.hashlock.clone(),
+            secret: swap.secret.clone().unwrap_or_default(),
+            counterpart_chain_id: swap.counterpart.chain_id.clone(),
+            counterpart_swap_id: swap.counterpart.counterpart_swap_id.clone(),
+            completed_at: swap.completed_at.unwrap_or_default(),
+        })
+    }
+
+    pub fn refunded(swap: &AtomicSwap, reason: RefundReason) -> Self {
+        SwapEvent::Refunded(SwapRefunded {
+            swap_id: swap.swap_id.clone(),
+            initiator: swap.initiator.clone(),
+            denom: swap.amount.denom.clone(),
+            amount: swap.amount.amount,
+            safety_deposit: swap.safety_deposit,
+            initiator_payout: swap.amount.amount + swap.safety_deposit,
+            hashlock: swap.hashlock.clone(),
+            counterpart_chain_id: swap.counterpart.chain_id.clone(),
+            counterpart_swap_id: swap.counterpart.counterpart_swap_id.clone(),
+            reason,
+            refunded_at: swap.completed_at.unwrap_or_default(),
+        })
+    }
+
+    pub fn to_event(&self) -> Event {
+        let event = match self {
+            SwapEvent::Initiated(e) => Event::new("atomic_swap.initiated")
+                .add_attribute("swap_id", &e.swap_id)
+                .add_attribute("initiator", &e.initiator)
+                .add_attribute("participant", &e.participant)
+                .add_attribute("denom", &e.denom)
+                .add_attribute("amount", e.amount)
+                .add_attribute("safety_deposit", e.safety_deposit)
+                .add_attribute("escrowed", e.escrowed)
+                .add_attribute("hashlock", &e.hashlock)
+                .add_attribute("timelock", e.timelock.to_string())
+                .add_attribute("counterpart_chain_id", &e.counterpart_chain_id)
+                .add_attribute("counterpart_recipient", &e.counterpart_recipient)
+                .add_attribute("counterpart_role", role_name(&e.counterpart_role))
+                .add_attributes(optional("counterpart_asset", &e.counterpart_asset))
+                .add_attributes(optional("counterpart_amount", &e.counterpart_amount))
+                .add_attributes(optional("counterpart_timelock", &e.counterpart_timelock)),
+            SwapEvent::Completed(e) => Event::new("atomic_swap.completed")
+                .add_attribute("swap_id", &e.swap_id)
+                .add_attribute("initiator", &e.initiator)
+                .add_attribute("participant", &e.participant)
+                .add_attribute("denom", &e.denom)
+                .add_attribute("amount", e.amount)
+                .add_attribute("safety_deposit", e.safety_deposit)
+                .add_attribute("participant_payout", e.participant_payout)
+                .add_attribute("initiator_payout", e.initiator_payout)
+                .add_attribute("hashlock", &e.hashlock)
+                .add_attribute("secret", &e.secret)
+                .add_attribute("counterpart_chain_id", &e.counterpart_chain_id)
+                .add_attributes(optional("counterpart_swap_id", &e.counterpart_swap_id))
+                .add_attribute("completed_at", e.completed_at.to_string()),
+            SwapEvent::Refunded(e) => Event::new("atomic_swap.refunded")
+                .add_attribute("swap_id", &e.swap_id)
+                .add_attribute("initiator", &e.initiator)
+                .add_attribute("denom", &e.denom)
+                .add_attribute("amount", e.amount)
+                .add_attribute("safety_deposit", e.safety_deposit)
+                .add_attribute("initiator_payout", e.initiator_payout)
+                .add_attribute("hashlock", &e.hashlock)
+                .add_attribute("counterpart_chain_id", &e.counterpart_chain_id)
+                .add_attributes(optional("counterpart_swap_id", &e.counterpart_swap_id))
+                .add_attribute("reason", reason_name(&e.reason))
+                .add_attribute("refunded_at", e.refunded_at.to_string()),
+        };
+
+        event.add_attribute("schema_version", EVENT_SCHEMA_VERSION)
+    }
+
+    // Reads an event back, as emitted or with the wasm- prefix added by wasmd.
+    // Events of other types are skipped with None.
+    pub fn parse(event: &Event) -> StdResult<Option<SwapEvent>> {
+        let ty = event
+            .ty
+            .strip_prefix(WASM_EVENT_PREFIX)
+            .unwrap_or(&event.ty);
+        let name = match ty.strip_prefix(EVENT_PREFIX) {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+
+        let attrs = Attributes(event);
+        let version = attrs.required("schema_version")?;
+        if version != EVENT_SCHEMA_VERSION {
+            return Err(StdError::parse_err(
+                ty,
+                format!("unsupported schema version {}", version),
+            ));
+        }
+
+        let parsed = match name {
+            "initiated" => SwapEvent::Initiated(SwapInitiated {
+                swap_id: attrs.required("swap_id")?,
+                initiator: Addr::unchecked(attrs.required("initiator")?),
+                participant: Addr::unchecked(attrs.required("participant")?),
+                denom: attrs.required("denom")?,
+                amount: attrs.parsed("amount")?,
+                safety_deposit: attrs.parsed("safety_deposit")?,
+                escrowed: attrs.parsed("escrowed")?,
+                hashlock: attrs.required("hashlock")?,
+                timelock: attrs.parsed("timelock")?,
+                counterpart_chain_id: attrs.required("counterpart_chain_id")?,
+                counterpart_recipient: attrs.required("counterpart_recipient")?,
+                counterpart_role: parse_role(&attrs.required("counterpart_role")?)?,
+                counterpart_asset: attrs.optional("counterpart_asset"),
+                counterpart_amount: attrs.optional_parsed("counterpart_amount")?,
+                counterpart_timelock: attrs.optional_parsed("counterpart_timelock")?,
+            }),
+            "completed" => SwapEvent::Completed(SwapCompleted {
+                swap_id: attrs.required("swap_id")?,
+                initiator: Addr::unchecked(attrs.required("initiator")?),
+                participant: Addr::unchecked(attrs.required("participant")?),
+                denom: attrs.required("denom")?,
+                amount: attrs.parsed("amount")?,
+                safety_deposit: attrs.parsed("safety_deposit")?,
+                participant_payout: attrs.parsed("participant_payout")?,
+                initiator_payout: attrs.parsed("initiator_payout")?,
+                hashlock: attrs.required("hashlock")?,
+                secret: attrs.required("secret")?,
+                counterpart_chain_id: attrs.required("counterpart_chain_id")?,
+                counterpart_swap_id: attrs.optional("counterpart_swap_id"),
+                completed_at: attrs.parsed("completed_at")?,
+            }),
+            "refunded" => SwapEvent::Refunded(SwapRefunded {
+                swap_id: attrs.required("swap_id")?,
+                initiator: Addr::unchecked(attrs.required("initiator")?),
+                denom: attrs.required("denom")?,
+                amount: attrs.parsed("amount")?,
+                safety_deposit: attrs.parsed("safety_deposit")?,
+                initiator_payout: attrs.parsed("initiator_payout")?,
+                hashlock: attrs.required("hashlock")?,
+                counterpart_chain_id: attrs.required("counterpart_chain_id")?,
+                counterpart_swap_id: attrs.optional("counterpart_swap_id"),
+                reason: parse_reason(&attrs.required("reason")?)?,
+                refunded_at: attrs.parsed("refunded_at")?,
+            }),
+            _ => return Err(StdError::parse_err(ty, "unknown atomic_swap event")),
+        };
+
+        Ok(Some(parsed))
+    }
+}
+
+// Parses every atomic_swap event out of a transaction's events
+pub fn parse_swap_events(events: &[Event]) -> StdResult<Vec<SwapEvent>> {
+    events
+        .iter()
+        .filter_map(|event| SwapEvent::parse(event).transpose())
+        .collect()
+}
+
+struct Attributes<'a>(&'a Event);
+
+impl Attributes<'_> {
+    fn optional(&self, key: &str) -> Option<String> {
+        self.0
+            .attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+    }
+
+    fn required(&self, key: &str) -> StdResult<String> {
+        self.optional(key)
+            .ok_or_else(|| StdError::parse_err(&self.0.ty, format!("missing attribute {}", key)))
+    }
+
+    fn parsed<T: FromStr>(&self, key: &str) -> StdResult<T> {
+        let value = self.required(key)?;
+        value
+            .parse()
+            .map_err(|_| StdError::parse_err(&self.0.ty, format!("invalid {}: {}", key, value)))
+    }
+
+    fn optional_parsed<T: FromStr>(&self, key: &str) -> StdResult<Option<T>> {
+        match self.optional(key) {
+            Some(_) => self.parsed(key).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+fn optional<T: ToString>(key: &str, value: &Option<T>) -> Vec<(String, String)> {
+    value
+        .iter()
+        .map(|value| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn role_name(role: &SwapRole) -> &'static str {
+    match role {
+        SwapRole::InitiatorLeg => "initiator_leg",
+        SwapRole::ResponderLeg => "responder_leg",
+    }
+}
+
+fn parse_role(role: &str) -> StdResult<SwapRole> {
+    match role {
+        "initiator_leg" => Ok(SwapRole::InitiatorLeg),
+        "responder_leg" => Ok(SwapRole::ResponderLeg),
+        _ => Err(StdError::parse_err("SwapRole", role)),
+    }
+}
+
+fn reason_name(reason: &RefundReason) -> &'static str {
+    match reason {
+        RefundReason::Expired => "expired",
+        RefundReason::Emergency => "emergency",
+    }
+}
+
+fn parse_reason(reason: &str) -> StdResult<RefundReason> {
+    match reason {
+        "expired" => Ok(RefundReason::Expired),
+        "emergency" => Ok(RefundReason::Emergency),
+        _ => Err(StdError::parse_err("RefundReason", reason)),
+    }
+}
@@ -0,0 +1,77 @@
+use cosmwasm_std::{to_json_binary, Addr, Order, StdResult, Storage, SubMsg, WasmMsg};
+
+use crate::msg::{SwapHookExecuteMsg, SwapHookMsg};
+use crate::state::{HOOKS, SWAP_HOOKS};
+
+pub const HOOK_REPLY_ID: u64 = 3;
+pub const MAX_SWAP_HOOKS: usize = 5;
+
+// Caps each listener so running out of gas is caught by its submessage
+const HOOK_GAS_LIMIT: u64 = 500_000;
+
+// Hooks registered on a swap by either party
+pub fn swap_hooks(storage: &dyn Storage, swap_id: &str) -> StdResult<Vec<Addr>> {
+    let mut hooks = vec![];
+    for key in SWAP_HOOKS
+        .sub_prefix(swap_id)
+        .keys(storage, None, None, Order::Ascending)
+    {
+        let (_, hook) = key?;
+        if !hooks.contains(&hook) {
+            hooks.push(hook);
+        }
+    }
+    Ok(hooks)
+}
+
+pub fn party_hooks(storage: &dyn Storage, swap_id: &str, party: &Addr) -> StdResult<Vec<Addr>> {
+    SWAP_HOOKS
+        .prefix((swap_id, party))
+        .keys(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+// Notifies the global hooks and the hooks registered by the party the event
+// pays out to, each in its own submessage so a failing listener is reverted
+// without blocking settlement. A party settling its side never runs hooks the
+// other party registered.
+pub fn hook_messages(
+    storage: &dyn Storage,
+    swap_id: &str,
+    msg: SwapHookMsg,
+) -> StdResult<Vec<SubMsg>> {
+    let mut hooks = HOOKS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let party = match &msg {
+        // Parties can only register hooks once the swap exists
+        SwapHookMsg::Initiated { .. } => None,
+        SwapHookMsg::Completed { participant, .. } => Some(participant),
+        SwapHookMsg::Refunded { initiator, .. } => Some(initiator),
+    };
+    let party_hooks = match party {
+        Some(party) => party_hooks(storage, swap_id, party)?,
+        None => vec![],
+    };
+    for hook in party_hooks {
+        if !hooks.contains(&hook) {
+            hooks.push(hook);
+        }
+    }
+
+    let msg = to_json_binary(&SwapHookExecuteMsg::SwapHook(msg))?;
+    Ok(hooks
+        .into_iter()
+        .map(|hook| {
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: hook.into_string(),
+                    msg: msg.clone(),
+                    funds: vec![],
+                },
+                HOOK_REPLY_ID,
+            )
+            .with_gas_limit(HOOK_GAS_LIMIT)
+        })
+        .collect())
+}
@@ -0,0 +1,440 @@
+#[cfg(not(feature = "imported"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_json, to_json_binary, DepsMut, Env, Ibc3ChannelOpenResponse, IbcBasicResponse, IbcChannel,
+    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcMsg,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, IbcTimeout,
+    Order, StdResult, Storage,
+};
+use sha2::{Digest, Sha256};
+
+use crate::circuit_breaker::ensure_not_tripped;
+use crate::contract::{ensure_completable, settle_completion, verify_secret};
+use crate::counterpart::{link_counterpart_swap_id, validate_timelock_ordering};
+use crate::error::ContractError;
+use crate::ibc_msg::{ack_success, SwapPacket, SwapPacketAck, IBC_ORDER, IBC_VERSION};
+use crate::msg::SwapState;
+use crate::state::{
+    swaps, AtomicSwap, AttestationStatus, CounterpartChain, IbcChannelInfo, RemoteLock,
+    CIRCUIT_BREAKER_CONFIG, CONFIG, COUNTERPART_CHAINS, IBC_CHANNELS, REMOTE_LOCKS,
+};
+
+// A lost packet only means falling back to manual settlement, so packets are short lived
+const PACKET_LIFETIME_SECONDS: u64 = 3600;
+
+#[cfg_attr(not(feature = "imported"), entry_point)]
+pub fn ibc_channel_open(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<IbcChannelOpenResponse, ContractError> {
+    validate_channel(msg.channel(), msg.counterparty_version())?;
+
+    Ok(Some(Ibc3ChannelOpenResponse {
+        version: IBC_VERSION.to_string(),
+    }))
+}
+
+#[cfg_attr(not(feature = "imported"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(channel, msg.counterparty_version())?;
+
+    let info = IbcChannelInfo {
+        channel_id: channel.endpoint.channel_id.clone(),
+        connection_id: channel.connection_id.clone(),
+        counterparty_port_id: channel.counterparty_endpoint.port_id.clone(),
+        counterparty_channel_id: channel.counterparty_endpoint.channel_id.clone(),
+    };
+    IBC_CHANNELS.save(deps.storage, &info.channel_id, &info)?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("method", "ibc_channel_connect")
+        .add_attribute("channel_id", info.channel_id))
+}
+
+#[cfg_attr(not(feature = "imported"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = &msg.channel().endpoint.channel_id;
+    IBC_CHANNELS.remove(deps.storage, channel_id);
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("method", "ibc_channel_close")
+        .add_attribute("channel_id", channel_id))
+}
+
+// An error is written as an error acknowledgement by the chain and the state
+// changes of the packet are reverted, so handlers may fail after writing
+#[cfg_attr(not(feature = "imported"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let channel_id = msg.packet.dest.channel_id;
+    if !IBC_CHANNELS.has(deps.storage, &channel_id) {
+        return Err(ContractError::InvalidIbcChannel {
+            reason: format!("channel {} is not connected", channel_id),
+        });
+    }
+
+    match from_json(&msg.packet.data)? {
+        SwapPacket::Lock {
+            swap_id,
+            hashlock,
+            amount,
+            timelock,
+            recipient,
+        } => {
+            let lock = RemoteLock {
+                swap_id,
+                amount,
+                timelock,
+                recipient,
+                received_at: env.block.time.seconds(),
+            };
+            receive_lock(deps, &channel_id, &hashlock, lock)
+        }
+        SwapPacket::SecretRevealed {
+            swap_id,
+            hashlock,
+            secret,
+        } => receive_secret(deps, env, &channel_id, swap_id, hashlock, secret),
+        SwapPacket::Refunded { swap_id, hashlock } => {
+            receive_refund(deps, env, &channel_id, swap_id, hashlock)
+        }
+    }
+}
+
+// Local swaps never depend on the counterpart accepting a packet. An error
+// ack is final, the counterpart already rejected what the packet reported.
+#[cfg_attr(not(feature = "imported"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let packet: SwapPacket = from_json(&msg.original_packet.data)?;
+    let ack = from_json(&msg.acknowledgement.data)
+        .unwrap_or_else(|_| SwapPacketAck::Error("unreadable acknowledgement".to_string()));
+
+    let response = IbcBasicResponse::new()
+        .add_attribute("method", "ibc_packet_ack")
+        .add_attribute("packet", packet.kind())
+        .add_attribute("swap_id", packet.swap_id());
+
+    Ok(match ack {
+        SwapPacketAck::Result(_) => response.add_attribute("success", "true"),
+        SwapPacketAck::Error(error) => response
+            .add_attribute("success", "false")
+            .add_attribute("error", error),
+    })
+}
+
+// A timed out packet never reached the counterpart, so it is sent again for
+// as long as the counterpart swap can still act on it
+#[cfg_attr(not(feature = "imported"), entry_point)]
+pub fn ibc_packet_timeout(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let packet: SwapPacket = from_json(&msg.packet.data)?;
+    let resent = resend_packet(deps.storage, &env, &packet)?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("method", "ibc_packet_timeout")
+        .add_attribute("packet", packet.kind())
+        .add_attribute("swap_id", packet.swap_id())
+        .add_attribute("resent", resent.is_some().to_string())
+        .add_messages(resent))
+}
+
+fn resend_packet(
+    storage: &dyn Storage,
+    env: &Env,
+    packet: &SwapPacket,
+) -> StdResult<Option<IbcMsg>> {
+    let swap = match swaps().may_load(storage, packet.swap_id())? {
+        Some(swap) => swap,
+        None => return Ok(None),
+    };
+
+    let current_time = env.block.time.seconds();
+    let relevant = match packet {
+        // A lock that was settled in the meantime must not be announced anymore
+        SwapPacket::Lock { .. } => {
+            swap.state == SwapState::Initiated && current_time < swap.timelock
+        }
+        // The counterpart swap can be completed until it expires
+        SwapPacket::SecretRevealed { .. } | SwapPacket::Refunded { .. } => {
+            current_time < swap.counterpart.timelock.unwrap_or(swap.timelock)
+        }
+    };
+    if !relevant {
+        return Ok(None);
+    }
+
+    notify_counterpart(storage, env, &swap.counterpart.chain_id, packet)
+}
+
+// Builds the packet for a swap's counterpart chain, if that chain is another
+// deployment of this contract behind an open channel
+pub fn notify_counterpart(
+    storage: &dyn Storage,
+    env: &Env,
+    chain_id: &str,
+    packet: &SwapPacket,
+) -> StdResult<Option<IbcMsg>> {
+    let channel_id = match COUNTERPART_CHAINS
+        .may_load(storage, chain_id)?
+        .and_then(|chain| chain.ibc_channel)
+    {
+        Some(channel_id) if IBC_CHANNELS.has(storage, &channel_id) => channel_id,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(IbcMsg::SendPacket {
+        channel_id,
+        data: to_json_binary(packet)?,
+        timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(PACKET_LIFETIME_SECONDS)),
+    }))
+}
+
+// Picks up a lock the counterpart deployment announced before the swap existed
+pub fn apply_stored_remote_lock(
+    storage: &mut dyn Storage,
+    chain: &CounterpartChain,
+    swap: &mut AtomicSwap,
+    current_time: u64,
+) -> Result<(), ContractError> {
+    if let Some(channel_id) = &chain.ibc_channel {
+        if let Some(lock) = REMOTE_LOCKS.may_load(storage, (channel_id, &swap.hashlock))? {
+            apply_remote_lock(storage, chain, swap, &lock, current_time)?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_channel(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    if channel.order != IBC_ORDER {
+        return Err(ContractError::InvalidIbcChannel {
+            reason: "channel must be unordered".to_string(),
+        });
+    }
+    if channel.version != IBC_VERSION
+        || counterparty_version.is_some_and(|version| version != IBC_VERSION)
+    {
+        return Err(ContractError::InvalidIbcChannel {
+            reason: format!("channel version must be {}", IBC_VERSION),
+        });
+    }
+    Ok(())
+}
+
+fn receive_lock(
+    deps: DepsMut,
+    channel_id: &str,
+    hashlock: &str,
+    lock: RemoteLock,
+) -> Result<IbcReceiveResponse, ContractError> {
+    REMOTE_LOCKS.save(deps.storage, (channel_id, hashlock), &lock)?;
+
+    let mut matched = 0;
+    for (mut swap, chain) in swaps_on_channel(deps.storage, channel_id, hashlock)? {
+        if apply_remote_lock(deps.storage, &chain, &mut swap, &lock, lock.received_at)? {
+            swaps().save(deps.storage, &swap.swap_id, &swap)?;
+            matched += 1;
+        }
+    }
+
+    Ok(IbcReceiveResponse::new()
+        .set_ack(ack_success()?)
+        .add_attribute("method", "ibc_receive_lock")
+        .add_attribute("counterpart_swap_id", lock.swap_id)
+        .add_attribute("matched_swaps", matched.to_string()))
+}
+
+fn receive_secret(
+    deps: DepsMut,
+    env: Env,
+    channel_id: &str,
+    counterpart_swap_id: String,
+    hashlock: String,
+    secret: String,
+) -> Result<IbcReceiveResponse, ContractError> {
+    if !verify_secret(&hashlock, &secret)? {
+        return Err(ContractError::InvalidSecret {
+            expected: hashlock,
+            actual: hex::encode(Sha256::digest(secret.as_bytes())),
+        });
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+    ensure_not_tripped(deps.storage)?;
+    let breaker = CIRCUIT_BREAKER_CONFIG.load(deps.storage)?;
+
+    let current_time = env.block.time.seconds();
+    let mut response = IbcReceiveResponse::new()
+        .set_ack(ack_success()?)
+        .add_attribute("method", "ibc_receive_secret")
+        .add_attribute("counterpart_swap_id", &counterpart_swap_id);
+
+    // Other swaps on the hashlock may be settled or expired, they are skipped
+    let mut completed = 0;
+    for (mut swap, _) in swaps_on_channel(deps.storage, channel_id, &hashlock)? {
+        if !accepts_counterpart(&swap, &counterpart_swap_id) {
+            continue;
+        }
+
+        // A counterpart swap that was completed must have been locked
+        if swap.attestation.status == AttestationStatus::Unattested {
+            swap.attestation.status = AttestationStatus::LockAttested;
+            swap.attestation.attested_at = Some(current_time);
+        }
+        if ensure_completable(deps.storage, &swap, current_time).is_err()
+            || link_counterpart_swap_id(deps.storage, &mut swap, counterpart_swap_id.clone())
+                .is_err()
+        {
+            continue;
+        }
+
+        let (messages, events) = settle_completion(
+            deps.storage,
+            &env,
+            &breaker,
+            &mut swap,
+            secret.clone(),
+            None,
+            None,
+        )?;
+
+        response = response
+            .add_submessages(messages)
+            .add_events(events)
+            .add_attribute("completed_swap_id", swap.swap_id);
+        completed += 1;
+    }
+
+    if completed == 0 {
+        return Err(ContractError::NoCompletableSwap { hashlock });
+    }
+    Ok(response)
+}
+
+fn receive_refund(
+    deps: DepsMut,
+    env: Env,
+    channel_id: &str,
+    counterpart_swap_id: String,
+    hashlock: String,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let key = (channel_id, hashlock.as_str());
+    if let Some(lock) = REMOTE_LOCKS.may_load(deps.storage, key)? {
+        if lock.swap_id == counterpart_swap_id {
+            REMOTE_LOCKS.remove(deps.storage, key);
+        }
+    }
+
+    let current_time = env.block.time.seconds();
+    let mut matched = 0;
+    for (mut swap, _) in swaps_on_channel(deps.storage, channel_id, &hashlock)? {
+        if swap.attestation.status == AttestationStatus::RefundAttested
+            || !accepts_counterpart(&swap, &counterpart_swap_id)
+        {
+            continue;
+        }
+
+        swap.attestation.status = AttestationStatus::RefundAttested;
+        swap.attestation.attested_at = Some(current_time);
+        swaps().save(deps.storage, &swap.swap_id, &swap)?;
+        matched += 1;
+    }
+
+    Ok(IbcReceiveResponse::new()
+        .set_ack(ack_success()?)
+        .add_attribute("method", "ibc_receive_refund")
+        .add_attribute("counterpart_swap_id", counterpart_swap_id)
+        .add_attribute("matched_swaps", matched.to_string()))
+}
+
+// Matches a remote lock to an initiated swap paying the announced recipient.
+// The lock must agree with whatever the swap declared about its counterpart,
+// and the timelocks must leave the margin the chain requires.
+fn apply_remote_lock(
+    storage: &mut dyn Storage,
+    chain: &CounterpartChain,
+    swap: &mut AtomicSwap,
+    lock: &RemoteLock,
+    current_time: u64,
+) -> Result<bool, ContractError> {
+    let counterpart = &swap.counterpart;
+    if swap.state != SwapState::Initiated
+        || swap.attestation.status == AttestationStatus::RefundAttested
+        || swap.participant.as_str() != lock.recipient
+        || counterpart
+            .expected_amount
+            .is_some_and(|amount| amount != lock.amount.amount)
+        || counterpart
+            .asset
+            .as_deref()
+            .is_some_and(|asset| asset != lock.amount.denom)
+        || counterpart
+            .timelock
+            .is_some_and(|timelock| timelock != lock.timelock)
+        || validate_timelock_ordering(chain, &counterpart.role, swap.timelock, Some(lock.timelock))
+            .is_err()
+    {
+        return Ok(false);
+    }
+
+    link_counterpart_swap_id(storage, swap, lock.swap_id.clone())?;
+    swap.attestation.status = AttestationStatus::LockAttested;
+    swap.attestation.attested_at = Some(current_time);
+    Ok(true)
+}
+
+fn swaps_on_channel(
+    storage: &dyn Storage,
+    channel_id: &str,
+    hashlock: &str,
+) -> StdResult<Vec<(AtomicSwap, CounterpartChain)>> {
+    let locked = swaps()
+        .idx
+        .hashlock
+        .prefix(hashlock.to_string())
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut matches = vec![];
+    for (_, swap) in locked {
+        if let Some(chain) = COUNTERPART_CHAINS.may_load(storage, &swap.counterpart.chain_id)? {
+            if chain.ibc_channel.as_deref() == Some(channel_id) {
+                matches.push((swap, chain));
+            }
+        }
+    }
+    Ok(matches)
+}
+
+// Unlinked swaps accept any counterpart swap, linked ones only their own
+fn accepts_counterpart(swap: &AtomicSwap, counterpart_swap_id: &str) -> bool {
+    swap.counterpart
+        .counterpart_swap_id
+        .as_deref()
+        .is_none_or(|linked| linked == counterpart_swap_id)
+}
@@ -0,0 +1,60 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_json_binary, Binary, Coin, IbcOrder, StdResult};
+
+pub const IBC_VERSION: &str = "atomic-swap-1";
+pub const IBC_ORDER: IbcOrder = IbcOrder::Unordered;
+
+// Packets exchanged between two deployments of this contract. Swaps on both
+// sides are matched by hashlock on the channel their counterpart chain uses.
+#[cw_serde]
+pub enum SwapPacket {
+    // A swap was locked on the sending chain, paying `recipient` on the receiving chain
+    Lock {
+        swap_id: String,
+        hashlock: String,
+        amount: Coin,
+        timelock: u64,
+        recipient: String,
+    },
+    // A swap was completed on the sending chain, revealing the secret
+    SecretRevealed {
+        swap_id: String,
+        hashlock: String,
+        secret: String,
+    },
+    // A swap was refunded on the sending chain
+    Refunded {
+        swap_id: String,
+        hashlock: String,
+    },
+}
+
+// Same shape as the ibc-go acknowledgement, so error acks written by the chain
+// when the receiving contract fails parse as well
+#[cw_serde]
+pub enum SwapPacketAck {
+    Result(Binary),
+    Error(String),
+}
+
+pub fn ack_success() -> StdResult<Binary> {
+    to_json_binary(&SwapPacketAck::Result(Binary::from(vec![1])))
+}
+
+impl SwapPacket {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SwapPacket::Lock { .. } => "lock",
+            SwapPacket::SecretRevealed { .. } => "secret_revealed",
+            SwapPacket::Refunded { .. } => "refunded",
+        }
+    }
+
+    pub fn swap_id(&self) -> &str {
+        match self {
+            SwapPacket::Lock { swap_id, .. }
+            | SwapPacket::SecretRevealed { swap_id, .. }
+            | SwapPacket::Refunded { swap_id, .. } => swap_id,
+        }
+    }
+}
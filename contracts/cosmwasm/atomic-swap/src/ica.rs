@@ -0,0 +1,452 @@
+use cosmwasm_std::{
+    from_json, to_json_vec, Binary, Coin, CosmosMsg, Env, StdError, StdResult, Storage, SubMsg,
+    SubMsgResponse,
+};
+use serde::Deserialize;
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, RequestPacket};
+use crate::proto::{
+    decode_fields, encode_any, encode_bytes, encode_coin, encode_string, encode_uint64, find_bytes,
+    find_varint,
+};
+use crate::state::{
+    IcaAccount, IcaTx, RemoteAction, RemoteLeg, RemoteLegStatus, ICA_ACCOUNTS, ICA_TXS,
+    REMOTE_LEGS, STAGED_ICA_TXS,
+};
+
+pub const ICA_SUBMIT_REPLY_ID: u64 = 2;
+
+const MSG_REGISTER_ICA_TYPE_URL: &str = "/neutron.interchaintxs.v1.MsgRegisterInterchainAccount";
+const MSG_SUBMIT_TX_TYPE_URL: &str = "/neutron.interchaintxs.v1.MsgSubmitTx";
+const MSG_EXECUTE_CONTRACT_TYPE_URL: &str = "/cosmwasm.wasm.v1.MsgExecuteContract";
+
+// The ICA host reports the account address in the channel version metadata
+#[derive(Deserialize)]
+struct IcaMetadata {
+    address: String,
+}
+
+pub fn validate_ica_account(account: &IcaAccount) -> Result<(), ContractError> {
+    let reason = if account.ica_id.is_empty()
+        || !account
+            .ica_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        "ica_id must be alphanumeric"
+    } else if !account.connection_id.starts_with("connection-") {
+        "connection_id must be a connection identifier"
+    } else if account.remote_contract.trim().is_empty() {
+        "remote_contract must be non-empty"
+    } else if account.timeout_seconds == 0 {
+        "timeout_seconds must be greater than 0"
+    } else {
+        return Ok(());
+    };
+
+    Err(ContractError::InvalidIcaAccount {
+        reason: reason.to_string(),
+    })
+}
+
+// Protobuf encoding of neutron.interchaintxs.v1.MsgRegisterInterchainAccount
+pub fn register_msg(env: &Env, account: &IcaAccount, register_fee: &[Coin]) -> CosmosMsg {
+    let mut msg = vec![];
+    encode_string(&mut msg, 1, env.contract.address.as_str());
+    encode_string(&mut msg, 2, &account.connection_id);
+    encode_string(&mut msg, 3, &account.ica_id);
+    for coin in register_fee {
+        encode_coin(&mut msg, 4, coin);
+    }
+
+    CosmosMsg::Stargate {
+        type_url: MSG_REGISTER_ICA_TYPE_URL.to_string(),
+        value: Binary::from(msg),
+    }
+}
+
+// Checks the leg can take the action. Nothing can be submitted while a
+// transaction is in flight, and a failed action can only be retried as is.
+pub fn ensure_action_allowed(
+    leg: Option<&RemoteLeg>,
+    action: &RemoteAction,
+) -> Result<(), ContractError> {
+    let allowed = match (leg, action) {
+        (None, RemoteAction::Lock) | (None, RemoteAction::Complete) => true,
+        (None, RemoteAction::Refund) => false,
+        (Some(leg), _) if leg.status == RemoteLegStatus::Failed => leg.action == *action,
+        (Some(leg), RemoteAction::Refund) => leg.status == RemoteLegStatus::Locked,
+        (Some(_), _) => false,
+    };
+
+    if !allowed {
+        return Err(ContractError::InvalidRemoteLeg {
+            reason: format!("{:?} is not allowed in the leg's current state", action),
+        });
+    }
+    Ok(())
+}
+
+// Executes a message on the remote atomic-swap deployment through the
+// interchain account. The transaction is staged until the reply reports the
+// packet sequence the sudo callback will refer to.
+pub fn submit_remote(
+    storage: &mut dyn Storage,
+    env: &Env,
+    account: &IcaAccount,
+    leg: RemoteLeg,
+    swap_id: &str,
+    remote_msg: &ExecuteMsg,
+    funds: &[Coin],
+) -> Result<SubMsg, ContractError> {
+    let ica_address =
+        account
+            .address
+            .as_deref()
+            .ok_or_else(|| ContractError::InvalidRemoteLeg {
+                reason: format!("interchain account {} is not open", account.ica_id),
+            })?;
+
+    let mut staged = STAGED_ICA_TXS.may_load(storage)?.unwrap_or_default();
+    staged.push(IcaTx {
+        swap_id: swap_id.to_string(),
+        action: leg.action.clone(),
+    });
+    STAGED_ICA_TXS.save(storage, &staged)?;
+    REMOTE_LEGS.save(storage, swap_id, &leg)?;
+
+    let mut execute = vec![];
+    encode_string(&mut execute, 1, ica_address);
+    encode_string(&mut execute, 2, &account.remote_contract);
+    encode_bytes(&mut execute, 3, &to_json_vec(remote_msg)?);
+    for coin in funds {
+        encode_coin(&mut execute, 5, coin);
+    }
+
+    let mut fee = vec![];
+    for coin in &account.ack_fee {
+        encode_coin(&mut fee, 2, coin);
+    }
+    for coin in &account.timeout_fee {
+        encode_coin(&mut fee, 3, coin);
+    }
+
+    let mut msg = vec![];
+    encode_string(&mut msg, 1, env.contract.address.as_str());
+    encode_string(&mut msg, 2, &account.ica_id);
+    encode_string(&mut msg, 3, &account.connection_id);
+    encode_any(&mut msg, 4, MSG_EXECUTE_CONTRACT_TYPE_URL, &execute);
+    encode_uint64(&mut msg, 6, account.timeout_seconds);
+    encode_bytes(&mut msg, 7, &fee);
+
+    Ok(SubMsg::reply_on_success(
+        CosmosMsg::Stargate {
+            type_url: MSG_SUBMIT_TX_TYPE_URL.to_string(),
+            value: Binary::from(msg),
+        },
+        ICA_SUBMIT_REPLY_ID,
+    ))
+}
+
+// Moves the oldest staged transaction under the (channel, sequence) from MsgSubmitTxResponse
+pub fn record_submission(storage: &mut dyn Storage, response: SubMsgResponse) -> StdResult<IcaTx> {
+    let mut staged = STAGED_ICA_TXS.may_load(storage)?.unwrap_or_default();
+    if staged.is_empty() {
+        return Err(StdError::generic_err("no staged interchain transaction"));
+    }
+    let tx = staged.remove(0);
+    STAGED_ICA_TXS.save(storage, &staged)?;
+
+    let fields = response
+        .data
+        .as_ref()
+        .and_then(|data| decode_fields(data.as_slice()))
+        .ok_or_else(|| StdError::generic_err("missing MsgSubmitTxResponse"))?;
+    let sequence = find_varint(&fields, 1);
+    let channel = find_bytes(&fields, 2).and_then(|channel| std::str::from_utf8(channel).ok());
+    match (sequence, channel) {
+        (Some(sequence), Some(channel)) => ICA_TXS.save(storage, (channel, sequence), &tx)?,
+        _ => return Err(StdError::generic_err("malformed MsgSubmitTxResponse")),
+    }
+
+    Ok(tx)
+}
+
+// Stores the channel and address once the host chain opened the account
+pub fn open_account(
+    storage: &mut dyn Storage,
+    port_id: &str,
+    channel_id: &str,
+    counterparty_version: &str,
+) -> Result<IcaAccount, ContractError> {
+    // Controller ports are named icacontroller-{contract}.{ica_id}
+    let ica_id = port_id
+        .rsplit_once('.')
+        .map(|(_, ica_id)| ica_id)
+        .ok_or_else(|| ContractError::InvalidRemoteLeg {
+            reason: format!("unexpected port {}", port_id),
+        })?;
+    let metadata: IcaMetadata = from_json(counterparty_version.as_bytes())?;
+
+    let mut account = ICA_ACCOUNTS.load(storage, ica_id)?;
+    account.channel_id = Some(channel_id.to_string());
+    account.address = Some(metadata.address);
+    ICA_ACCOUNTS.save(storage, ica_id, &account)?;
+
+    Ok(account)
+}
+
+// Applies the outcome of an interchain transaction to its remote leg.
+// `result` holds the acknowledgement data or the error reported by the host.
+pub fn settle_ica_tx(
+    storage: &mut dyn Storage,
+    request: &RequestPacket,
+    result: Result<&Binary, String>,
+) -> StdResult<Option<(IcaTx, RemoteLeg)>> {
+    let key = match (&request.source_channel, request.sequence) {
+        (Some(channel), Some(sequence)) => (channel.as_str(), sequence),
+        _ => return Ok(None),
+    };
+    let tx = match ICA_TXS.may_load(storage, key)? {
+        Some(tx) => tx,
+        None => return Ok(None),
+    };
+    ICA_TXS.remove(storage, key);
+
+    let mut leg = REMOTE_LEGS.load(storage, &tx.swap_id)?;
+    match result {
+        Ok(data) => {
+            leg.status = match tx.action {
+                RemoteAction::Lock => RemoteLegStatus::Locked,
+                RemoteAction::Complete => RemoteLegStatus::Completed,
+                RemoteAction::Refund => RemoteLegStatus::Refunded,
+            };
+            leg.error = None;
+            if tx.action == RemoteAction::Lock {
+                leg.remote_swap_id = decode_remote_swap_id(data.as_slice());
+            }
+        }
+        Err(error) => {
+            leg.status = RemoteLegStatus::Failed;
+            leg.error = Some(error);
+        }
+    }
+    REMOTE_LEGS.save(storage, &tx.swap_id, &leg)?;
+
+    Ok(Some((tx, leg)))
+}
+
+// The remote InitiateSwap returns the swap id as its data. The acknowledgement
+// is a TxMsgData, holding it in msg_responses on SDK 0.46+ and in data before.
+fn decode_remote_swap_id(ack: &[u8]) -> Option<String> {
+    let tx_msg_data = decode_fields(ack)?;
+    let response = match find_bytes(&tx_msg_data, 2) {
+        Some(any) => find_bytes(&decode_fields(any)?, 2)?,
+        None => find_bytes(&decode_fields(find_bytes(&tx_msg_data, 1)?)?, 2)?,
+    };
+    let data = find_bytes(&decode_fields(response)?, 1)?;
+    from_json(data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+
+    fn account() -> IcaAccount {
+        IcaAccount {
+            ica_id: "alice".to_string(),
+            chain_id: "chain-b".to_string(),
+            connection_id: "connection-0".to_string(),
+            remote_contract: "cosmos1remote".to_string(),
+            ack_fee: vec![],
+            timeout_fee: vec![],
+            timeout_seconds: 600,
+            channel_id: None,
+            address: None,
+        }
+    }
+
+    fn leg(action: RemoteAction, status: RemoteLegStatus) -> RemoteLeg {
+        RemoteLeg {
+            ica_id: "alice".to_string(),
+            remote_swap_id: None,
+            action,
+            status,
+            error: None,
+        }
+    }
+
+    fn request(sequence: u64) -> RequestPacket {
+        RequestPacket {
+            sequence: Some(sequence),
+            source_port: Some("icacontroller-contract.alice".to_string()),
+            source_channel: Some("channel-9".to_string()),
+            destination_port: Some("icahost".to_string()),
+            destination_channel: Some("channel-3".to_string()),
+            data: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+        }
+    }
+
+    // Opens the account and submits a lock for `swap-1` as packet `sequence`
+    fn submit_lock(storage: &mut MockStorage, sequence: u64) {
+        ICA_ACCOUNTS.save(storage, "alice", &account()).unwrap();
+        let account = open_account(
+            storage,
+            "icacontroller-contract.alice",
+            "channel-9",
+            r#"{"version":"ics27-1","address":"cosmos1ica"}"#,
+        )
+        .unwrap();
+        let msg = ExecuteMsg::RefundSwap {
+            swap_id: "remote".to_string(),
+        };
+        let leg = leg(RemoteAction::Lock, RemoteLegStatus::Pending);
+        submit_remote(storage, &mock_env(), &account, leg, "swap-1", &msg, &[]).unwrap();
+
+        let mut data = vec![];
+        encode_uint64(&mut data, 1, sequence);
+        encode_string(&mut data, 2, "channel-9");
+        let response = SubMsgResponse {
+            events: vec![],
+            data: Some(Binary::from(data)),
+        };
+        record_submission(storage, response).unwrap();
+    }
+
+    #[test]
+    fn accounts_are_validated() {
+        assert!(validate_ica_account(&account()).is_ok());
+
+        let mut invalid = account();
+        invalid.ica_id = "alice.bob".to_string();
+        assert!(validate_ica_account(&invalid).is_err());
+        let mut invalid = account();
+        invalid.connection_id = "channel-0".to_string();
+        assert!(validate_ica_account(&invalid).is_err());
+        let mut invalid = account();
+        invalid.timeout_seconds = 0;
+        assert!(validate_ica_account(&invalid).is_err());
+    }
+
+    #[test]
+    fn actions_follow_the_leg() {
+        use RemoteAction::*;
+        use RemoteLegStatus::*;
+
+        assert!(ensure_action_allowed(None, &Lock).is_ok());
+        assert!(ensure_action_allowed(None, &Complete).is_ok());
+        assert!(ensure_action_allowed(None, &Refund).is_err());
+        assert!(ensure_action_allowed(Some(&leg(Lock, Pending)), &Lock).is_err());
+        assert!(ensure_action_allowed(Some(&leg(Lock, Locked)), &Refund).is_ok());
+        assert!(ensure_action_allowed(Some(&leg(Lock, Locked)), &Lock).is_err());
+        assert!(ensure_action_allowed(Some(&leg(Lock, Failed)), &Lock).is_ok());
+        assert!(ensure_action_allowed(Some(&leg(Lock, Failed)), &Refund).is_err());
+        assert!(ensure_action_allowed(Some(&leg(Refund, Refunded)), &Refund).is_err());
+    }
+
+    #[test]
+    fn submissions_wait_for_the_open_account() {
+        let mut storage = MockStorage::new();
+        let msg = ExecuteMsg::RefundSwap {
+            swap_id: "remote".to_string(),
+        };
+        let leg = leg(RemoteAction::Lock, RemoteLegStatus::Pending);
+        let err = submit_remote(
+            &mut storage,
+            &mock_env(),
+            &account(),
+            leg,
+            "swap-1",
+            &msg,
+            &[],
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRemoteLeg { .. }));
+
+        let response = SubMsgResponse {
+            events: vec![],
+            data: None,
+        };
+        assert!(record_submission(&mut storage, response).is_err());
+    }
+
+    #[test]
+    fn acknowledgement_locks_the_leg() {
+        let mut storage = MockStorage::new();
+        submit_lock(&mut storage, 7);
+
+        // SDK 0.46+ acknowledgements carry the response in msg_responses
+        let mut response = vec![];
+        encode_bytes(&mut response, 1, br#""remote-swap""#);
+        let mut ack = vec![];
+        encode_any(
+            &mut ack,
+            2,
+            "/cosmwasm.wasm.v1.MsgExecuteContractResponse",
+            &response,
+        );
+
+        let (tx, leg) = settle_ica_tx(&mut storage, &request(7), Ok(&Binary::from(ack)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.swap_id, "swap-1");
+        assert_eq!(leg.status, RemoteLegStatus::Locked);
+        assert_eq!(leg.remote_swap_id.as_deref(), Some("remote-swap"));
+
+        // The packet is settled once
+        let ack = Binary::default();
+        assert!(settle_ica_tx(&mut storage, &request(7), Ok(&ack))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn legacy_acknowledgement_locks_the_leg() {
+        let mut storage = MockStorage::new();
+        submit_lock(&mut storage, 1);
+
+        // Older hosts put MsgData { msg_type, data } in the data field
+        let mut response = vec![];
+        encode_bytes(&mut response, 1, br#""remote-swap""#);
+        let mut msg_data = vec![];
+        encode_string(&mut msg_data, 1, "/cosmwasm.wasm.v1.MsgExecuteContract");
+        encode_bytes(&mut msg_data, 2, &response);
+        let mut ack = vec![];
+        encode_bytes(&mut ack, 1, &msg_data);
+
+        let (_, leg) = settle_ica_tx(&mut storage, &request(1), Ok(&Binary::from(ack)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(leg.remote_swap_id.as_deref(), Some("remote-swap"));
+    }
+
+    #[test]
+    fn errors_fail_the_leg() {
+        let mut storage = MockStorage::new();
+        submit_lock(&mut storage, 2);
+
+        let (_, leg) = settle_ica_tx(&mut storage, &request(2), Err("timeout".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(leg.status, RemoteLegStatus::Failed);
+        assert_eq!(leg.error.as_deref(), Some("timeout"));
+        assert_eq!(REMOTE_LEGS.load(&storage, "swap-1").unwrap(), leg);
+    }
+
+    #[test]
+    fn unknown_packets_are_ignored() {
+        let mut storage = MockStorage::new();
+        submit_lock(&mut storage, 3);
+
+        let result = settle_ica_tx(&mut storage, &request(4), Err("timeout".to_string()));
+        assert!(result.unwrap().is_none());
+        assert_eq!(
+            REMOTE_LEGS.load(&storage, "swap-1").unwrap().status,
+            RemoteLegStatus::Pending
+        );
+    }
+}
@@ -1,6 +1,26 @@
+pub mod access;
+pub mod actions;
+pub mod attestation;
+pub mod circuit_breaker;
+pub mod claims;
 pub mod contract;
+pub mod counterpart;
 pub mod error;
+pub mod eth_proof;
+pub mod events;
+pub mod hooks;
+pub mod ibc;
+pub mod ibc_msg;
+pub mod ica;
+pub mod light_client;
+pub mod limits;
+pub mod migrations;
 pub mod msg;
+pub mod payouts;
+pub mod proto;
+pub mod recipient;
 pub mod state;
+pub mod transfer;
+pub mod validation;
 
-pub use crate::error::ContractError;
\ No newline at end of file
+pub use crate::error::ContractError;
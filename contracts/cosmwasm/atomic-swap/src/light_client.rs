@@ -0,0 +1,153 @@
+use cosmwasm_std::{Addr, Storage};
+
+use crate::attestation::load_attestor;
+use crate::counterpart::validate_timelock_ordering;
+use crate::error::ContractError;
+use crate::eth_proof::{decode_header, decode_swap_initiated, verify_receipt};
+use crate::recipient::is_valid_evm_address;
+use crate::state::{
+    AtomicSwap, ChainFamily, CounterpartChain, TrustedHeader, COUNTERPART_HEADERS, HEADER_VOTES,
+};
+
+// Adds a header to the trusted store. The admin's submission is trusted
+// directly, attestors vote and the header is trusted once the attestor
+// threshold agrees on the same hash for a block number.
+pub fn submit_header(
+    storage: &mut dyn Storage,
+    chain: &CounterpartChain,
+    header_rlp: &[u8],
+    sender: &Addr,
+    admin: &Addr,
+    current_time: u64,
+) -> Result<Option<TrustedHeader>, ContractError> {
+    if chain.address_format.family() != ChainFamily::Evm {
+        return Err(ContractError::InvalidCounterpartChain {
+            reason: "headers can only be submitted for EVM chains".to_string(),
+        });
+    }
+
+    let decoded = decode_header(header_rlp)?;
+    let header = TrustedHeader {
+        chain_id: chain.chain_id.clone(),
+        number: decoded.number,
+        hash: to_hex(&decoded.hash),
+        receipts_root: to_hex(&decoded.receipts_root),
+        timestamp: decoded.timestamp,
+        trusted_at: current_time,
+    };
+
+    let key = (chain.chain_id.as_str(), header.number);
+    if let Some(existing) = COUNTERPART_HEADERS.may_load(storage, key)? {
+        if existing.hash != header.hash {
+            return Err(ContractError::HeaderConflict {
+                chain_id: chain.chain_id.clone(),
+                number: header.number,
+            });
+        }
+        return Ok(None);
+    }
+
+    if sender != admin {
+        let attestors = load_attestor(storage, sender)?;
+        let vote_key = (chain.chain_id.as_str(), header.number, header.hash.as_str());
+        let mut votes = HEADER_VOTES
+            .may_load(storage, vote_key)?
+            .unwrap_or_default();
+        if votes.contains(sender) {
+            return Err(ContractError::HeaderAlreadyVoted {
+                chain_id: chain.chain_id.clone(),
+                number: header.number,
+            });
+        }
+        votes.push(sender.clone());
+
+        if votes.len() < attestors.threshold as usize {
+            HEADER_VOTES.save(storage, vote_key, &votes)?;
+            return Ok(None);
+        }
+        HEADER_VOTES.remove(storage, vote_key);
+    }
+
+    COUNTERPART_HEADERS.save(storage, key, &header)?;
+    Ok(Some(header))
+}
+
+// Proves the receipt against a trusted header and checks its SwapInitiated log
+// against the swap. Returns the counterpart swap id taken from the log.
+pub fn verify_lock_proof(
+    storage: &dyn Storage,
+    swap: &AtomicSwap,
+    chain: &CounterpartChain,
+    block_number: u64,
+    tx_index: u64,
+    log_index: u32,
+    receipt_proof: &[Vec<u8>],
+) -> Result<String, ContractError> {
+    let htlc_contract = chain
+        .htlc_contract
+        .as_deref()
+        .ok_or_else(|| proof_error("counterpart chain has no htlc_contract"))?;
+    let header = COUNTERPART_HEADERS
+        .may_load(storage, (chain.chain_id.as_str(), block_number))?
+        .ok_or_else(|| proof_error("block header is not trusted"))?;
+
+    let receipts_root = hex::decode(header.receipts_root.trim_start_matches("0x"))
+        .ok()
+        .and_then(|root| <[u8; 32]>::try_from(root).ok())
+        .ok_or_else(|| proof_error("malformed receipts root"))?;
+    let receipt = verify_receipt(&receipts_root, tx_index, receipt_proof)?;
+    let log = decode_swap_initiated(&receipt, log_index as usize)?;
+
+    let counterpart = &swap.counterpart;
+    if !to_hex(&log.contract).eq_ignore_ascii_case(htlc_contract) {
+        return Err(proof_error("log was not emitted by the counterpart HTLC"));
+    }
+    if !hex::encode(log.hashlock).eq_ignore_ascii_case(&swap.hashlock) {
+        return Err(proof_error("hashlock does not match the swap"));
+    }
+    if !to_hex(&log.participant).eq_ignore_ascii_case(&counterpart.recipient) {
+        return Err(proof_error(
+            "participant does not match the counterpart recipient",
+        ));
+    }
+
+    let expected_amount = counterpart
+        .expected_amount
+        .ok_or_else(|| proof_error("swap does not declare an expected counterpart amount"))?;
+    if log.amount != expected_amount {
+        return Err(proof_error(
+            "amount does not match the expected counterpart amount",
+        ));
+    }
+
+    // Only token addresses can be compared, symbolic assets such as "ETH" are not checked
+    if let Some(asset) = counterpart.asset.as_deref() {
+        if is_valid_evm_address(asset) && !to_hex(&log.token).eq_ignore_ascii_case(asset) {
+            return Err(proof_error("token does not match the counterpart asset"));
+        }
+    }
+    match counterpart.timelock {
+        Some(timelock) if timelock != log.timelock => {
+            return Err(proof_error(
+                "timelock does not match the counterpart timelock",
+            ));
+        }
+        Some(_) => {}
+        // An undeclared timelock is only known now and must still leave the margin
+        None => {
+            validate_timelock_ordering(chain, &counterpart.role, swap.timelock, Some(log.timelock))?
+        }
+    }
+
+    Ok(to_hex(&log.swap_id))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn proof_error(reason: &str) -> ContractError {
+    ContractError::InvalidProof {
+        reason: reason.to_string(),
+    }
+}
@@ -0,0 +1,335 @@
+use cosmwasm_std::{Addr, Coin, StdResult, Storage, Uint128};
+
+use crate::error::ContractError;
+use crate::state::{
+    OutflowEpoch, OutflowLimits, SwapLimits, VolumeBucket, VolumeWindow, ACTIVE_SWAPS_BY_INITIATOR,
+    ACTIVE_SWAPS_BY_PARTICIPANT, OUTFLOW_BY_DENOM, VOLUME_BY_ADDRESS,
+};
+
+// The rolling volume window is summed over this many buckets, so volume stops
+// counting at most one bucket later than the window length
+pub const VOLUME_WINDOW_BUCKETS: u64 = 24;
+
+pub fn default_limits() -> SwapLimits {
+    SwapLimits {
+        max_active_swaps_per_initiator: None,
+        max_active_swaps_per_participant: None,
+        max_volume_per_window: vec![],
+        volume_window_seconds: 24 * 3600,
+        min_swap_amount: vec![],
+    }
+}
+
+pub fn default_outflow_limits() -> OutflowLimits {
+    OutflowLimits {
+        epoch_seconds: 24 * 3600,
+        caps: vec![],
+    }
+}
+
+pub fn validate_outflow_limits(limits: &OutflowLimits) -> Result<(), ContractError> {
+    if !limits.caps.is_empty() && limits.epoch_seconds == 0 {
+        return Err(ContractError::InvalidConfigOutflowEpoch {});
+    }
+    Ok(())
+}
+
+pub fn validate_limits(limits: &SwapLimits) -> Result<(), ContractError> {
+    if !limits.max_volume_per_window.is_empty() && limits.volume_window_seconds == 0 {
+        return Err(ContractError::InvalidConfigVolumeWindow {});
+    }
+    Ok(())
+}
+
+// Checks every limit for a new swap, then records it against the counters
+pub fn record_initiation(
+    storage: &mut dyn Storage,
+    limits: &SwapLimits,
+    initiator: &Addr,
+    participant: &Addr,
+    amount: &Coin,
+    current_time: u64,
+) -> Result<(), ContractError> {
+    if let Some(minimum) = find_amount(&limits.min_swap_amount, &amount.denom) {
+        if amount.amount < minimum {
+            return Err(ContractError::SwapAmountTooLow {
+                minimum: Coin::new(minimum.u128(), &amount.denom).to_string(),
+            });
+        }
+    }
+
+    let initiator_active = ACTIVE_SWAPS_BY_INITIATOR
+        .may_load(storage, initiator)?
+        .unwrap_or_default();
+    if let Some(limit) = limits.max_active_swaps_per_initiator {
+        if initiator_active >= limit {
+            return Err(ContractError::TooManyActiveSwaps {
+                address: initiator.to_string(),
+                limit,
+            });
+        }
+    }
+
+    let participant_active = ACTIVE_SWAPS_BY_PARTICIPANT
+        .may_load(storage, participant)?
+        .unwrap_or_default();
+    if let Some(limit) = limits.max_active_swaps_per_participant {
+        if participant_active >= limit {
+            return Err(ContractError::TooManyActiveSwaps {
+                address: participant.to_string(),
+                limit,
+            });
+        }
+    }
+
+    let mut window = current_window(storage, limits, initiator, &amount.denom, current_time)?;
+    if let Some(cap) = find_amount(&limits.max_volume_per_window, &amount.denom) {
+        let remaining = cap.saturating_sub(window_volume(&window));
+        if amount.amount > remaining {
+            return Err(ContractError::VolumeLimitExceeded {
+                address: initiator.to_string(),
+                remaining: Coin::new(remaining.u128(), &amount.denom).to_string(),
+            });
+        }
+    }
+    let start = current_time - current_time % bucket_seconds(limits);
+    match window.buckets.last_mut() {
+        Some(bucket) if bucket.start == start => bucket.volume += amount.amount,
+        _ => window.buckets.push(VolumeBucket {
+            start,
+            volume: amount.amount,
+        }),
+    }
+
+    VOLUME_BY_ADDRESS.save(storage, (initiator, &amount.denom), &window)?;
+    ACTIVE_SWAPS_BY_INITIATOR.save(storage, initiator, &(initiator_active + 1))?;
+    ACTIVE_SWAPS_BY_PARTICIPANT.save(storage, participant, &(participant_active + 1))?;
+
+    Ok(())
+}
+
+// Frees the active swap slots of both parties once a swap is completed or refunded
+pub fn release_active(
+    storage: &mut dyn Storage,
+    initiator: &Addr,
+    participant: &Addr,
+) -> StdResult<()> {
+    // Swaps created before the counters existed were never counted, hence the saturation
+    ACTIVE_SWAPS_BY_INITIATOR.update(storage, initiator, |count| -> StdResult<_> {
+        Ok(count.unwrap_or_default().saturating_sub(1))
+    })?;
+    ACTIVE_SWAPS_BY_PARTICIPANT.update(storage, participant, |count| -> StdResult<_> {
+        Ok(count.unwrap_or_default().saturating_sub(1))
+    })?;
+    Ok(())
+}
+
+// Counts coins leaving escrow towards the epoch cap of their denom.
+// Ordinary refunds to the initiator are not routed through here.
+pub fn record_outflow(
+    storage: &mut dyn Storage,
+    limits: &OutflowLimits,
+    coins: &[Coin],
+    current_time: u64,
+) -> Result<(), ContractError> {
+    for coin in coins {
+        let mut epoch = current_epoch(storage, limits, &coin.denom, current_time)?;
+        if let Some(cap) = find_amount(&limits.caps, &coin.denom) {
+            let remaining = cap.saturating_sub(epoch.released);
+            if coin.amount > remaining {
+                return Err(ContractError::OutflowLimitExceeded {
+                    denom: coin.denom.clone(),
+                    remaining: Coin::new(remaining.u128(), &coin.denom).to_string(),
+                });
+            }
+        }
+        epoch.released += coin.amount;
+        OUTFLOW_BY_DENOM.save(storage, &coin.denom, &epoch)?;
+    }
+    Ok(())
+}
+
+pub fn current_epoch(
+    storage: &dyn Storage,
+    limits: &OutflowLimits,
+    denom: &str,
+    current_time: u64,
+) -> StdResult<OutflowEpoch> {
+    let epoch = current_time
+        .checked_div(limits.epoch_seconds)
+        .unwrap_or_default();
+    Ok(match OUTFLOW_BY_DENOM.may_load(storage, denom)? {
+        Some(usage) if usage.epoch == epoch => usage,
+        _ => OutflowEpoch {
+            epoch,
+            released: Uint128::zero(),
+        },
+    })
+}
+
+// Loads the volume of an address in the window ending now, without the
+// buckets that ended before it started
+pub fn current_window(
+    storage: &dyn Storage,
+    limits: &SwapLimits,
+    address: &Addr,
+    denom: &str,
+    current_time: u64,
+) -> StdResult<VolumeWindow> {
+    let mut window = VOLUME_BY_ADDRESS
+        .may_load(storage, (address, denom))?
+        .unwrap_or(VolumeWindow { buckets: vec![] });
+    let window_start = current_time.saturating_sub(limits.volume_window_seconds);
+    let bucket_seconds = bucket_seconds(limits);
+    window
+        .buckets
+        .retain(|bucket| bucket.start + bucket_seconds > window_start);
+    Ok(window)
+}
+
+pub fn window_volume(window: &VolumeWindow) -> Uint128 {
+    window.buckets.iter().map(|bucket| bucket.volume).sum()
+}
+
+// When the oldest bucket of the window stops counting
+pub fn volume_frees_at(limits: &SwapLimits, window: &VolumeWindow) -> Option<u64> {
+    window
+        .buckets
+        .first()
+        .map(|bucket| bucket.start + bucket_seconds(limits) + limits.volume_window_seconds)
+}
+
+fn bucket_seconds(limits: &SwapLimits) -> u64 {
+    limits
+        .volume_window_seconds
+        .div_ceil(VOLUME_WINDOW_BUCKETS)
+        .max(1)
+}
+
+pub fn find_amount(amounts: &[Coin], denom: &str) -> Option<Uint128> {
+    amounts
+        .iter()
+        .find(|coin| coin.denom == denom)
+        .map(|coin| coin.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::coin;
+    use cosmwasm_std::testing::MockStorage;
+
+    const DAY: u64 = 24 * 3600;
+
+    fn capped(cap: u128) -> OutflowLimits {
+        OutflowLimits {
+            epoch_seconds: DAY,
+            caps: vec![coin(cap, "uatom")],
+        }
+    }
+
+    #[test]
+    fn outflow_stops_at_the_cap() {
+        let mut storage = MockStorage::new();
+        let limits = capped(1_000);
+        let now = 10 * DAY + 5;
+
+        record_outflow(&mut storage, &limits, &[coin(600, "uatom")], now).unwrap();
+        let err = record_outflow(&mut storage, &limits, &[coin(401, "uatom")], now).unwrap_err();
+        match err {
+            ContractError::OutflowLimitExceeded { denom, remaining } => {
+                assert_eq!((denom.as_str(), remaining.as_str()), ("uatom", "400uatom"))
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        // The cap itself can be reached
+        record_outflow(&mut storage, &limits, &[coin(400, "uatom")], now).unwrap();
+        let epoch = current_epoch(&storage, &limits, "uatom", now).unwrap();
+        assert_eq!(epoch.released, Uint128::new(1_000));
+
+        // Denoms without a cap are only counted
+        record_outflow(&mut storage, &limits, &[coin(5_000, "uosmo")], now).unwrap();
+        let epoch = current_epoch(&storage, &limits, "uosmo", now).unwrap();
+        assert_eq!(epoch.released, Uint128::new(5_000));
+    }
+
+    #[test]
+    fn outflow_resets_with_the_epoch() {
+        let mut storage = MockStorage::new();
+        let limits = capped(1_000);
+        let last_second = 11 * DAY - 1;
+
+        record_outflow(&mut storage, &limits, &[coin(1_000, "uatom")], 10 * DAY).unwrap();
+        assert!(record_outflow(&mut storage, &limits, &[coin(1, "uatom")], last_second).is_err());
+
+        let next = current_epoch(&storage, &limits, "uatom", 11 * DAY).unwrap();
+        assert_eq!(next.epoch, 11);
+        assert_eq!(next.released, Uint128::zero());
+        record_outflow(&mut storage, &limits, &[coin(1_000, "uatom")], 11 * DAY).unwrap();
+    }
+
+    #[test]
+    fn changed_limits_apply_to_the_running_epoch() {
+        let mut storage = MockStorage::new();
+        let now = 10 * DAY;
+        record_outflow(&mut storage, &capped(1_000), &[coin(800, "uatom")], now).unwrap();
+
+        // What was released before a cap was lowered still counts against it
+        let lowered = capped(900);
+        assert!(record_outflow(&mut storage, &lowered, &[coin(101, "uatom")], now).is_err());
+        record_outflow(&mut storage, &lowered, &[coin(100, "uatom")], now).unwrap();
+
+        // Without a cap nothing is held back
+        let lifted = default_outflow_limits();
+        assert!(validate_outflow_limits(&lifted).is_ok());
+        record_outflow(&mut storage, &lifted, &[coin(1_000_000, "uatom")], now).unwrap();
+    }
+
+    #[test]
+    fn participants_hold_their_own_slots() {
+        let mut storage = MockStorage::new();
+        let mut limits = default_limits();
+        limits.max_active_swaps_per_participant = Some(2);
+        let (alice, bob, victim) = (
+            Addr::unchecked("alice"),
+            Addr::unchecked("bob"),
+            Addr::unchecked("victim"),
+        );
+        let amount = coin(100, "uatom");
+
+        record_initiation(&mut storage, &limits, &alice, &victim, &amount, 0).unwrap();
+        record_initiation(&mut storage, &limits, &bob, &victim, &amount, 0).unwrap();
+        let err = record_initiation(&mut storage, &limits, &alice, &victim, &amount, 0);
+        match err.unwrap_err() {
+            ContractError::TooManyActiveSwaps { address, limit } => {
+                assert_eq!((address.as_str(), limit), ("victim", 2))
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        // Being a participant elsewhere takes none of an initiator's slots
+        record_initiation(&mut storage, &limits, &victim, &alice, &amount, 0).unwrap();
+        assert_eq!(
+            ACTIVE_SWAPS_BY_INITIATOR.load(&storage, &victim).unwrap(),
+            1
+        );
+
+        release_active(&mut storage, &bob, &victim).unwrap();
+        record_initiation(&mut storage, &limits, &alice, &victim, &amount, 0).unwrap();
+
+        // Swaps from before the counters never drive them below zero
+        release_active(&mut storage, &bob, &bob).unwrap();
+        assert_eq!(ACTIVE_SWAPS_BY_PARTICIPANT.load(&storage, &bob).unwrap(), 0);
+    }
+
+    #[test]
+    fn capped_outflow_needs_an_epoch() {
+        let mut limits = capped(1_000);
+        limits.epoch_seconds = 0;
+        assert!(matches!(
+            validate_outflow_limits(&limits),
+            Err(ContractError::InvalidConfigOutflowEpoch {})
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{from_json, Addr, Coin, Order, StdError, StdResult, Storage, Uint128};
+use cw_storage_plus::Map;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+use crate::msg::SwapState;
+use crate::state::{
+    swaps, AtomicSwap, Attestation, ChainFamily, CounterpartLeg, SwapRole, COUNTERPART_CHAINS,
+};
+
+// Swap layout before counterpart legs, when only Ethereum was supported
+#[cw_serde]
+pub struct LegacyAtomicSwap {
+    pub swap_id: String,
+    pub initiator: Addr,
+    pub participant: Addr,
+    pub amount: Coin,
+    pub hashlock: String,
+    pub timelock: u64,
+    pub state: SwapState,
+    pub secret: Option<String>,
+    pub ethereum_recipient: String,
+    pub ethereum_chain_id: String,
+    pub created_at: u64,
+    pub completed_at: Option<u64>,
+    pub safety_deposit: Uint128,
+}
+
+const LEGACY_SWAPS: Map<&str, LegacyAtomicSwap> = Map::new("swaps");
+// Plain view of the swaps namespace, read without touching the indexes
+const SWAP_RECORDS: Map<&str, AtomicSwap> = Map::new("swaps");
+
+// Indexes kept by hand before swaps moved to an IndexedMap, keyed by (value, swap id)
+const LEGACY_SWAPS_BY_INITIATOR: Map<(&str, &str), bool> = Map::new("swaps_by_initiator");
+const LEGACY_SWAPS_BY_PARTICIPANT: Map<(&str, &str), bool> = Map::new("swaps_by_participant");
+const LEGACY_SWAPS_BY_HASHLOCK: Map<(&str, &str), bool> = Map::new("swaps_by_hashlock");
+
+// Only the current layout has a counterpart leg. Telling the layouts apart by
+// it keeps a record that fails to parse from passing for a legacy one.
+#[derive(Deserialize)]
+struct StoredLayout {
+    counterpart: Option<IgnoredAny>,
+}
+
+#[derive(Default)]
+pub struct MigratedSwaps {
+    pub migrated: u64,
+    pub indexed: u64,
+}
+
+// Every swap initiated before the IndexedMap wrote the hand-kept indexes, so a
+// deployment without them has nothing to migrate
+pub fn has_legacy_swaps(storage: &dyn Storage) -> bool {
+    !LEGACY_SWAPS_BY_INITIATOR.is_empty(storage)
+}
+
+// Rewrites every stored swap in the current layout, indexes it and drops its
+// hand-kept index entries. migrate() runs it over the whole store at once, so
+// the new code never meets a swap it cannot read.
+pub fn migrate_legacy_swaps(storage: &mut dyn Storage) -> StdResult<MigratedSwaps> {
+    let mut migrated = MigratedSwaps::default();
+    if !has_legacy_swaps(storage) {
+        return Ok(migrated);
+    }
+
+    // Raw keys, as typed ones would parse every record in the legacy layout
+    let swap_ids = SWAP_RECORDS
+        .keys_raw(storage, None, None, Order::Ascending)
+        .map(|key| String::from_utf8(key).map_err(StdError::from))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for swap_id in &swap_ids {
+        let record = storage
+            .get(&SWAP_RECORDS.key(swap_id))
+            .ok_or_else(|| StdError::not_found(swap_id))?;
+        let swap = match from_json::<StoredLayout>(&record)?.counterpart {
+            Some(_) => SWAP_RECORDS.load(storage, swap_id)?,
+            None => {
+                migrated.migrated += 1;
+                from_legacy(storage, LEGACY_SWAPS.load(storage, swap_id)?)?
+            }
+        };
+
+        // No old value, so the record is written and only index entries are added
+        swaps().replace(storage, swap_id, Some(&swap), None)?;
+        LEGACY_SWAPS_BY_INITIATOR.remove(storage, (swap.initiator.as_str(), swap_id));
+        LEGACY_SWAPS_BY_PARTICIPANT.remove(storage, (swap.participant.as_str(), swap_id));
+        LEGACY_SWAPS_BY_HASHLOCK.remove(storage, (&swap.hashlock, swap_id));
+        migrated.indexed += 1;
+    }
+
+    Ok(migrated)
+}
+
+// Moves a swap stored with the Ethereum-only fields over to a counterpart leg
+fn from_legacy(storage: &dyn Storage, legacy: LegacyAtomicSwap) -> StdResult<AtomicSwap> {
+    let chain_family = COUNTERPART_CHAINS
+        .may_load(storage, &legacy.ethereum_chain_id)?
+        .map(|chain| chain.address_format.family())
+        .unwrap_or(ChainFamily::Evm);
+
+    Ok(AtomicSwap {
+        swap_id: legacy.swap_id,
+        initiator: legacy.initiator,
+        participant: legacy.participant,
+        amount: legacy.amount,
+        hashlock: legacy.hashlock,
+        timelock: legacy.timelock,
+        state: legacy.state,
+        secret: legacy.secret,
+        counterpart: CounterpartLeg {
+            chain_family,
+            chain_id: legacy.ethereum_chain_id,
+            recipient: legacy.ethereum_recipient,
+            asset: None,
+            expected_amount: None,
+            asset_decimals: None,
+            timelock: None,
+            role: SwapRole::InitiatorLeg,
+            counterpart_swap_id: None,
+            lock_tx_hash: None,
+        },
+        attestation: Attestation::default(),
+        created_at: legacy.created_at,
+        completed_at: legacy.completed_at,
+        safety_deposit: legacy.safety_deposit,
+        delivery: None,
+        action: None,
+        funder: None,
+    })
+}
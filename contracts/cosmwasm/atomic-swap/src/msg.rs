@@ -1,27 +1,68 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Coin, Uint128};
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Uint128};
+
+use crate::state::{
+    AtomicSwap, Attestation, AttestorConfig, CircuitBreakerConfig, CompletionAction,
+    CounterpartChain, CounterpartLeg, HookFunder, IbcChannelInfo, IbcDelivery, IcaAccount,
+    OutflowLimits, PendingTransfer, RemoteLeg, RemoteLock, RevealedSecret, SwapLimits, SwapRole,
+    TripReason, TrustedHeader,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub admin: Option<String>,
     pub minimum_safety_deposit: Option<Uint128>,
+    pub safety_deposit_ceiling: Option<Uint128>,
     pub min_timelock_duration: Option<u64>,
     pub max_timelock_duration: Option<u64>,
+    pub limits: Option<SwapLimits>,
+    pub outflow_limits: Option<OutflowLimits>,
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    pub counterpart_chains: Option<Vec<CounterpartChain>>,
 }
 
 #[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
+    // The funds in the amount's denom cover `amount` and the rest is the safety deposit
     InitiateSwap {
         participant: String,
         amount: Coin,
         hashlock: String,
         timelock: u64,
-        ethereum_recipient: String,
-        ethereum_chain_id: String,
+        counterpart: Option<CounterpartLegMsg>,
+        // Legacy Ethereum-only fields, still accepted when `counterpart` is omitted
+        ethereum_recipient: Option<String>,
+        ethereum_chain_id: Option<String>,
+    },
+    // Sent as the msg of an ibc-hooks wasm memo. The transferred coin, in its
+    // local IBC denom, covers `amount` and the rest is the safety deposit.
+    // `source_channel` is the channel the transfer arrived on and
+    // `original_sender` its sender on the source chain.
+    HookInitiateSwap {
+        source_channel: String,
+        original_sender: String,
+        initiator: String,
+        participant: String,
+        amount: Uint128,
+        hashlock: String,
+        timelock: u64,
+        counterpart: CounterpartLegMsg,
+    },
+    // Callable by the participant while the swap is open. Delivers the payout
+    // over IBC or calls an allowed contract with it instead of a bank send,
+    // also when the swap is completed by a relayed secret. Both None clears it.
+    SetPayoutRoute {
+        swap_id: String,
+        delivery: Option<IbcDelivery>,
+        action: Option<CompletionAction>,
     },
     CompleteSwap {
         swap_id: String,
         secret: String,
+        // Either overrides the payout route set with SetPayoutRoute
+        delivery: Option<IbcDelivery>,
+        action: Option<CompletionAction>,
     },
     RefundSwap {
         swap_id: String,
@@ -29,6 +70,8 @@ pub enum ExecuteMsg {
     UpdateConfig {
         admin: Option<String>,
         minimum_safety_deposit: Option<Uint128>,
+        // Raises or lowers the bound minimum_safety_deposit is checked against
+        safety_deposit_ceiling: Option<Uint128>,
         min_timelock_duration: Option<u64>,
         max_timelock_duration: Option<u64>,
         paused: Option<bool>,
@@ -36,6 +79,124 @@ pub enum ExecuteMsg {
     EmergencyRefund {
         swap_id: String,
     },
+    UpdateLimits {
+        limits: SwapLimits,
+    },
+    UpdateOutflowLimits {
+        limits: OutflowLimits,
+    },
+    UpdateCircuitBreaker {
+        config: CircuitBreakerConfig,
+    },
+    ResetCircuitBreaker {},
+    UpdateDenylist {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    UpdateAllowlist {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    SetAllowlistOnly {
+        enabled: bool,
+    },
+    Claim {},
+    UpsertCounterpartChain {
+        chain: CounterpartChain,
+    },
+    RemoveCounterpartChain {
+        chain_id: String,
+    },
+    UpdateRelayers {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    UpdateHooks {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    UpdateActionContracts {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    // Callable by either swap party while the swap is still open. A party's
+    // hooks are notified when the swap pays out to it.
+    UpdateSwapHooks {
+        swap_id: String,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    LinkCounterpart {
+        swap_id: String,
+        counterpart_swap_id: String,
+        lock_tx_hash: Option<String>,
+    },
+    UpdateAttestors {
+        attestors: Vec<String>,
+        threshold: u32,
+        require_lock_attestation: bool,
+    },
+    AttestCounterpartLock {
+        swap_id: String,
+    },
+    AttestCounterpartRefund {
+        swap_id: String,
+    },
+    SubmitCounterpartHeader {
+        chain_id: String,
+        // RLP encoded block header, only submit finalized blocks
+        header_rlp: Binary,
+    },
+    // Registers an interchain account on a counterpart chain, funds pay the registration fee
+    RegisterIca {
+        ica_id: String,
+        chain_id: String,
+        connection_id: String,
+        remote_contract: String,
+        ack_fee: Vec<Coin>,
+        timeout_fee: Vec<Coin>,
+        timeout_seconds: u64,
+    },
+    // Locks the other leg of a local swap on the counterpart chain from the
+    // interchain account, funded with `amount` plus `safety_deposit`.
+    IcaLockCounterpart {
+        swap_id: String,
+        ica_id: String,
+        amount: Coin,
+        safety_deposit: Uint128,
+        timelock: u64,
+    },
+    // Claims a remote swap paying the interchain account with the local swap's revealed secret
+    IcaCompleteCounterpart {
+        swap_id: String,
+        ica_id: String,
+        remote_swap_id: String,
+    },
+    // Refunds the remote leg locked by IcaLockCounterpart once it expired
+    IcaRefundCounterpart {
+        swap_id: String,
+    },
+    ProveCounterpartLock {
+        swap_id: String,
+        block_number: u64,
+        tx_index: u64,
+        // Index of the SwapInitiated log within the receipt
+        log_index: u32,
+        // Trie nodes from the receipts root down to the receipt
+        receipt_proof: Vec<Binary>,
+    },
+}
+
+#[cw_serde]
+pub struct CounterpartLegMsg {
+    pub chain_id: String,
+    pub recipient: String,
+    pub asset: Option<String>,
+    pub expected_amount: Option<Uint128>,
+    pub asset_decimals: Option<u8>,
+    pub timelock: Option<u64>,
+    // Defaults to the initiator leg
+    pub role: Option<SwapRole>,
 }
 
 #[cw_serde]
@@ -46,31 +207,126 @@ pub enum QueryMsg {
     #[returns(SwapResponse)]
     Swap { swap_id: String },
     #[returns(SwapsResponse)]
-    SwapsByInitiator { 
+    SwapsByInitiator {
         initiator: String,
         start_after: Option<String>,
         limit: Option<u32>,
     },
     #[returns(SwapsResponse)]
-    SwapsByParticipant { 
+    SwapsByParticipant {
         participant: String,
         start_after: Option<String>,
         limit: Option<u32>,
     },
     #[returns(SwapsResponse)]
-    SwapsByHashlock { 
+    SwapsByHashlock {
         hashlock: String,
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    // Every swap, optionally filtered, paged by swap id
+    #[returns(SwapsResponse)]
+    Swaps {
+        filter: Option<SwapFilter>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: Option<SortOrder>,
+    },
+    // Initiated swaps with a timelock before `before`, soonest first. Swaps
+    // already past their timelock are still listed until refunded.
+    #[returns(ExpiringSwapsResponse)]
+    ExpiringSwaps {
+        before: u64,
+        start_after: Option<ExpiryCursor>,
+        limit: Option<u32>,
+    },
     #[returns(SwapStatsResponse)]
     SwapStats {},
+    #[returns(SwapLimits)]
+    Limits {},
+    #[returns(AllowanceResponse)]
+    Allowance { address: String, denom: String },
+    #[returns(OutflowLimits)]
+    OutflowLimits {},
+    #[returns(OutflowUsageResponse)]
+    OutflowUsage { denom: String },
+    #[returns(CircuitBreakerResponse)]
+    CircuitBreaker {},
+    #[returns(AddressesResponse)]
+    Denylist {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(AddressesResponse)]
+    Allowlist {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(AccessStatusResponse)]
+    AccessStatus { address: String },
+    #[returns(ClaimableResponse)]
+    Claimable { address: String },
+    #[returns(CounterpartChain)]
+    CounterpartChain { chain_id: String },
+    #[returns(CounterpartChainsResponse)]
+    CounterpartChains {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(SwapResponse)]
+    SwapByCounterpartId {
+        chain_id: String,
+        counterpart_swap_id: String,
+    },
+    #[returns(AddressesResponse)]
+    Relayers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(AddressesResponse)]
+    Hooks {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(AddressesResponse)]
+    ActionContracts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(AddressesResponse)]
+    SwapHooks { swap_id: String },
+    #[returns(AttestorConfig)]
+    Attestors {},
+    #[returns(ExchangeRateResponse)]
+    ExchangeRate {
+        swap_id: String,
+        // Decimals of the locked denom, needed for a decimal-adjusted rate
+        local_decimals: Option<u8>,
+    },
+    #[returns(TrustedHeader)]
+    CounterpartHeader { chain_id: String, number: u64 },
+    #[returns(IbcChannelsResponse)]
+    IbcChannels {},
+    #[returns(IcaAccount)]
+    IcaAccount { ica_id: String },
+    #[returns(RemoteLeg)]
+    RemoteLeg { swap_id: String },
+    #[returns(RevealedSecret)]
+    RevealedSecret { hashlock: String },
+    #[returns(PendingTransfer)]
+    IbcTransfer { channel_id: String, sequence: u64 },
+    #[returns(RemoteLock)]
+    RemoteLock {
+        channel_id: String,
+        hashlock: String,
+    },
 }
 
 #[cw_serde]
 pub struct ConfigResponse {
     pub admin: Addr,
     pub minimum_safety_deposit: Uint128,
+    pub safety_deposit_ceiling: Uint128,
     pub min_timelock_duration: u64,
     pub max_timelock_duration: u64,
     pub paused: bool,
@@ -86,15 +342,87 @@ pub struct SwapResponse {
     pub timelock: u64,
     pub state: SwapState,
     pub secret: Option<String>,
+    pub counterpart: CounterpartLeg,
+    // Deprecated copies of counterpart.recipient and counterpart.chain_id for
+    // clients of the Ethereum-only layout, to be dropped after the next release
     pub ethereum_recipient: String,
     pub ethereum_chain_id: String,
+    pub attestation: Attestation,
     pub created_at: u64,
     pub completed_at: Option<u64>,
+    pub delivery: Option<IbcDelivery>,
+    pub action: Option<CompletionAction>,
+    pub funder: Option<HookFunder>,
+}
+
+impl From<AtomicSwap> for SwapResponse {
+    fn from(swap: AtomicSwap) -> Self {
+        SwapResponse {
+            swap_id: swap.swap_id,
+            initiator: swap.initiator,
+            participant: swap.participant,
+            amount: swap.amount,
+            hashlock: swap.hashlock,
+            timelock: swap.timelock,
+            state: swap.state,
+            secret: swap.secret,
+            ethereum_recipient: swap.counterpart.recipient.clone(),
+            ethereum_chain_id: swap.counterpart.chain_id.clone(),
+            counterpart: swap.counterpart,
+            attestation: swap.attestation,
+            created_at: swap.created_at,
+            completed_at: swap.completed_at,
+            delivery: swap.delivery,
+            action: swap.action,
+            funder: swap.funder,
+        }
+    }
 }
 
 #[cw_serde]
 pub struct SwapsResponse {
     pub swaps: Vec<SwapResponse>,
+    // Pass as start_after to continue, None once there is nothing left
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct ExpiryCursor {
+    pub timelock: u64,
+    pub swap_id: String,
+}
+
+#[cw_serde]
+pub struct ExpiringSwap {
+    pub swap: SwapResponse,
+    // The timelock has passed and the initiator can refund
+    pub expired: bool,
+}
+
+#[cw_serde]
+pub struct ExpiringSwapsResponse {
+    pub swaps: Vec<ExpiringSwap>,
+    pub next_start_after: Option<ExpiryCursor>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct SwapFilter {
+    pub state: Option<SwapState>,
+    pub denom: Option<String>,
+    pub counterpart_chain_id: Option<String>,
+    // Time ranges are inclusive, in seconds
+    pub created_after: Option<u64>,
+    pub created_before: Option<u64>,
+    pub expires_after: Option<u64>,
+    pub expires_before: Option<u64>,
+    pub min_amount: Option<Uint128>,
+}
+
+#[cw_serde]
+pub enum SortOrder {
+    Ascending,
+    Descending,
 }
 
 #[cw_serde]
@@ -106,6 +434,77 @@ pub struct SwapStatsResponse {
     pub total_volume: Vec<Coin>,
 }
 
+#[cw_serde]
+pub struct AllowanceResponse {
+    pub address: Addr,
+    pub denom: String,
+    pub active_swaps_as_initiator: u32,
+    pub remaining_swaps_as_initiator: Option<u32>,
+    pub active_swaps_as_participant: u32,
+    pub remaining_swaps_as_participant: Option<u32>,
+    pub volume_in_window: Uint128,
+    pub remaining_volume: Option<Uint128>,
+    // When the oldest volume in the window stops counting, None without any
+    pub volume_frees_at: Option<u64>,
+    pub min_swap_amount: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct OutflowUsageResponse {
+    pub denom: String,
+    pub epoch: u64,
+    pub epoch_start: u64,
+    pub epoch_end: u64,
+    pub released: Uint128,
+    pub cap: Option<Uint128>,
+    pub remaining: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct CircuitBreakerResponse {
+    pub config: CircuitBreakerConfig,
+    pub tripped: bool,
+    pub tripped_at: Option<u64>,
+    pub reason: Option<TripReason>,
+    pub emergency_refunds: u32,
+}
+
+#[cw_serde]
+pub struct AddressesResponse {
+    pub addresses: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct AccessStatusResponse {
+    pub address: Addr,
+    pub denied: bool,
+    pub allowlisted: bool,
+    pub allowlist_only: bool,
+}
+
+#[cw_serde]
+pub struct ClaimableResponse {
+    pub address: Addr,
+    pub balances: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct CounterpartChainsResponse {
+    pub chains: Vec<CounterpartChain>,
+}
+
+#[cw_serde]
+pub struct ExchangeRateResponse {
+    pub swap_id: String,
+    pub locked: Coin,
+    pub counterpart_asset: Option<String>,
+    pub expected_amount: Uint128,
+    // Counterpart base units expected per locked base unit
+    pub rate: Decimal,
+    // Whole counterpart tokens per whole locked token, when both decimals are known
+    pub normalized_rate: Option<Decimal>,
+}
+
 #[cw_serde]
 pub enum SwapState {
     Initiated,
@@ -113,5 +512,99 @@ pub enum SwapState {
     Refunded,
 }
 
+// Hook contracts accept this as a `swap_hook` variant of their own ExecuteMsg
+#[cw_serde]
+pub enum SwapHookExecuteMsg {
+    SwapHook(SwapHookMsg),
+}
+
+#[cw_serde]
+pub enum SwapHookMsg {
+    Initiated {
+        swap_id: String,
+        initiator: Addr,
+        participant: Addr,
+        amount: Coin,
+        hashlock: String,
+        timelock: u64,
+    },
+    Completed {
+        swap_id: String,
+        participant: Addr,
+        amount: Coin,
+        secret: String,
+    },
+    Refunded {
+        swap_id: String,
+        initiator: Addr,
+        amount: Coin,
+    },
+}
+
 #[cw_serde]
-pub struct MigrateMsg {}
\ No newline at end of file
+pub enum SudoMsg {
+    // Sent by the ibc-hooks middleware once a transfer carrying our ibc_callback memo settles
+    IbcLifecycleComplete(IbcLifecycleComplete),
+    // Sent by Neutron for interchain account channels and transactions, and for
+    // the outcome of transfers the contract sent from the transfer port
+    Response {
+        request: RequestPacket,
+        data: Binary,
+    },
+    Error {
+        request: RequestPacket,
+        details: String,
+    },
+    Timeout {
+        request: RequestPacket,
+    },
+    OpenAck {
+        port_id: String,
+        channel_id: String,
+        counterparty_channel_id: String,
+        counterparty_version: String,
+    },
+}
+
+#[cw_serde]
+pub struct RequestPacket {
+    pub sequence: Option<u64>,
+    pub source_port: Option<String>,
+    pub source_channel: Option<String>,
+    pub destination_port: Option<String>,
+    pub destination_channel: Option<String>,
+    pub data: Option<Binary>,
+    pub timeout_height: Option<RequestPacketTimeoutHeight>,
+    pub timeout_timestamp: Option<u64>,
+}
+
+#[cw_serde]
+pub struct RequestPacketTimeoutHeight {
+    pub revision_number: Option<u64>,
+    pub revision_height: Option<u64>,
+}
+
+#[cw_serde]
+pub enum IbcLifecycleComplete {
+    IbcAck {
+        channel: String,
+        sequence: u64,
+        ack: String,
+        success: bool,
+    },
+    IbcTimeout {
+        channel: String,
+        sequence: u64,
+    },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    // Seeds the counterpart chain registry when upgrading a deployment that predates it
+    pub counterpart_chains: Option<Vec<CounterpartChain>>,
+}
+
+#[cw_serde]
+pub struct IbcChannelsResponse {
+    pub channels: Vec<IbcChannelInfo>,
+}
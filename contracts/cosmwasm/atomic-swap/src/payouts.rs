@@ -0,0 +1,73 @@
+use cosmwasm_std::{Addr, BankMsg, Coin, Order, StdError, StdResult, Storage, SubMsg};
+
+use crate::access::is_denied;
+use crate::claims::credit_claimable;
+use crate::state::{PayoutRoute, PendingPayout, STAGED_PAYOUTS};
+
+// Each payout submessage replies under its own id, counted up from here
+pub const PAYOUT_REPLY_ID_BASE: u64 = 1_000;
+
+// Stages the payout under the reply id of its submessage. Every payout replies
+// within the same transaction and is unstaged then, so an id only has to be
+// unique among the payouts still in flight.
+pub fn stage_payout(storage: &mut dyn Storage, payout: PendingPayout) -> StdResult<u64> {
+    let id = match STAGED_PAYOUTS
+        .keys(storage, None, None, Order::Descending)
+        .next()
+    {
+        Some(last) => last? + 1,
+        None => PAYOUT_REPLY_ID_BASE,
+    };
+    STAGED_PAYOUTS.save(storage, id, &payout)?;
+
+    Ok(id)
+}
+
+// Sends coins to the recipient, unless the address has been denied since the
+// swap was created. In that case the coins are held as a claimable balance.
+// A failed send credits the claimable balance from its reply instead of
+// reverting the settlement.
+pub fn payout(
+    storage: &mut dyn Storage,
+    swap_id: &str,
+    recipient: &Addr,
+    coin: Coin,
+) -> StdResult<Option<SubMsg>> {
+    if coin.amount.is_zero() {
+        return Ok(None);
+    }
+
+    if is_denied(storage, recipient) {
+        credit_claimable(storage, recipient, &coin)?;
+        return Ok(None);
+    }
+
+    let id = stage_payout(
+        storage,
+        PendingPayout {
+            swap_id: swap_id.to_string(),
+            recipient: recipient.clone(),
+            amount: coin.clone(),
+            route: PayoutRoute::Bank,
+        },
+    )?;
+
+    Ok(Some(SubMsg::reply_always(
+        BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![coin],
+        },
+        id,
+    )))
+}
+
+// Unstages the payout a reply is for. A failed submessage was reverted, so
+// the funds it carried are back in the contract.
+pub fn take_staged_payout(storage: &mut dyn Storage, reply_id: u64) -> StdResult<PendingPayout> {
+    let payout = STAGED_PAYOUTS
+        .may_load(storage, reply_id)?
+        .ok_or_else(|| StdError::generic_err(format!("No staged payout for reply {}", reply_id)))?;
+    STAGED_PAYOUTS.remove(storage, reply_id);
+
+    Ok(payout)
+}
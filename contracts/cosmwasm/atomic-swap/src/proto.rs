@@ -0,0 +1,169 @@
+use cosmwasm_std::Coin;
+
+// Minimal protobuf wire encoding for the Cosmos SDK messages sent as Stargate messages
+
+pub enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+pub fn encode_string(buf: &mut Vec<u8>, field: u64, value: &str) {
+    encode_bytes(buf, field, value.as_bytes());
+}
+
+pub fn encode_bytes(buf: &mut Vec<u8>, field: u64, value: &[u8]) {
+    encode_varint(buf, (field << 3) | 2);
+    encode_varint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+pub fn encode_uint64(buf: &mut Vec<u8>, field: u64, value: u64) {
+    encode_varint(buf, field << 3);
+    encode_varint(buf, value);
+}
+
+// cosmos.base.v1beta1.Coin
+pub fn encode_coin(buf: &mut Vec<u8>, field: u64, coin: &Coin) {
+    let mut value = vec![];
+    encode_string(&mut value, 1, &coin.denom);
+    encode_string(&mut value, 2, &coin.amount.to_string());
+    encode_bytes(buf, field, &value);
+}
+
+// google.protobuf.Any
+pub fn encode_any(buf: &mut Vec<u8>, field: u64, type_url: &str, value: &[u8]) {
+    let mut any = vec![];
+    encode_string(&mut any, 1, type_url);
+    encode_bytes(&mut any, 2, value);
+    encode_bytes(buf, field, &any);
+}
+
+fn encode_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+// Splits a message into its fields, None if it is malformed or uses fixed width fields
+pub fn decode_fields(mut data: &[u8]) -> Option<Vec<(u64, Field<'_>)>> {
+    let mut fields = vec![];
+    while !data.is_empty() {
+        let key = decode_varint(&mut data)?;
+        let field = match key & 7 {
+            0 => Field::Varint(decode_varint(&mut data)?),
+            2 => {
+                let len = usize::try_from(decode_varint(&mut data)?).ok()?;
+                if len > data.len() {
+                    return None;
+                }
+                let (value, rest) = data.split_at(len);
+                data = rest;
+                Field::Bytes(value)
+            }
+            _ => return None,
+        };
+        fields.push((key >> 3, field));
+    }
+    Some(fields)
+}
+
+pub fn find_varint(fields: &[(u64, Field<'_>)], number: u64) -> Option<u64> {
+    fields.iter().find_map(|(field, value)| match value {
+        Field::Varint(value) if *field == number => Some(*value),
+        _ => None,
+    })
+}
+
+pub fn find_bytes<'a>(fields: &[(u64, Field<'a>)], number: u64) -> Option<&'a [u8]> {
+    fields.iter().find_map(|(field, value)| match value {
+        Field::Bytes(value) if *field == number => Some(*value),
+        _ => None,
+    })
+}
+
+fn decode_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first()?;
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::coin;
+
+    #[test]
+    fn fields_round_trip() {
+        let mut buf = vec![];
+        encode_string(&mut buf, 1, "cosmos1abc");
+        encode_uint64(&mut buf, 2, 300);
+        encode_bytes(&mut buf, 3, &[0, 1, 2]);
+        encode_uint64(&mut buf, 4, u64::MAX);
+
+        let fields = decode_fields(&buf).unwrap();
+        assert_eq!(find_bytes(&fields, 1), Some(&b"cosmos1abc"[..]));
+        assert_eq!(find_varint(&fields, 2), Some(300));
+        assert_eq!(find_bytes(&fields, 3), Some(&[0, 1, 2][..]));
+        assert_eq!(find_varint(&fields, 4), Some(u64::MAX));
+        // Lookups are typed, a varint field is no bytes field
+        assert_eq!(find_bytes(&fields, 2), None);
+        assert_eq!(find_varint(&fields, 1), None);
+    }
+
+    #[test]
+    fn varints_use_seven_bit_groups() {
+        let mut buf = vec![];
+        encode_uint64(&mut buf, 1, 300);
+        assert_eq!(buf, vec![0x08, 0xac, 0x02]);
+
+        let mut buf = vec![];
+        encode_string(&mut buf, 16, "a");
+        assert_eq!(buf, vec![0x82, 0x01, 0x01, b'a']);
+    }
+
+    #[test]
+    fn nested_messages_round_trip() {
+        let mut buf = vec![];
+        encode_any(&mut buf, 4, "/cosmos.bank.v1beta1.MsgSend", &[0x08, 0x01]);
+        encode_coin(&mut buf, 5, &coin(1_000_500, "uosmo"));
+
+        let fields = decode_fields(&buf).unwrap();
+        let any = decode_fields(find_bytes(&fields, 4).unwrap()).unwrap();
+        assert_eq!(
+            find_bytes(&any, 1),
+            Some(&b"/cosmos.bank.v1beta1.MsgSend"[..])
+        );
+        let value = decode_fields(find_bytes(&any, 2).unwrap()).unwrap();
+        assert_eq!(find_varint(&value, 1), Some(1));
+
+        let coin = decode_fields(find_bytes(&fields, 5).unwrap()).unwrap();
+        assert_eq!(find_bytes(&coin, 1), Some(&b"uosmo"[..]));
+        assert_eq!(find_bytes(&coin, 2), Some(&b"1000500"[..]));
+    }
+
+    #[test]
+    fn malformed_messages_are_rejected() {
+        // Length prefix past the end of the buffer
+        assert!(decode_fields(&[0x0a, 0x05, b'a']).is_none());
+        // Unterminated varint
+        assert!(decode_fields(&[0x08, 0x80]).is_none());
+        // Fixed width wire types
+        assert!(decode_fields(&[0x0d, 0, 0, 0, 0]).is_none());
+        assert!(decode_fields(&[0x09, 0, 0, 0, 0, 0, 0, 0, 0]).is_none());
+        // Varints longer than 64 bits
+        assert!(decode_fields(&[
+            0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01
+        ])
+        .is_none());
+        assert_eq!(decode_fields(&[]).map(|fields| fields.len()), Some(0));
+    }
+}
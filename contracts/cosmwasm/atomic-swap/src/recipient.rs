@@ -0,0 +1,306 @@
+use bech32::{FromBase32, Variant};
+use sha2::{Digest as _, Sha256};
+use sha3::Keccak256;
+
+use crate::error::ContractError;
+use crate::state::AddressFormat;
+
+// Base58check version bytes for P2PKH and P2SH addresses
+const BITCOIN_MAINNET_VERSIONS: [u8; 2] = [0x00, 0x05];
+const BITCOIN_TESTNET_VERSIONS: [u8; 2] = [0x6f, 0xc4];
+
+pub fn validate_recipient(format: &AddressFormat, recipient: &str) -> Result<(), ContractError> {
+    let valid = match format {
+        AddressFormat::Evm {} => is_valid_evm_address(recipient),
+        AddressFormat::Bech32 { prefix } => is_valid_bech32_address(prefix, recipient),
+        AddressFormat::Bitcoin { testnet } => is_valid_bitcoin_address(*testnet, recipient),
+        AddressFormat::Base58 {} => is_valid_base58_pubkey(recipient),
+    };
+
+    if !valid {
+        return Err(ContractError::InvalidCounterpartRecipient {
+            recipient: recipient.to_string(),
+        });
+    }
+    Ok(())
+}
+
+// Accepts all-lowercase or all-uppercase hex, mixed case must carry a valid EIP-55 checksum
+pub fn is_valid_evm_address(address: &str) -> bool {
+    let hex_part = match address.strip_prefix("0x") {
+        Some(hex_part) if hex_part.len() == 40 => hex_part,
+        _ => return false,
+    };
+    if !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        return false;
+    }
+
+    let has_lower = hex_part.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = hex_part.chars().any(|c| c.is_ascii_uppercase());
+    if !(has_lower && has_upper) {
+        return true;
+    }
+
+    hex_part == eip55_checksum(hex_part)
+}
+
+pub fn eip55_checksum(hex_part: &str) -> String {
+    let lower = hex_part.to_ascii_lowercase();
+    let hash = hex::encode(Keccak256::digest(lower.as_bytes()));
+
+    lower
+        .chars()
+        .zip(hash.chars())
+        .map(|(c, h)| {
+            // Letters are uppercased where the matching hash nibble is 8 or above
+            if c.is_ascii_alphabetic() && h.to_digit(16).unwrap_or_default() >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+// Cosmos account (20 bytes) or contract (32 bytes) addresses under the given prefix
+pub fn is_valid_bech32_address(prefix: &str, address: &str) -> bool {
+    match bech32::decode(address) {
+        Ok((hrp, data, Variant::Bech32)) if hrp == prefix => Vec::<u8>::from_base32(&data)
+            .map(|bytes| bytes.len() == 20 || bytes.len() == 32)
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+pub fn is_valid_bitcoin_address(testnet: bool, address: &str) -> bool {
+    let hrp = if testnet { "tb" } else { "bc" };
+    if address.to_ascii_lowercase().starts_with(hrp) {
+        return is_valid_segwit_address(hrp, address);
+    }
+
+    let versions = if testnet {
+        BITCOIN_TESTNET_VERSIONS
+    } else {
+        BITCOIN_MAINNET_VERSIONS
+    };
+    is_valid_base58check_address(&versions, address)
+}
+
+// BIP-173 / BIP-350: v0 programs use bech32, v1 and above bech32m
+fn is_valid_segwit_address(hrp: &str, address: &str) -> bool {
+    let (decoded_hrp, data, variant) = match bech32::decode(address) {
+        Ok(decoded) => decoded,
+        Err(_) => return false,
+    };
+    if decoded_hrp != hrp || data.is_empty() {
+        return false;
+    }
+
+    let version = data[0].to_u8();
+    let program = match Vec::<u8>::from_base32(&data[1..]) {
+        Ok(program) => program,
+        Err(_) => return false,
+    };
+
+    match version {
+        0 => variant == Variant::Bech32 && (program.len() == 20 || program.len() == 32),
+        1..=16 => variant == Variant::Bech32m && (2..=40).contains(&program.len()),
+        _ => false,
+    }
+}
+
+fn is_valid_base58check_address(versions: &[u8], address: &str) -> bool {
+    let bytes = match bs58::decode(address).into_vec() {
+        Ok(bytes) if bytes.len() == 25 => bytes,
+        _ => return false,
+    };
+
+    let (payload, checksum) = bytes.split_at(21);
+    let digest = Sha256::digest(Sha256::digest(payload));
+    versions.contains(&payload[0]) && digest[..4] == *checksum
+}
+
+// Solana style addresses: a base58 encoded 32 byte public key
+pub fn is_valid_base58_pubkey(address: &str) -> bool {
+    matches!(bs58::decode(address).into_vec(), Ok(bytes) if bytes.len() == 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evm_addresses_follow_eip55() {
+        // Checksummed examples from the EIP
+        for address in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            assert!(is_valid_evm_address(address), "{}", address);
+            assert_eq!(
+                eip55_checksum(&address[2..].to_ascii_lowercase()),
+                &address[2..]
+            );
+        }
+
+        // Single-case addresses carry no checksum
+        assert!(is_valid_evm_address(
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+        ));
+        assert!(is_valid_evm_address(
+            "0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED"
+        ));
+
+        for address in [
+            // One letter with its case flipped
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD",
+            "0xfb6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed00",
+            "0xgaaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+        ] {
+            assert!(!is_valid_evm_address(address), "{}", address);
+        }
+    }
+
+    #[test]
+    fn cosmos_addresses_are_bech32_under_the_prefix() {
+        // transfer and gov module accounts on the Cosmos Hub
+        assert!(is_valid_bech32_address(
+            "cosmos",
+            "cosmos1yl6hdjhmkf37639730gffanpzndzdpmhwlkfhr"
+        ));
+        assert!(is_valid_bech32_address(
+            "cosmos",
+            "cosmos10d07y265gmmuvt4z0w9aw880jnsr700j6zn9kn"
+        ));
+
+        assert!(!is_valid_bech32_address(
+            "osmo",
+            "cosmos1yl6hdjhmkf37639730gffanpzndzdpmhwlkfhr"
+        ));
+        // Last character changed
+        assert!(!is_valid_bech32_address(
+            "cosmos",
+            "cosmos1yl6hdjhmkf37639730gffanpzndzdpmhwlkfhq"
+        ));
+        // 20 byte payloads from BIP-173 and BIP-350, only the bech32 one passes
+        assert!(is_valid_bech32_address(
+            "abcdef",
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw"
+        ));
+        assert!(!is_valid_bech32_address(
+            "abcdef",
+            "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx"
+        ));
+        // Valid bech32 without a payload
+        assert!(!is_valid_bech32_address("a", "a12uel5l"));
+    }
+
+    #[test]
+    fn segwit_addresses_follow_bip173_and_bip350() {
+        for address in [
+            "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4",
+            "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+            "BC1SW50QGDZ25J",
+            "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+        ] {
+            assert!(is_valid_bitcoin_address(false, address), "{}", address);
+            assert!(!is_valid_bitcoin_address(true, address), "{}", address);
+        }
+        for address in [
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+            "tb1qqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesrxh6hy",
+            "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c",
+        ] {
+            assert!(is_valid_bitcoin_address(true, address), "{}", address);
+            assert!(!is_valid_bitcoin_address(false, address), "{}", address);
+        }
+
+        // Invalid addresses from BIP-350
+        for address in [
+            // Witness v1 and above with a bech32 checksum
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+            "BC1S0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ54WELL",
+            // Witness v0 with a bech32m checksum
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+            // Invalid character in the checksum
+            "bc1p38j9r5y49hruaue7wxjce0updqjuyyx0kh56v8s25huc6995vvpql3jow4",
+            // Witness version 17
+            "BC130XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ7ZWS8R",
+            // Programs of 1 and 41 bytes, and of 16 bytes under v0
+            "bc1pw5dgrnzv",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav",
+            "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+            // More than 4 bits of padding
+            "bc1zw508d6qejxtdg4y5r3zarvaryvqyzf3du",
+            // Empty data part
+            "bc1gmk9yu",
+        ] {
+            assert!(!is_valid_bitcoin_address(false, address), "{}", address);
+        }
+        for address in [
+            "tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqglt7rf",
+            "tb1q0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq24jc47",
+            // Mixed case
+            "tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq47Zagq",
+            // Non-zero padding
+            "tb1pw508d6qejxtdg4y5r3zarqfsj6c3",
+            // Unknown human readable part
+            "tc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq5zuyut",
+        ] {
+            assert!(!is_valid_bitcoin_address(true, address), "{}", address);
+        }
+    }
+
+    #[test]
+    fn legacy_bitcoin_addresses_are_base58check() {
+        for address in [
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2",
+            "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+        ] {
+            assert!(is_valid_bitcoin_address(false, address), "{}", address);
+            assert!(!is_valid_bitcoin_address(true, address), "{}", address);
+        }
+        for address in [
+            "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn",
+            "2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc",
+        ] {
+            assert!(is_valid_bitcoin_address(true, address), "{}", address);
+            assert!(!is_valid_bitcoin_address(false, address), "{}", address);
+        }
+
+        for address in [
+            // Checksum broken in the last character
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb",
+            "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLz",
+            // Characters outside the base58 alphabet
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7Divf0a",
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa1",
+            "",
+        ] {
+            assert!(!is_valid_bitcoin_address(false, address), "{}", address);
+        }
+    }
+
+    #[test]
+    fn solana_addresses_are_32_byte_keys() {
+        assert!(is_valid_base58_pubkey("11111111111111111111111111111111"));
+        assert!(is_valid_base58_pubkey(
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+        ));
+
+        assert!(!is_valid_base58_pubkey("1111111111111111111111111111111"));
+        assert!(!is_valid_base58_pubkey(
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5D0"
+        ));
+        assert!(!is_valid_base58_pubkey(
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"
+        ));
+    }
+}
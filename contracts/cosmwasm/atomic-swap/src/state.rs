@@ -1,6 +1,6 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, Coin, StdError, StdResult, Storage, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
 use crate::msg::SwapState;
 
@@ -8,11 +8,320 @@ use crate::msg::SwapState;
 pub struct Config {
     pub admin: Addr,
     pub minimum_safety_deposit: Uint128,
+    // Upper bound for minimum_safety_deposit, sized for the decimals of the
+    // swapped denoms. Configs stored before it existed use the default ceiling.
+    pub safety_deposit_ceiling: Option<Uint128>,
     pub min_timelock_duration: u64,
     pub max_timelock_duration: u64,
     pub paused: bool,
 }
 
+#[cw_serde]
+pub struct SwapLimits {
+    pub max_active_swaps_per_initiator: Option<u32>,
+    // Anyone can name an address as participant, so this cap is best kept well above
+    // what the address takes part in, or others can fill it
+    pub max_active_swaps_per_participant: Option<u32>,
+    // Per-denom cap on the amount a single initiator can lock within any rolling window
+    pub max_volume_per_window: Vec<Coin>,
+    pub volume_window_seconds: u64,
+    // Per-denom minimum swap amount, denoms not listed have no minimum
+    pub min_swap_amount: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct VolumeWindow {
+    // Oldest first, buckets leave the window once they end before its start
+    pub buckets: Vec<VolumeBucket>,
+}
+
+#[cw_serde]
+pub struct VolumeBucket {
+    pub start: u64,
+    pub volume: Uint128,
+}
+
+#[cw_serde]
+pub struct OutflowLimits {
+    pub epoch_seconds: u64,
+    // Per-denom cap on value released by completions and emergency refunds per epoch
+    pub caps: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct OutflowEpoch {
+    pub epoch: u64,
+    pub released: Uint128,
+}
+
+#[cw_serde]
+pub struct CircuitBreakerConfig {
+    pub window_seconds: u64,
+    // Number of past windows the completion volume average is taken over
+    pub trailing_windows: u32,
+    // Trip when a window's completion volume exceeds this multiple of the trailing average
+    pub completion_volume_multiplier: Option<u64>,
+    // Trip on an emergency refund beyond this many within a window
+    pub max_emergency_refunds: Option<u32>,
+}
+
+#[cw_serde]
+pub enum TripReason {
+    CompletionVolume { denom: String },
+    EmergencyRefunds {},
+}
+
+#[cw_serde]
+pub struct CircuitBreakerState {
+    pub tripped: bool,
+    pub tripped_at: Option<u64>,
+    pub reason: Option<TripReason>,
+    pub window: u64,
+    pub emergency_refunds: u32,
+}
+
+#[cw_serde]
+pub struct CompletionVolume {
+    pub window: u64,
+    pub volume: Uint128,
+    pub trailing_average: Uint128,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct AccessConfig {
+    // When set, only allowlisted addresses can initiate or take part in swaps
+    pub allowlist_only: bool,
+}
+
+#[cw_serde]
+pub enum AddressFormat {
+    Evm {},
+    Bech32 { prefix: String },
+    Bitcoin { testnet: bool },
+    Base58 {},
+}
+
+impl AddressFormat {
+    pub fn family(&self) -> ChainFamily {
+        match self {
+            AddressFormat::Evm {} => ChainFamily::Evm,
+            AddressFormat::Bech32 { .. } => ChainFamily::Cosmos,
+            AddressFormat::Bitcoin { .. } => ChainFamily::Bitcoin,
+            AddressFormat::Base58 {} => ChainFamily::Solana,
+        }
+    }
+}
+
+#[cw_serde]
+pub enum ChainFamily {
+    Evm,
+    Cosmos,
+    Bitcoin,
+    Solana,
+}
+
+#[cw_serde]
+pub struct CounterpartChain {
+    pub chain_id: String,
+    pub name: String,
+    pub address_format: AddressFormat,
+    // Minimum gap in seconds required between the Cosmos and the counterpart timelock
+    pub min_timelock_margin: u64,
+    pub enabled: bool,
+    // HTLC contract whose SwapInitiated logs prove a counterpart lock, EVM chains only
+    pub htlc_contract: Option<String>,
+    // Local channel to a deployment of this contract on the chain, Cosmos chains only
+    pub ibc_channel: Option<String>,
+}
+
+// Which side of the HTLC pair this Cosmos swap is. The initiator leg belongs to
+// the secret holder and must outlive the responder leg on the other chain.
+#[cw_serde]
+pub enum SwapRole {
+    InitiatorLeg,
+    ResponderLeg,
+}
+
+// What the initiator expects to happen on the other chain
+#[cw_serde]
+pub struct CounterpartLeg {
+    pub chain_family: ChainFamily,
+    pub chain_id: String,
+    pub recipient: String,
+    // Token contract, denom or other asset identifier on the counterpart chain
+    pub asset: Option<String>,
+    pub expected_amount: Option<Uint128>,
+    pub asset_decimals: Option<u8>,
+    pub timelock: Option<u64>,
+    pub role: SwapRole,
+    // Filled in once the counterpart HTLC is known, see LinkCounterpart
+    pub counterpart_swap_id: Option<String>,
+    pub lock_tx_hash: Option<String>,
+}
+
+#[cw_serde]
+pub struct AttestorConfig {
+    pub attestors: Vec<Addr>,
+    // Distinct attestors needed before a lock or refund counts as attested
+    pub threshold: u32,
+    // Completion is only allowed once the counterpart lock is attested
+    pub require_lock_attestation: bool,
+}
+
+#[cw_serde]
+pub enum AttestationStatus {
+    Unattested,
+    LockAttested,
+    RefundAttested,
+}
+
+// Relayer evidence about the counterpart HTLC. The attestor lists are kept so
+// that a false attestation can be traced back to whoever signed it.
+#[cw_serde]
+pub struct Attestation {
+    pub status: AttestationStatus,
+    pub lock_attestors: Vec<Addr>,
+    pub refund_attestors: Vec<Addr>,
+    pub attested_at: Option<u64>,
+    // Counterpart block whose receipt proved the lock, if it was proven rather than attested
+    pub proven_at_block: Option<u64>,
+}
+
+impl Default for Attestation {
+    fn default() -> Self {
+        Attestation {
+            status: AttestationStatus::Unattested,
+            lock_attestors: vec![],
+            refund_attestors: vec![],
+            attested_at: None,
+            proven_at_block: None,
+        }
+    }
+}
+
+// A counterpart block header accepted by the admin or the attestor committee.
+// Hashes are 0x prefixed lowercase hex.
+#[cw_serde]
+pub struct TrustedHeader {
+    pub chain_id: String,
+    pub number: u64,
+    pub hash: String,
+    pub receipts_root: String,
+    pub timestamp: u64,
+    pub trusted_at: u64,
+}
+
+#[cw_serde]
+pub struct IbcChannelInfo {
+    pub channel_id: String,
+    pub connection_id: String,
+    pub counterparty_port_id: String,
+    pub counterparty_channel_id: String,
+}
+
+// Lock announced by the counterpart deployment, kept until a local swap can match it
+#[cw_serde]
+pub struct RemoteLock {
+    pub swap_id: String,
+    pub amount: Coin,
+    pub timelock: u64,
+    pub recipient: String,
+    pub received_at: u64,
+}
+
+// ICS-20 delivery of the participant's payout to another chain
+#[cw_serde]
+pub struct IbcDelivery {
+    pub channel_id: String,
+    pub receiver: String,
+    // Relative to the completion, the packet times out this many seconds later
+    pub timeout_seconds: u64,
+    // JSON object forwarded as the transfer memo, e.g. packet-forward-middleware routing
+    pub memo: Option<String>,
+}
+
+// Contract call that receives the participant's payout instead of a bank send
+#[cw_serde]
+pub struct CompletionAction {
+    pub contract: String,
+    pub msg: Binary,
+}
+
+#[cw_serde]
+pub enum PayoutRoute {
+    Bank,
+    Action,
+}
+
+// Payout sent as a submessage, kept until its reply reports the outcome
+#[cw_serde]
+pub struct PendingPayout {
+    pub swap_id: String,
+    pub recipient: Addr,
+    pub amount: Coin,
+    pub route: PayoutRoute,
+}
+
+#[cw_serde]
+pub struct PendingTransfer {
+    pub swap_id: String,
+    pub recipient: Addr,
+    pub amount: Coin,
+    pub channel_id: String,
+}
+
+// Interchain account registered through Neutron on a counterpart chain
+#[cw_serde]
+pub struct IcaAccount {
+    pub ica_id: String,
+    // Registered counterpart chain the account lives on
+    pub chain_id: String,
+    pub connection_id: String,
+    // Deployment of this contract on the host chain
+    pub remote_contract: String,
+    // IBC fees paid from the contract balance for every submitted transaction
+    pub ack_fee: Vec<Coin>,
+    pub timeout_fee: Vec<Coin>,
+    pub timeout_seconds: u64,
+    // Set once the host chain opened the account
+    pub channel_id: Option<String>,
+    pub address: Option<String>,
+}
+
+#[cw_serde]
+pub enum RemoteAction {
+    Lock,
+    Complete,
+    Refund,
+}
+
+#[cw_serde]
+pub enum RemoteLegStatus {
+    Pending,
+    Locked,
+    Completed,
+    Refunded,
+    Failed,
+}
+
+// HTLC on the counterpart chain driven by an interchain account for a local swap
+#[cw_serde]
+pub struct RemoteLeg {
+    pub ica_id: String,
+    pub remote_swap_id: Option<String>,
+    // Last action submitted, and how far it got
+    pub action: RemoteAction,
+    pub status: RemoteLegStatus,
+    pub error: Option<String>,
+}
+
+#[cw_serde]
+pub struct IcaTx {
+    pub swap_id: String,
+    pub action: RemoteAction,
+}
+
 #[cw_serde]
 pub struct AtomicSwap {
     pub swap_id: String,
@@ -23,11 +332,31 @@ pub struct AtomicSwap {
     pub timelock: u64,
     pub state: SwapState,
     pub secret: Option<String>,
-    pub ethereum_recipient: String,
-    pub ethereum_chain_id: String,
+    pub counterpart: CounterpartLeg,
+    pub attestation: Attestation,
     pub created_at: u64,
     pub completed_at: Option<u64>,
     pub safety_deposit: Uint128,
+    pub delivery: Option<IbcDelivery>,
+    pub action: Option<CompletionAction>,
+    pub funder: Option<HookFunder>,
+}
+
+// The ibc-hooks transfer that funded a swap on behalf of its initiator
+#[cw_serde]
+pub struct HookFunder {
+    pub channel_id: String,
+    pub sender: String,
+    pub intermediary: Addr,
+}
+
+// First completion that revealed the preimage of a hashlock
+#[cw_serde]
+pub struct RevealedSecret {
+    pub secret: String,
+    pub swap_id: String,
+    pub block_height: u64,
+    pub revealed_at: u64,
 }
 
 #[cw_serde]
@@ -39,10 +368,168 @@ pub struct SwapStats {
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
-pub const SWAPS: Map<&str, AtomicSwap> = Map::new("swaps");
 pub const SWAP_STATS: Item<SwapStats> = Item::new("swap_stats");
+pub const SWAP_LIMITS: Item<SwapLimits> = Item::new("swap_limits");
+
+// Rate limiting counters
+pub const ACTIVE_SWAPS_BY_INITIATOR: Map<&Addr, u32> = Map::new("active_swaps_by_initiator");
+pub const ACTIVE_SWAPS_BY_PARTICIPANT: Map<&Addr, u32> = Map::new("active_swaps_by_participant");
+pub const VOLUME_BY_ADDRESS: Map<(&Addr, &str), VolumeWindow> = Map::new("volume_by_address");
+
+// Global outflow caps, keyed by denom
+pub const OUTFLOW_LIMITS: Item<OutflowLimits> = Item::new("outflow_limits");
+pub const OUTFLOW_BY_DENOM: Map<&str, OutflowEpoch> = Map::new("outflow_by_denom");
+
+// Anomaly detection, keyed by denom where it applies
+pub const CIRCUIT_BREAKER_CONFIG: Item<CircuitBreakerConfig> = Item::new("circuit_breaker_config");
+pub const CIRCUIT_BREAKER: Item<CircuitBreakerState> = Item::new("circuit_breaker");
+pub const COMPLETION_VOLUME: Map<&str, CompletionVolume> = Map::new("completion_volume");
+
+// Address screening
+pub const ACCESS_CONFIG: Item<AccessConfig> = Item::new("access_config");
+pub const DENYLIST: Map<&Addr, bool> = Map::new("denylist");
+pub const ALLOWLIST: Map<&Addr, bool> = Map::new("allowlist");
+
+// Maps (counterpart chain id, counterpart swap id) to the Cosmos swap id
+pub const SWAPS_BY_COUNTERPART_ID: Map<(&str, &str), String> = Map::new("swaps_by_counterpart_id");
+
+// Addresses allowed to link counterpart data on behalf of swap parties
+pub const RELAYERS: Map<&Addr, bool> = Map::new("relayers");
+
+// Contracts notified of every swap, and contracts a party of one swap is
+// notified through, keyed by swap id, party and hook
+pub const HOOKS: Map<&Addr, bool> = Map::new("hooks");
+pub const SWAP_HOOKS: Map<(&str, &Addr, &Addr), bool> = Map::new("swap_hooks");
+
+// Contracts a swap may call with its payout on completion
+pub const ACTION_CONTRACTS: Map<&Addr, bool> = Map::new("action_contracts");
+
+// Payouts in flight keyed by the id their submessage replies with
+pub const STAGED_PAYOUTS: Map<u64, PendingPayout> = Map::new("staged_payouts_by_reply");
+
+// Relayers attesting to the state of counterpart HTLCs
+pub const ATTESTOR_CONFIG: Item<AttestorConfig> = Item::new("attestor_config");
+
+// Trusted counterpart headers keyed by (chain id, block number), and the
+// committee votes for candidates keyed by (chain id, block number, block hash)
+pub const COUNTERPART_HEADERS: Map<(&str, u64), TrustedHeader> = Map::new("counterpart_headers");
+pub const HEADER_VOTES: Map<(&str, u64, &str), Vec<Addr>> = Map::new("header_votes");
+
+// Open channels to other deployments of this contract, keyed by local channel id
+pub const IBC_CHANNELS: Map<&str, IbcChannelInfo> = Map::new("ibc_channels");
+
+// Locks received over IBC, keyed by (local channel id, hashlock)
+pub const REMOTE_LOCKS: Map<(&str, &str), RemoteLock> = Map::new("remote_locks");
+
+// ICS-20 payouts awaiting their outcome, keyed by (source channel, sequence).
+// Transfers are staged until the reply reports their sequence.
+pub const IBC_TRANSFERS: Map<(&str, u64), PendingTransfer> = Map::new("ibc_transfers");
+pub const STAGED_TRANSFERS: Item<Vec<PendingTransfer>> = Item::new("staged_transfers");
+
+// Interchain accounts keyed by ica id, and the remote legs they drive keyed by local swap id
+pub const ICA_ACCOUNTS: Map<&str, IcaAccount> = Map::new("ica_accounts");
+pub const REMOTE_LEGS: Map<&str, RemoteLeg> = Map::new("remote_legs");
+
+// Interchain transactions awaiting their sudo callback, keyed by (channel, sequence).
+// Transactions are staged until the reply reports their sequence.
+pub const ICA_TXS: Map<(&str, u64), IcaTx> = Map::new("ica_txs");
+pub const STAGED_ICA_TXS: Item<Vec<IcaTx>> = Item::new("staged_ica_txs");
+
+// Supported counterpart chains, keyed by chain id
+pub const COUNTERPART_CHAINS: Map<&str, CounterpartChain> = Map::new("counterpart_chains");
+
+// Secrets revealed by completed swaps, keyed by hashlock
+pub const REVEALED_SECRETS: Map<&str, RevealedSecret> = Map::new("revealed_secrets");
 
-// Secondary indexes for efficient querying
-pub const SWAPS_BY_INITIATOR: Map<(&str, &str), bool> = Map::new("swaps_by_initiator");
-pub const SWAPS_BY_PARTICIPANT: Map<(&str, &str), bool> = Map::new("swaps_by_participant");
-pub const SWAPS_BY_HASHLOCK: Map<(&str, &str), bool> = Map::new("swaps_by_hashlock");
\ No newline at end of file
+// Payouts held back from the recipient, keyed by (recipient, denom)
+pub const CLAIMABLE: Map<(&Addr, &str), Uint128> = Map::new("claimable");
+
+pub struct SwapIndexes<'a> {
+    pub initiator: MultiIndex<'a, Addr, AtomicSwap, String>,
+    pub participant: MultiIndex<'a, Addr, AtomicSwap, String>,
+    pub hashlock: MultiIndex<'a, String, AtomicSwap, String>,
+    pub state: MultiIndex<'a, u8, AtomicSwap, String>,
+    pub expiry: ExpiryIndex<'a>,
+}
+
+// Initiated swaps keyed by (timelock, swap id). A swap leaves the index when it
+// is completed or refunded, so only swaps still open read back in expiry order.
+pub struct ExpiryIndex<'a> {
+    namespace: &'a str,
+}
+
+impl<'a> ExpiryIndex<'a> {
+    pub const fn new(namespace: &'a str) -> Self {
+        ExpiryIndex { namespace }
+    }
+
+    pub fn open_swaps(&self) -> Map<'a, (u64, &'a str), bool> {
+        Map::new(self.namespace)
+    }
+}
+
+impl Index<AtomicSwap> for ExpiryIndex<'_> {
+    fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &AtomicSwap) -> StdResult<()> {
+        if data.state == SwapState::Initiated {
+            let swap_id = swap_id_of(pk)?;
+            Map::<(u64, &str), bool>::new(self.namespace).save(
+                store,
+                (data.timelock, swap_id),
+                &true,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, store: &mut dyn Storage, pk: &[u8], old_data: &AtomicSwap) -> StdResult<()> {
+        let swap_id = swap_id_of(pk)?;
+        Map::<(u64, &str), bool>::new(self.namespace).remove(store, (old_data.timelock, swap_id));
+        Ok(())
+    }
+}
+
+fn swap_id_of(pk: &[u8]) -> StdResult<&str> {
+    std::str::from_utf8(pk).map_err(|_| StdError::invalid_utf8("swap id"))
+}
+
+impl IndexList<AtomicSwap> for SwapIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<AtomicSwap>> + '_> {
+        let indexes: Vec<&dyn Index<AtomicSwap>> = vec![
+            &self.initiator,
+            &self.participant,
+            &self.hashlock,
+            &self.state,
+            &self.expiry,
+        ];
+        Box::new(indexes.into_iter())
+    }
+}
+
+// Swaps keyed by swap id, stored under the same namespace as before the indexes
+pub fn swaps<'a>() -> IndexedMap<'a, &'a str, AtomicSwap, SwapIndexes<'a>> {
+    let indexes = SwapIndexes {
+        initiator: MultiIndex::new(
+            |_, swap| swap.initiator.clone(),
+            "swaps",
+            "swaps__initiator",
+        ),
+        participant: MultiIndex::new(
+            |_, swap| swap.participant.clone(),
+            "swaps",
+            "swaps__participant",
+        ),
+        hashlock: MultiIndex::new(|_, swap| swap.hashlock.clone(), "swaps", "swaps__hashlock"),
+        state: MultiIndex::new(|_, swap| state_key(&swap.state), "swaps", "swaps__state"),
+        expiry: ExpiryIndex::new("swaps__expiry"),
+    };
+    IndexedMap::new("swaps", indexes)
+}
+
+// Key of a swap state in the state index
+pub fn state_key(state: &SwapState) -> u8 {
+    match state {
+        SwapState::Initiated => 0,
+        SwapState::Completed => 1,
+        SwapState::Refunded => 2,
+    }
+}
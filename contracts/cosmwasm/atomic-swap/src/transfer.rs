@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+
+use bech32::FromBase32;
+use cosmwasm_std::{
+    Addr, Binary, Coin, CosmosMsg, Env, StdError, StdResult, Storage, SubMsg, SubMsgResponse,
+};
+use serde::de::IgnoredAny;
+use sha2::{Digest, Sha256};
+
+use crate::claims::credit_claimable;
+use crate::error::ContractError;
+use crate::msg::RequestPacket;
+use crate::proto::{decode_fields, encode_coin, encode_string, encode_uint64, find_varint};
+use crate::state::{IbcDelivery, PendingTransfer, IBC_TRANSFERS, STAGED_TRANSFERS};
+
+pub const IBC_TRANSFER_REPLY_ID: u64 = 1;
+
+// Prefix of the intermediary accounts ibc-hooks executes wasm memos from
+const HOOK_SENDER_PREFIX: &str = "ibc-wasm-hook-intermediary";
+const MSG_TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+pub const TRANSFER_PORT: &str = "transfer";
+const MAX_TRANSFER_TIMEOUT: u64 = 7 * 24 * 3600; // 1 week
+const MAX_MEMO_LENGTH: usize = 4096;
+
+pub fn validate_delivery(delivery: &IbcDelivery) -> Result<(), ContractError> {
+    let reason = if !delivery.channel_id.starts_with("channel-") {
+        "channel_id must be a channel identifier"
+    } else if delivery.receiver.trim().is_empty() {
+        "receiver must be non-empty"
+    } else if delivery.timeout_seconds == 0 || delivery.timeout_seconds > MAX_TRANSFER_TIMEOUT {
+        "timeout_seconds must be between 1 second and 1 week"
+    } else if delivery
+        .memo
+        .as_deref()
+        .is_some_and(|memo| !is_valid_memo(memo))
+    {
+        "memo must be a JSON object without an ibc_callback key"
+    } else {
+        return Ok(());
+    };
+
+    Err(ContractError::InvalidIbcDelivery {
+        reason: reason.to_string(),
+    })
+}
+
+// Sends the coin over ICS-20 and stages it until the reply reports the packet
+// sequence. The memo asks ibc-hooks to call back with the transfer's outcome.
+pub fn deliver(
+    storage: &mut dyn Storage,
+    env: &Env,
+    delivery: &IbcDelivery,
+    swap_id: &str,
+    recipient: &Addr,
+    coin: Coin,
+) -> StdResult<SubMsg> {
+    let mut staged = STAGED_TRANSFERS.may_load(storage)?.unwrap_or_default();
+    staged.push(PendingTransfer {
+        swap_id: swap_id.to_string(),
+        recipient: recipient.clone(),
+        amount: coin.clone(),
+        channel_id: delivery.channel_id.clone(),
+    });
+    STAGED_TRANSFERS.save(storage, &staged)?;
+
+    // IbcMsg::Transfer has no memo field before CosmWasm 2.0, so the transfer is sent as MsgTransfer
+    let msg = CosmosMsg::Stargate {
+        type_url: MSG_TRANSFER_TYPE_URL.to_string(),
+        value: Binary::from(encode_msg_transfer(
+            &delivery.channel_id,
+            &coin,
+            env.contract.address.as_str(),
+            &delivery.receiver,
+            env.block
+                .time
+                .plus_seconds(delivery.timeout_seconds)
+                .nanos(),
+            &callback_memo(delivery.memo.as_deref(), env.contract.address.as_str()),
+        )),
+    };
+
+    // A transfer the chain refuses to send must not undo the completion
+    Ok(SubMsg::reply_always(msg, IBC_TRANSFER_REPLY_ID))
+}
+
+// Moves the oldest staged transfer under the sequence the transfer module assigned.
+// Replies arrive in the order the submessages were emitted.
+pub fn record_transfer_sequence(
+    storage: &mut dyn Storage,
+    response: SubMsgResponse,
+) -> StdResult<PendingTransfer> {
+    let transfer = take_staged_transfer(storage)?;
+
+    let sequence = response
+        .data
+        .as_ref()
+        .and_then(|data| decode_transfer_sequence(data.as_slice()))
+        .ok_or_else(|| StdError::generic_err("missing MsgTransferResponse sequence"))?;
+    IBC_TRANSFERS.save(storage, (&transfer.channel_id, sequence), &transfer)?;
+
+    Ok(transfer)
+}
+
+// The coin of a transfer that was never sent is still held by the contract
+pub fn fail_staged_transfer(storage: &mut dyn Storage) -> StdResult<PendingTransfer> {
+    let transfer = take_staged_transfer(storage)?;
+    credit_claimable(storage, &transfer.recipient, &transfer.amount)?;
+    Ok(transfer)
+}
+
+// Neutron reports transfers it sent through the same sudo callbacks as
+// interchain transactions, told apart by the transfer port
+pub fn transfer_packet(request: &RequestPacket) -> Option<(String, u64)> {
+    match (
+        &request.source_port,
+        &request.source_channel,
+        request.sequence,
+    ) {
+        (Some(port), Some(channel), Some(sequence)) if port == TRANSFER_PORT => {
+            Some((channel.clone(), sequence))
+        }
+        _ => None,
+    }
+}
+
+// Settles a transfer once ibc-hooks or Neutron reports its outcome. A failed or timed out
+// transfer was refunded to the contract, so it becomes claimable by the recipient.
+pub fn settle_transfer(
+    storage: &mut dyn Storage,
+    channel_id: &str,
+    sequence: u64,
+    success: bool,
+) -> StdResult<Option<PendingTransfer>> {
+    let transfer = match IBC_TRANSFERS.may_load(storage, (channel_id, sequence))? {
+        Some(transfer) => transfer,
+        None => return Ok(None),
+    };
+    IBC_TRANSFERS.remove(storage, (channel_id, sequence));
+
+    if !success {
+        credit_claimable(storage, &transfer.recipient, &transfer.amount)?;
+    }
+    Ok(Some(transfer))
+}
+
+// ibc-hooks executes a memo from an account derived from the channel the
+// transfer arrived on and its original sender, which nobody holds a key for.
+// Only the address bytes are compared, so the local bech32 prefix does not matter.
+pub fn ensure_hook_sender(
+    sender: &Addr,
+    channel_id: &str,
+    original_sender: &str,
+) -> Result<(), ContractError> {
+    let expected = hook_intermediary(channel_id, original_sender);
+    let matches = bech32::decode(sender.as_str())
+        .ok()
+        .and_then(|(_, data, _)| Vec::<u8>::from_base32(&data).ok())
+        .is_some_and(|bytes| bytes == expected);
+
+    if !matches {
+        return Err(ContractError::NotIbcHookSender {
+            channel_id: channel_id.to_string(),
+            original_sender: original_sender.to_string(),
+        });
+    }
+    Ok(())
+}
+
+// address.Hash(prefix, "{channel}/{sender}") as computed by ibc-hooks
+pub fn hook_intermediary(channel_id: &str, original_sender: &str) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(Sha256::digest(HOOK_SENDER_PREFIX.as_bytes()));
+    hasher.update(format!("{}/{}", channel_id, original_sender).as_bytes());
+    hasher.finalize().to_vec()
+}
+
+fn take_staged_transfer(storage: &mut dyn Storage) -> StdResult<PendingTransfer> {
+    let mut staged = STAGED_TRANSFERS.may_load(storage)?.unwrap_or_default();
+    if staged.is_empty() {
+        return Err(StdError::generic_err("no staged IBC transfer"));
+    }
+    let transfer = staged.remove(0);
+    STAGED_TRANSFERS.save(storage, &staged)?;
+    Ok(transfer)
+}
+
+// The callback key is added to the top level of the memo, so it must not be taken there
+fn is_valid_memo(memo: &str) -> bool {
+    memo.len() <= MAX_MEMO_LENGTH
+        && serde_json_wasm::from_str::<BTreeMap<String, IgnoredAny>>(memo)
+            .is_ok_and(|fields| !fields.contains_key("ibc_callback"))
+}
+
+fn callback_memo(memo: Option<&str>, contract: &str) -> String {
+    let callback = format!("\"ibc_callback\":\"{}\"", contract);
+    match memo.map(|memo| memo.trim()[1..].trim_start()) {
+        Some(rest) if rest != "}" => format!("{{{},{}", callback, rest),
+        _ => format!("{{{}}}", callback),
+    }
+}
+
+// Protobuf encoding of ibc.applications.transfer.v1.MsgTransfer
+fn encode_msg_transfer(
+    channel_id: &str,
+    coin: &Coin,
+    sender: &str,
+    receiver: &str,
+    timeout_timestamp: u64,
+    memo: &str,
+) -> Vec<u8> {
+    let mut msg = vec![];
+    encode_string(&mut msg, 1, TRANSFER_PORT);
+    encode_string(&mut msg, 2, channel_id);
+    encode_coin(&mut msg, 3, coin);
+    encode_string(&mut msg, 4, sender);
+    encode_string(&mut msg, 5, receiver);
+    // Field 6 (timeout height) is left empty, the timestamp alone bounds the packet
+    encode_uint64(&mut msg, 7, timeout_timestamp);
+    encode_string(&mut msg, 8, memo);
+    msg
+}
+
+// MsgTransferResponse carries the packet sequence as field 1
+fn decode_transfer_sequence(data: &[u8]) -> Option<u64> {
+    find_varint(&decode_fields(data)?, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memos_are_json_objects_without_a_callback() {
+        for memo in [
+            "{}",
+            " {\"wasm\":{\"contract\":\"osmo1c\",\"msg\":{}}} ",
+            "{\"forward\":{\"ibc_callback\":\"nested\"}}",
+            "{\"note\":\"mentions \\\"ibc_callback\\\" in a value\"}",
+        ] {
+            assert!(is_valid_memo(memo), "{}", memo);
+        }
+
+        for memo in [
+            "",
+            "[]",
+            "\"ibc_callback\"",
+            "{\"wasm\":",
+            "{} {}",
+            "{\"ibc_callback\":\"osmo1c\"}",
+            "{ \"ibc_callback\" : null }",
+            "{\"ibc\\u005fcallback\":\"osmo1c\"}",
+        ] {
+            assert!(!is_valid_memo(memo), "{}", memo);
+        }
+
+        let long = format!("{{\"note\":\"{}\"}}", "a".repeat(MAX_MEMO_LENGTH));
+        assert!(!is_valid_memo(&long));
+    }
+
+    #[test]
+    fn callbacks_join_the_memo() {
+        assert_eq!(callback_memo(None, "c"), "{\"ibc_callback\":\"c\"}");
+        assert_eq!(
+            callback_memo(Some(" { } "), "c"),
+            "{\"ibc_callback\":\"c\"}"
+        );
+        let memo = callback_memo(Some("{\"wasm\":{}}"), "c");
+        assert_eq!(memo, "{\"ibc_callback\":\"c\",\"wasm\":{}}");
+        assert!(serde_json_wasm::from_str::<BTreeMap<String, IgnoredAny>>(&memo).is_ok());
+    }
+}
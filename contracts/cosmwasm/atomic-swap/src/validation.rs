@@ -0,0 +1,168 @@
+use cosmwasm_std::{Addr, Api, Uint128};
+
+use crate::error::ContractError;
+use crate::state::Config;
+
+// Hard bounds every configuration must stay within, regardless of who sets it
+pub const MIN_TIMELOCK_DURATION_FLOOR: u64 = 60; // 1 minute
+pub const MAX_TIMELOCK_DURATION_CEILING: u64 = 30 * 24 * 3600; // 30 days
+
+// Bound on minimum_safety_deposit until the admin sets a ceiling that fits its denoms
+pub const DEFAULT_SAFETY_DEPOSIT_CEILING: u128 = 1_000_000_000_000; // 1,000,000 tokens at 6 decimals
+
+pub fn validate_admin(api: &dyn Api, admin: &str, contract: &Addr) -> Result<Addr, ContractError> {
+    let addr = api
+        .addr_validate(admin)
+        .map_err(|_| ContractError::InvalidConfigAdmin {
+            address: admin.to_string(),
+        })?;
+
+    // The contract cannot administer itself, that would lock the config forever
+    if addr == *contract {
+        return Err(ContractError::InvalidConfigAdmin {
+            address: admin.to_string(),
+        });
+    }
+
+    Ok(addr)
+}
+
+pub fn validate_config(config: &Config) -> Result<(), ContractError> {
+    validate_timelock_duration("min_timelock_duration", config.min_timelock_duration)?;
+    validate_timelock_duration("max_timelock_duration", config.max_timelock_duration)?;
+
+    if config.min_timelock_duration >= config.max_timelock_duration {
+        return Err(ContractError::InvalidConfigTimelockRange {
+            min: config.min_timelock_duration,
+            max: config.max_timelock_duration,
+        });
+    }
+
+    let ceiling = safety_deposit_ceiling(config);
+    if config.minimum_safety_deposit.is_zero() || config.minimum_safety_deposit > ceiling {
+        return Err(ContractError::InvalidConfigSafetyDeposit {
+            value: config.minimum_safety_deposit.to_string(),
+            max: ceiling.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+pub fn safety_deposit_ceiling(config: &Config) -> Uint128 {
+    config
+        .safety_deposit_ceiling
+        .unwrap_or_else(|| Uint128::new(DEFAULT_SAFETY_DEPOSIT_CEILING))
+}
+
+fn validate_timelock_duration(field: &str, value: u64) -> Result<(), ContractError> {
+    if !(MIN_TIMELOCK_DURATION_FLOOR..=MAX_TIMELOCK_DURATION_CEILING).contains(&value) {
+        return Err(ContractError::InvalidConfigTimelockDuration {
+            field: field.to_string(),
+            value,
+            min: MIN_TIMELOCK_DURATION_FLOOR,
+            max: MAX_TIMELOCK_DURATION_CEILING,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockApi;
+
+    fn config() -> Config {
+        Config {
+            admin: Addr::unchecked("admin"),
+            minimum_safety_deposit: Uint128::new(1_000_000),
+            safety_deposit_ceiling: None,
+            min_timelock_duration: 3600,
+            max_timelock_duration: 7 * 24 * 3600,
+            paused: false,
+        }
+    }
+
+    #[test]
+    fn admin_must_be_valid_and_not_the_contract() {
+        let api = MockApi::default();
+        let contract = Addr::unchecked("contract");
+
+        assert_eq!(
+            validate_admin(&api, "admin", &contract).unwrap(),
+            Addr::unchecked("admin")
+        );
+        assert!(matches!(
+            validate_admin(&api, "contract", &contract),
+            Err(ContractError::InvalidConfigAdmin { .. })
+        ));
+        assert!(matches!(
+            validate_admin(&api, "a", &contract),
+            Err(ContractError::InvalidConfigAdmin { .. })
+        ));
+    }
+
+    #[test]
+    fn timelock_durations_stay_within_bounds() {
+        assert!(validate_config(&config()).is_ok());
+
+        let mut below = config();
+        below.min_timelock_duration = MIN_TIMELOCK_DURATION_FLOOR - 1;
+        assert!(matches!(
+            validate_config(&below),
+            Err(ContractError::InvalidConfigTimelockDuration { .. })
+        ));
+
+        let mut above = config();
+        above.max_timelock_duration = MAX_TIMELOCK_DURATION_CEILING + 1;
+        assert!(matches!(
+            validate_config(&above),
+            Err(ContractError::InvalidConfigTimelockDuration { .. })
+        ));
+
+        let mut inverted = config();
+        inverted.min_timelock_duration = inverted.max_timelock_duration;
+        assert!(matches!(
+            validate_config(&inverted),
+            Err(ContractError::InvalidConfigTimelockRange { .. })
+        ));
+    }
+
+    #[test]
+    fn safety_deposit_stays_under_the_default_ceiling() {
+        let mut zero = config();
+        zero.minimum_safety_deposit = Uint128::zero();
+        assert!(validate_config(&zero).is_err());
+
+        let mut max = config();
+        max.minimum_safety_deposit = Uint128::new(DEFAULT_SAFETY_DEPOSIT_CEILING);
+        assert!(validate_config(&max).is_ok());
+
+        max.minimum_safety_deposit += Uint128::new(1);
+        match validate_config(&max) {
+            Err(ContractError::InvalidConfigSafetyDeposit { value, max }) => {
+                assert_eq!(value, "1000000000001");
+                assert_eq!(max, DEFAULT_SAFETY_DEPOSIT_CEILING.to_string());
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn ceiling_fits_other_decimals() {
+        // 0.1 of an 18 decimal token
+        let mut config = config();
+        config.minimum_safety_deposit = Uint128::new(100_000_000_000_000_000);
+        assert!(validate_config(&config).is_err());
+
+        config.safety_deposit_ceiling = Some(Uint128::new(1_000_000_000_000_000_000_000_000));
+        assert!(validate_config(&config).is_ok());
+
+        // Lowering the ceiling under the current minimum is refused
+        config.safety_deposit_ceiling = Some(Uint128::new(1));
+        assert!(matches!(
+            validate_config(&config),
+            Err(ContractError::InvalidConfigSafetyDeposit { .. })
+        ));
+    }
+}
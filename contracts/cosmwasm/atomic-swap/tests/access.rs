@@ -0,0 +1,161 @@
+mod common;
+
+use atomic_swap::contract::{execute, query};
+use atomic_swap::msg::{AddressesResponse, ClaimableResponse, ExecuteMsg, QueryMsg};
+use atomic_swap::ContractError;
+use common::{addr, initiate, setup, Deps, ADMIN, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, from_json, BankMsg, Coin, CosmosMsg, Response};
+
+fn deny(deps: &mut Deps, address: &str) {
+    let msg = ExecuteMsg::UpdateDenylist {
+        add: vec![address.to_string()],
+        remove: vec![],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+}
+
+fn claimable(deps: &Deps, address: &str) -> Vec<Coin> {
+    let msg = QueryMsg::Claimable {
+        address: address.to_string(),
+    };
+    let res: ClaimableResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    res.balances
+}
+
+fn claim(deps: &mut Deps, sender: &str) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(sender, &[]),
+        ExecuteMsg::Claim {},
+    )
+}
+
+fn bank_sends(res: &Response) -> Vec<(String, Vec<Coin>)> {
+    res.messages
+        .iter()
+        .filter_map(|msg| match &msg.msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                Some((to_address.clone(), amount.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn denied_recipients_are_paid_into_claimable() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+
+    // Denied after the swap was created, so completion still goes through
+    deny(&mut deps, &bob);
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id,
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), msg).unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![(alice.clone(), vec![coin(1_000_000, "uatom")])]
+    );
+    assert_eq!(claimable(&deps, &bob), vec![coin(100, "uatom")]);
+
+    // Held funds stay put while the address is denied
+    let err = claim(&mut deps, &bob).unwrap_err();
+    assert!(matches!(err, ContractError::AddressDenied { .. }));
+    assert_eq!(claimable(&deps, &bob), vec![coin(100, "uatom")]);
+}
+
+#[test]
+fn claims_pay_out_once() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+    deny(&mut deps, &bob);
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id,
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateDenylist {
+        add: vec![],
+        remove: vec![bob.clone()],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    let res = claim(&mut deps, &bob).unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![(bob.clone(), vec![coin(100, "uatom")])]
+    );
+    assert!(claimable(&deps, &bob).is_empty());
+
+    let err = claim(&mut deps, &bob).unwrap_err();
+    assert!(matches!(err, ContractError::NothingToClaim {}));
+    let err = claim(&mut deps, &alice).unwrap_err();
+    assert!(matches!(err, ContractError::NothingToClaim {}));
+}
+
+#[test]
+fn only_the_admin_changes_the_lists() {
+    let mut deps = setup("chain-b");
+    let stranger = mock_info(&addr(3), &[]);
+
+    let msgs = [
+        ExecuteMsg::UpdateDenylist {
+            add: vec![addr(2)],
+            remove: vec![],
+        },
+        ExecuteMsg::UpdateAllowlist {
+            add: vec![addr(3)],
+            remove: vec![],
+        },
+        ExecuteMsg::SetAllowlistOnly { enabled: true },
+    ];
+    for msg in msgs {
+        let err = execute(deps.as_mut(), mock_env(), stranger.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    let msg = QueryMsg::Denylist {
+        start_after: None,
+        limit: None,
+    };
+    let res: AddressesResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert!(res.addresses.is_empty());
+
+    // Nothing changed, so the stranger can still open a swap
+    let id = initiate(&mut deps, &addr(3), &addr(2));
+    assert!(!id.is_empty());
+}
+
+#[test]
+fn enforced_allowlists_gate_new_swaps() {
+    let mut deps = setup("chain-b");
+    let msg = ExecuteMsg::UpdateAllowlist {
+        add: vec![addr(1), addr(2)],
+        remove: vec![],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    let msg = ExecuteMsg::SetAllowlistOnly { enabled: true };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    initiate(&mut deps, &addr(1), &addr(2));
+
+    let msg = common::initiate_msg(
+        &addr(3),
+        coin(100, "uatom"),
+        common::counterpart("chain-b", addr(200)),
+    );
+    let info = mock_info(&addr(1), &[coin(1_000_100, "uatom")]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::AddressNotAllowed { .. }));
+}
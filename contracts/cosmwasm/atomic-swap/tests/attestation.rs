@@ -0,0 +1,136 @@
+mod common;
+
+use atomic_swap::contract::execute;
+use atomic_swap::msg::ExecuteMsg;
+use atomic_swap::state::AttestationStatus;
+use atomic_swap::ContractError;
+use common::{addr, initiate, initiate_at, next_block, setup, swap, Deps, ADMIN, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{Env, Response};
+
+// Two attestors, either of which attests on its own
+fn with_attestors(deps: &mut Deps) {
+    let msg = ExecuteMsg::UpdateAttestors {
+        attestors: vec![addr(50), addr(51)],
+        threshold: 1,
+        require_lock_attestation: false,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+}
+
+fn attest_lock(deps: &mut Deps, env: Env, swap_id: &str) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::AttestCounterpartLock {
+        swap_id: swap_id.to_string(),
+    };
+    execute(deps.as_mut(), env, mock_info(&addr(50), &[]), msg)
+}
+
+fn attest_refund(deps: &mut Deps, env: Env, swap_id: &str) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::AttestCounterpartRefund {
+        swap_id: swap_id.to_string(),
+    };
+    execute(deps.as_mut(), env, mock_info(&addr(51), &[]), msg)
+}
+
+#[test]
+fn lock_attestations_need_an_initiated_swap() {
+    let mut deps = setup("chain-b");
+    with_attestors(&mut deps);
+    let (alice, bob) = (addr(1), addr(2));
+
+    let completed = initiate(&mut deps, &alice, &bob);
+    let env = next_block(&mock_env(), 0);
+    let refunded = initiate_at(&mut deps, env.clone(), &alice, &bob);
+    let open = initiate_at(&mut deps, next_block(&env, 0), &alice, &bob);
+
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: completed.clone(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), msg).unwrap();
+    let err = attest_lock(&mut deps, mock_env(), &completed).unwrap_err();
+    assert!(matches!(err, ContractError::SwapAlreadyCompleted {}));
+
+    let env = next_block(&env, 7200);
+    let msg = ExecuteMsg::RefundSwap {
+        swap_id: refunded.clone(),
+    };
+    execute(deps.as_mut(), env.clone(), mock_info(&alice, &[]), msg).unwrap();
+    let err = attest_lock(&mut deps, env.clone(), &refunded).unwrap_err();
+    assert!(matches!(err, ContractError::SwapAlreadyRefunded {}));
+    assert_eq!(
+        swap(&deps, &refunded).attestation.status,
+        AttestationStatus::Unattested
+    );
+
+    let res = attest_lock(&mut deps, mock_env(), &open).unwrap();
+    assert_eq!(res.events[0].ty, "counterpart_attested");
+    assert_eq!(
+        swap(&deps, &open).attestation.status,
+        AttestationStatus::LockAttested
+    );
+}
+
+#[test]
+fn refund_attestations_need_an_initiated_swap() {
+    let mut deps = setup("chain-b");
+    with_attestors(&mut deps);
+    let (alice, bob) = (addr(1), addr(2));
+
+    let completed = initiate(&mut deps, &alice, &bob);
+    let env = next_block(&mock_env(), 0);
+    let refunded = initiate_at(&mut deps, env.clone(), &alice, &bob);
+    let open = initiate_at(&mut deps, next_block(&env, 0), &alice, &bob);
+
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: completed.clone(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), msg).unwrap();
+    let err = attest_refund(&mut deps, mock_env(), &completed).unwrap_err();
+    assert!(matches!(err, ContractError::SwapAlreadyCompleted {}));
+
+    let later = next_block(&env, 7200);
+    let msg = ExecuteMsg::RefundSwap {
+        swap_id: refunded.clone(),
+    };
+    execute(deps.as_mut(), later.clone(), mock_info(&alice, &[]), msg).unwrap();
+    let err = attest_refund(&mut deps, later, &refunded).unwrap_err();
+    assert!(matches!(err, ContractError::SwapAlreadyRefunded {}));
+
+    attest_refund(&mut deps, mock_env(), &open).unwrap();
+    assert_eq!(
+        swap(&deps, &open).attestation.status,
+        AttestationStatus::RefundAttested
+    );
+}
+
+#[test]
+fn whichever_attestation_lands_first_holds() {
+    let mut deps = setup("chain-b");
+    with_attestors(&mut deps);
+    let (alice, bob) = (addr(1), addr(2));
+    let locked = initiate(&mut deps, &alice, &bob);
+    let refunded = initiate_at(&mut deps, next_block(&mock_env(), 0), &alice, &bob);
+
+    // A refund vote cannot undo a lock the participant may already rely on
+    attest_lock(&mut deps, mock_env(), &locked).unwrap();
+    let err = attest_refund(&mut deps, mock_env(), &locked).unwrap_err();
+    assert!(matches!(err, ContractError::AttestationConflict { .. }));
+    assert_eq!(
+        swap(&deps, &locked).attestation.status,
+        AttestationStatus::LockAttested
+    );
+
+    attest_refund(&mut deps, mock_env(), &refunded).unwrap();
+    let err = attest_lock(&mut deps, mock_env(), &refunded).unwrap_err();
+    assert!(matches!(err, ContractError::AttestationConflict { .. }));
+    assert_eq!(
+        swap(&deps, &refunded).attestation.status,
+        AttestationStatus::RefundAttested
+    );
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use atomic_swap::contract::{execute, query};
+use atomic_swap::error::ContractError;
+use atomic_swap::msg::{CircuitBreakerResponse, ExecuteMsg, QueryMsg, SwapState};
+use atomic_swap::state::CircuitBreakerConfig;
+use common::{
+    addr, counterpart, initiate, initiate_at, initiate_msg, next_block, setup, swap, Deps, ADMIN,
+    SECRET,
+};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, from_json, Env};
+
+fn breaker(deps: &Deps) -> CircuitBreakerResponse {
+    from_json(query(deps.as_ref(), mock_env(), QueryMsg::CircuitBreaker {}).unwrap()).unwrap()
+}
+
+fn watch_volume(deps: &mut Deps) {
+    let config = CircuitBreakerConfig {
+        window_seconds: 600,
+        trailing_windows: 24,
+        completion_volume_multiplier: Some(2),
+        max_emergency_refunds: None,
+    };
+    let msg = ExecuteMsg::UpdateCircuitBreaker { config };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+}
+
+fn complete(
+    deps: &mut Deps,
+    env: Env,
+    participant: &str,
+    swap_id: &str,
+    secret: &str,
+) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: swap_id.to_string(),
+        secret: secret.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), env, mock_info(participant, &[]), msg).map(|_| ())
+}
+
+#[test]
+fn invalid_secret_is_an_error_and_never_trips() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+
+    for _ in 0..10 {
+        let err = complete(&mut deps, mock_env(), &bob, &swap_id, "wrong").unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSecret { .. }));
+    }
+    assert!(!breaker(&deps).tripped);
+
+    complete(&mut deps, mock_env(), &bob, &swap_id, SECRET).unwrap();
+    assert_eq!(swap(&deps, &swap_id).state, SwapState::Completed);
+}
+
+#[test]
+fn completion_volume_spike_trips() {
+    let mut deps = setup("chain-b");
+    watch_volume(&mut deps);
+    let (alice, bob) = (addr(1), addr(2));
+
+    let first = initiate(&mut deps, &alice, &bob);
+    let second = initiate_at(&mut deps, next_block(&mock_env(), 5), &alice, &bob);
+    complete(&mut deps, mock_env(), &bob, &first, SECRET).unwrap();
+    assert!(!breaker(&deps).tripped);
+
+    // The next window is compared against the average the first one left behind
+    complete(
+        &mut deps,
+        next_block(&mock_env(), 600),
+        &bob,
+        &second,
+        SECRET,
+    )
+    .unwrap();
+    assert!(breaker(&deps).tripped);
+}
+
+#[test]
+fn self_swaps_do_not_count_towards_completion_volume() {
+    let mut deps = setup("chain-b");
+    watch_volume(&mut deps);
+    let (alice, bob) = (addr(1), addr(2));
+
+    let first = initiate(&mut deps, &alice, &bob);
+    let to_self = initiate(&mut deps, &alice, &alice);
+    complete(&mut deps, mock_env(), &bob, &first, SECRET).unwrap();
+
+    complete(
+        &mut deps,
+        next_block(&mock_env(), 600),
+        &alice,
+        &to_self,
+        SECRET,
+    )
+    .unwrap();
+    assert!(!breaker(&deps).tripped);
+}
+
+#[test]
+fn emergency_refunds_beyond_the_limit_trip_and_refunds_stay_open() {
+    let mut deps = setup("chain-b");
+    let config = CircuitBreakerConfig {
+        window_seconds: 600,
+        trailing_windows: 24,
+        completion_volume_multiplier: None,
+        max_emergency_refunds: Some(2),
+    };
+    let msg = ExecuteMsg::UpdateCircuitBreaker { config };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    let (alice, bob) = (addr(1), addr(2));
+    let mut env = mock_env();
+    let mut swap_ids = vec![];
+    for _ in 0..4 {
+        env = next_block(&env, 1);
+        swap_ids.push(initiate_at(&mut deps, env.clone(), &alice, &bob));
+    }
+    let emergency_refund = |deps: &mut Deps, swap_id: &str| {
+        let msg = ExecuteMsg::EmergencyRefund {
+            swap_id: swap_id.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap()
+    };
+
+    // Reaching the limit is not beyond it
+    for swap_id in &swap_ids[..2] {
+        let res = emergency_refund(&mut deps, swap_id);
+        assert!(res.events.iter().all(|e| e.ty != "circuit_breaker_tripped"));
+    }
+    assert!(!breaker(&deps).tripped);
+
+    let res = emergency_refund(&mut deps, &swap_ids[2]);
+    assert!(res.events.iter().any(|e| e.ty == "circuit_breaker_tripped"));
+    assert!(breaker(&deps).tripped);
+
+    // Only refunds go through while tripped
+    let res = emergency_refund(&mut deps, &swap_ids[3]);
+    assert!(res.events.iter().all(|e| e.ty != "circuit_breaker_tripped"));
+    assert_eq!(swap(&deps, &swap_ids[3]).state, SwapState::Refunded);
+
+    let msg = initiate_msg(&bob, coin(100, "uatom"), counterpart("chain-b", addr(200)));
+    let info = mock_info(&alice, &[coin(1_000_100, "uatom")]);
+    let err = execute(deps.as_mut(), next_block(&env, 1), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::CircuitBreakerTripped {}));
+}
@@ -0,0 +1,149 @@
+#![allow(dead_code)]
+
+use atomic_swap::contract::{execute, instantiate, query};
+use atomic_swap::ibc::{ibc_channel_connect, ibc_channel_open};
+use atomic_swap::ibc_msg::{SwapPacket, IBC_VERSION};
+use atomic_swap::msg::{CounterpartLegMsg, ExecuteMsg, InstantiateMsg, QueryMsg, SwapResponse};
+use atomic_swap::state::{AddressFormat, CounterpartChain};
+use bech32::ToBase32;
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_ibc_channel_connect_ack, mock_ibc_channel_open_try,
+    mock_info, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{
+    coin, from_json, Attribute, Coin, CosmosMsg, Env, IbcMsg, IbcOrder, OwnedDeps, Response, SubMsg,
+};
+use sha2::{Digest, Sha256};
+
+pub type Deps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+pub const ADMIN: &str = "admin";
+pub const CHANNEL: &str = "channel-0";
+pub const SECRET: &str = "s3cret";
+
+pub fn addr(seed: u8) -> String {
+    bech32::encode("cosmos", [seed; 20].to_base32(), bech32::Variant::Bech32).unwrap()
+}
+
+pub fn hashlock() -> String {
+    hex::encode(Sha256::digest(SECRET.as_bytes()))
+}
+
+pub fn now() -> u64 {
+    mock_env().block.time.seconds()
+}
+
+pub fn chain(chain_id: &str) -> CounterpartChain {
+    CounterpartChain {
+        chain_id: chain_id.to_string(),
+        name: chain_id.to_string(),
+        address_format: AddressFormat::Bech32 {
+            prefix: "cosmos".to_string(),
+        },
+        min_timelock_margin: 600,
+        enabled: true,
+        htlc_contract: None,
+        ibc_channel: Some(CHANNEL.to_string()),
+    }
+}
+
+// A deployment with `counterpart_chain_id` registered behind an open swap channel
+pub fn setup(counterpart_chain_id: &str) -> Deps {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        admin: None,
+        minimum_safety_deposit: None,
+        safety_deposit_ceiling: None,
+        min_timelock_duration: None,
+        max_timelock_duration: None,
+        limits: None,
+        outflow_limits: None,
+        circuit_breaker: None,
+        counterpart_chains: Some(vec![chain(counterpart_chain_id)]),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    let open = mock_ibc_channel_open_try(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap();
+    let connect = mock_ibc_channel_connect_ack(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_connect(deps.as_mut(), mock_env(), connect).unwrap();
+    deps
+}
+
+pub fn counterpart(chain_id: &str, recipient: String) -> CounterpartLegMsg {
+    CounterpartLegMsg {
+        chain_id: chain_id.to_string(),
+        recipient,
+        asset: None,
+        expected_amount: None,
+        asset_decimals: None,
+        timelock: None,
+        role: None,
+    }
+}
+
+pub fn initiate_msg(participant: &str, amount: Coin, leg: CounterpartLegMsg) -> ExecuteMsg {
+    ExecuteMsg::InitiateSwap {
+        participant: participant.to_string(),
+        amount,
+        hashlock: hashlock(),
+        timelock: now() + 7200,
+        counterpart: Some(leg),
+        ethereum_recipient: None,
+        ethereum_chain_id: None,
+    }
+}
+
+// Initiates a 100uatom swap from `initiator` to `participant` and returns its id
+pub fn initiate(deps: &mut Deps, initiator: &str, participant: &str) -> String {
+    initiate_at(deps, mock_env(), initiator, participant)
+}
+
+// Swap ids include the block height, so repeated swaps between a pair need their own block
+pub fn initiate_at(deps: &mut Deps, env: Env, initiator: &str, participant: &str) -> String {
+    let msg = initiate_msg(
+        participant,
+        coin(100, "uatom"),
+        counterpart("chain-b", addr(200)),
+    );
+    let info = mock_info(initiator, &[coin(1_000_100, "uatom")]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    attr(&res, "swap_id")
+}
+
+pub fn next_block(env: &Env, seconds: u64) -> Env {
+    let mut env = env.clone();
+    env.block.height += 1;
+    env.block.time = env.block.time.plus_seconds(seconds);
+    env
+}
+
+pub fn attr(res: &Response, key: &str) -> String {
+    attr_of(&res.attributes, key)
+}
+
+pub fn attr_of(attributes: &[Attribute], key: &str) -> String {
+    attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .unwrap_or_else(|| panic!("missing attribute {}", key))
+        .value
+        .clone()
+}
+
+pub fn swap(deps: &Deps, swap_id: &str) -> SwapResponse {
+    let msg = QueryMsg::Swap {
+        swap_id: swap_id.to_string(),
+    };
+    from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+}
+
+pub fn sent_packet(messages: &[SubMsg]) -> SwapPacket {
+    messages
+        .iter()
+        .find_map(|msg| match &msg.msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => Some(from_json(data).unwrap()),
+            _ => None,
+        })
+        .expect("no packet sent")
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use atomic_swap::contract::{execute, query};
+use atomic_swap::msg::{ConfigResponse, ExecuteMsg, QueryMsg};
+use atomic_swap::ContractError;
+use common::{setup, Deps, ADMIN};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{from_json, Response, Uint128};
+
+fn update(deps: &mut Deps, sender: &str, msg: ExecuteMsg) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+}
+
+fn update_msg() -> ExecuteMsg {
+    ExecuteMsg::UpdateConfig {
+        admin: None,
+        minimum_safety_deposit: None,
+        safety_deposit_ceiling: None,
+        min_timelock_duration: None,
+        max_timelock_duration: None,
+        paused: None,
+    }
+}
+
+fn config(deps: &Deps) -> ConfigResponse {
+    from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap()
+}
+
+#[test]
+fn only_the_admin_updates_the_config() {
+    let mut deps = setup("chain-b");
+    let err = update(&mut deps, "mallory", update_msg()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+#[test]
+fn ceiling_is_raised_for_high_decimal_deposits() {
+    let mut deps = setup("chain-b");
+    assert_eq!(
+        config(&deps).safety_deposit_ceiling,
+        Uint128::new(1_000_000_000_000)
+    );
+
+    // 0.1 of an 18 decimal token is over the default ceiling
+    let deposit = Uint128::new(100_000_000_000_000_000);
+    let mut msg = update_msg();
+    if let ExecuteMsg::UpdateConfig {
+        minimum_safety_deposit,
+        ..
+    } = &mut msg
+    {
+        *minimum_safety_deposit = Some(deposit);
+    }
+    let err = update(&mut deps, ADMIN, msg.clone()).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::InvalidConfigSafetyDeposit { .. }
+    ));
+
+    // Both fields are applied before the config is validated
+    let ceiling = Uint128::new(1_000_000_000_000_000_000_000);
+    if let ExecuteMsg::UpdateConfig {
+        safety_deposit_ceiling,
+        ..
+    } = &mut msg
+    {
+        *safety_deposit_ceiling = Some(ceiling);
+    }
+    let res = update(&mut deps, ADMIN, msg).unwrap();
+    assert_eq!(
+        common::attr(&res, "new_safety_deposit_ceiling"),
+        ceiling.to_string()
+    );
+
+    let config = config(&deps);
+    assert_eq!(config.minimum_safety_deposit, deposit);
+    assert_eq!(config.safety_deposit_ceiling, ceiling);
+}
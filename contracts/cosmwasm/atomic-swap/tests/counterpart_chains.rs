@@ -0,0 +1,121 @@
+mod common;
+
+use atomic_swap::contract::execute;
+use atomic_swap::msg::ExecuteMsg;
+use atomic_swap::state::SwapRole;
+use atomic_swap::ContractError;
+use common::{
+    addr, chain, counterpart, initiate, initiate_msg, next_block, now, setup, swap, Deps, ADMIN,
+};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, Env, Response};
+
+fn admin(deps: &mut Deps, sender: &str, msg: ExecuteMsg) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+}
+
+fn initiate_on(deps: &mut Deps, env: Env, chain_id: &str) -> Result<Response, ContractError> {
+    let msg = initiate_msg(
+        &addr(2),
+        coin(100, "uatom"),
+        counterpart(chain_id, addr(200)),
+    );
+    let info = mock_info(&addr(1), &[coin(1_000_100, "uatom")]);
+    execute(deps.as_mut(), env, info, msg)
+}
+
+#[test]
+fn only_the_admin_manages_chains() {
+    let mut deps = setup("chain-b");
+    let msg = ExecuteMsg::UpsertCounterpartChain {
+        chain: chain("chain-c"),
+    };
+    let err = admin(&mut deps, &addr(1), msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let msg = ExecuteMsg::RemoveCounterpartChain {
+        chain_id: "chain-b".to_string(),
+    };
+    let err = admin(&mut deps, &addr(1), msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    let mut invalid = chain("chain-c");
+    invalid.name = String::new();
+    let msg = ExecuteMsg::UpsertCounterpartChain { chain: invalid };
+    let err = admin(&mut deps, ADMIN, msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidCounterpartChain { .. }));
+}
+
+#[test]
+fn swaps_need_a_registered_enabled_chain() {
+    let mut deps = setup("chain-b");
+    let env = mock_env();
+    let err = initiate_on(&mut deps, env.clone(), "chain-c").unwrap_err();
+    assert!(matches!(err, ContractError::UnknownCounterpartChain { .. }));
+
+    let msg = ExecuteMsg::UpsertCounterpartChain {
+        chain: chain("chain-c"),
+    };
+    admin(&mut deps, ADMIN, msg).unwrap();
+    initiate_on(&mut deps, env.clone(), "chain-c").unwrap();
+
+    let mut disabled = chain("chain-c");
+    disabled.enabled = false;
+    let msg = ExecuteMsg::UpsertCounterpartChain { chain: disabled };
+    admin(&mut deps, ADMIN, msg).unwrap();
+    let env = next_block(&env, 5);
+    let err = initiate_on(&mut deps, env, "chain-c").unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::CounterpartChainDisabled { .. }
+    ));
+}
+
+#[test]
+fn removed_chains_leave_open_swaps_alone() {
+    let mut deps = setup("chain-b");
+    let swap_id = initiate(&mut deps, &addr(1), &addr(2));
+
+    let msg = ExecuteMsg::RemoveCounterpartChain {
+        chain_id: "chain-b".to_string(),
+    };
+    admin(&mut deps, ADMIN, msg).unwrap();
+    let err = initiate_on(&mut deps, next_block(&mock_env(), 5), "chain-b").unwrap_err();
+    assert!(matches!(err, ContractError::UnknownCounterpartChain { .. }));
+    assert_eq!(swap(&deps, &swap_id).counterpart.chain_id, "chain-b");
+}
+
+#[test]
+fn timelocks_must_be_apart_by_the_chain_margin() {
+    let mut deps = setup("chain-b");
+    let mut env = mock_env();
+    // chain-b asks for 600 seconds between the legs, the swap expires at now() + 7200
+    let mut initiate_with = |role, counterpart_timelock| {
+        env = next_block(&env, 0);
+        let mut leg = counterpart("chain-b", addr(200));
+        leg.role = Some(role);
+        leg.timelock = Some(counterpart_timelock);
+        let msg = initiate_msg(&addr(2), coin(100, "uatom"), leg);
+        let info = mock_info(&addr(1), &[coin(1_000_100, "uatom")]);
+        execute(deps.as_mut(), env.clone(), info, msg)
+    };
+
+    // The initiator leg outlives the counterpart one
+    initiate_with(SwapRole::InitiatorLeg, now() + 6600).unwrap();
+    let err = initiate_with(SwapRole::InitiatorLeg, now() + 6601).unwrap_err();
+    match err {
+        ContractError::UnsafeTimelockOrdering {
+            timelock,
+            counterpart_timelock,
+            min_margin,
+        } => assert_eq!(
+            (timelock, counterpart_timelock, min_margin),
+            (now() + 7200, now() + 6601, 600)
+        ),
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    // The responder leg expires first
+    initiate_with(SwapRole::ResponderLeg, now() + 7800).unwrap();
+    let err = initiate_with(SwapRole::ResponderLeg, now() + 7799).unwrap_err();
+    assert!(matches!(err, ContractError::UnsafeTimelockOrdering { .. }));
+}
@@ -0,0 +1,422 @@
+mod common;
+
+use atomic_swap::contract::execute;
+use atomic_swap::error::ContractError;
+use atomic_swap::eth_proof::{
+    decode_header, decode_swap_initiated, keccak256, swap_initiated_topic, verify_proof,
+    verify_receipt,
+};
+use atomic_swap::msg::{CounterpartLegMsg, ExecuteMsg};
+use atomic_swap::state::{AddressFormat, AttestationStatus, CounterpartChain};
+use common::{addr, hashlock, initiate_msg, now, setup, swap, Deps, ADMIN};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, Binary, Response, Uint128};
+use rlp::RlpStream;
+
+const EMPTY_TRIE_ROOT: &str = "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421";
+const HTLC: [u8; 20] = [0x11; 20];
+const ETH_RECIPIENT: [u8; 20] = [0x22; 20];
+const SWAP_ID: [u8; 32] = [0x33; 32];
+
+// Minimal Merkle-Patricia trie builder producing the nodes an Ethereum client stores
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+fn compact(path: &[u8], leaf: bool) -> Vec<u8> {
+    let odd = (path.len() % 2) as u8;
+    let mut nibbles = vec![if leaf { 2 } else { 0 } + odd];
+    if odd == 0 {
+        nibbles.push(0);
+    }
+    nibbles.extend_from_slice(path);
+    nibbles
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect()
+}
+
+fn append_ref(stream: &mut RlpStream, node: &[u8]) {
+    if node.len() < 32 {
+        stream.append_raw(node, 1);
+    } else {
+        stream.append(&keccak256(node).to_vec());
+    }
+}
+
+fn encode_node(entries: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+    if let [(path, value)] = entries {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&compact(path, true));
+        stream.append(value);
+        return stream.out().to_vec();
+    }
+
+    let first = &entries[0].0;
+    let shared = (0..first.len())
+        .take_while(|&i| entries.iter().all(|(path, _)| path.get(i) == first.get(i)))
+        .count();
+    if shared > 0 {
+        let rest: Vec<_> = entries
+            .iter()
+            .map(|(path, value)| (path[shared..].to_vec(), value.clone()))
+            .collect();
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&compact(&first[..shared], false));
+        append_ref(&mut stream, &encode_node(&rest));
+        return stream.out().to_vec();
+    }
+
+    let mut stream = RlpStream::new_list(17);
+    for nibble in 0..16u8 {
+        let children: Vec<_> = entries
+            .iter()
+            .filter(|(path, _)| path.first() == Some(&nibble))
+            .map(|(path, value)| (path[1..].to_vec(), value.clone()))
+            .collect();
+        if children.is_empty() {
+            stream.append_empty_data();
+        } else {
+            append_ref(&mut stream, &encode_node(&children));
+        }
+    }
+    match entries.iter().find(|(path, _)| path.is_empty()) {
+        Some((_, value)) => stream.append(value),
+        None => stream.append_empty_data(),
+    };
+    stream.out().to_vec()
+}
+
+fn nibble_entries(entries: &[(Vec<u8>, Vec<u8>)]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    entries
+        .iter()
+        .map(|(key, value)| (to_nibbles(key), value.clone()))
+        .collect()
+}
+
+fn trie_root(entries: &[(Vec<u8>, Vec<u8>)]) -> [u8; 32] {
+    keccak256(&encode_node(&nibble_entries(entries)))
+}
+
+// Collects the hashed nodes on the way to `key`, as eth_getProof returns them
+fn trie_proof(entries: &[(Vec<u8>, Vec<u8>)], key: &[u8]) -> Vec<Vec<u8>> {
+    let mut entries = nibble_entries(entries);
+    let mut path = to_nibbles(key);
+    let mut proof = vec![];
+    loop {
+        let node = encode_node(&entries);
+        if proof.is_empty() || node.len() >= 32 {
+            proof.push(node);
+        }
+        if entries.len() == 1 || path.is_empty() {
+            return proof;
+        }
+        let first = entries[0].0.clone();
+        let shared = (0..first.len())
+            .take_while(|&i| entries.iter().all(|(p, _)| p.get(i) == first.get(i)))
+            .count();
+        let step = if shared > 0 { shared } else { 1 };
+        entries = entries
+            .into_iter()
+            .filter(|(p, _)| p.len() >= step && p[..step] == path[..step])
+            .map(|(p, value)| (p[step..].to_vec(), value))
+            .collect();
+        path = path[step..].to_vec();
+        if entries.is_empty() {
+            return proof;
+        }
+    }
+}
+
+fn word(bytes: &[u8]) -> Vec<u8> {
+    let mut word = vec![0; 32 - bytes.len()];
+    word.extend_from_slice(bytes);
+    word
+}
+
+// An EIP-1559 receipt carrying a SwapInitiated log of the counterpart HTLC
+fn swap_receipt(status: u64, amount: u128, timelock: u64) -> Vec<u8> {
+    let hashlock = hex::decode(hashlock()).unwrap();
+    let mut data = word(&[0; 20]);
+    data.extend(word(&amount.to_be_bytes()));
+    data.extend(hashlock);
+    data.extend(word(&timelock.to_be_bytes()));
+
+    let mut stream = RlpStream::new_list(4);
+    stream.append(&status);
+    stream.append(&21_000u64);
+    stream.append(&vec![0u8; 256]);
+    stream.begin_list(1);
+    stream.begin_list(3);
+    stream.append(&HTLC.to_vec());
+    stream.begin_list(4);
+    stream.append(&swap_initiated_topic().to_vec());
+    stream.append(&SWAP_ID.to_vec());
+    stream.append(&word(&[0x44; 20]));
+    stream.append(&word(&ETH_RECIPIENT));
+    stream.append(&data);
+
+    let mut receipt = vec![0x02];
+    receipt.extend_from_slice(&stream.out());
+    receipt
+}
+
+// A legacy receipt without logs, to fill the block around the swap transaction
+fn plain_receipt(gas: u64) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(4);
+    stream.append(&1u64);
+    stream.append(&gas);
+    stream.append(&vec![0u8; 256]);
+    stream.begin_list(0);
+    stream.out().to_vec()
+}
+
+fn receipts(swap_receipt: Vec<u8>) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut receipts: Vec<_> = (0..20u64)
+        .map(|index| {
+            (
+                rlp::encode(&index).to_vec(),
+                plain_receipt(21_000 * (index + 1)),
+            )
+        })
+        .collect();
+    receipts[7].1 = swap_receipt;
+    receipts
+}
+
+fn header(number: u64, receipts_root: &[u8; 32]) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(15);
+    stream.append(&vec![0u8; 32]);
+    stream.append(&vec![0u8; 32]);
+    stream.append(&vec![0u8; 20]);
+    stream.append(&vec![0u8; 32]);
+    stream.append(&vec![0u8; 32]);
+    stream.append(&receipts_root.to_vec());
+    stream.append(&vec![0u8; 256]);
+    stream.append(&0u64);
+    stream.append(&number);
+    stream.append(&30_000_000u64);
+    stream.append(&21_000u64);
+    stream.append(&1_700_000_000u64);
+    stream.append(&Vec::<u8>::new());
+    stream.append(&vec![0u8; 32]);
+    stream.append(&vec![0u8; 8]);
+    stream.out().to_vec()
+}
+
+#[test]
+fn trie_builder_matches_known_roots() {
+    assert_eq!(hex::encode(keccak256(&rlp::encode(&""))), EMPTY_TRIE_ROOT);
+
+    let entries = vec![
+        (b"doe".to_vec(), b"reindeer".to_vec()),
+        (b"dog".to_vec(), b"puppy".to_vec()),
+        (b"dogglesworth".to_vec(), b"cat".to_vec()),
+    ];
+    assert_eq!(
+        hex::encode(trie_root(&entries)),
+        "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"
+    );
+}
+
+#[test]
+fn proofs_resolve_every_key() {
+    let entries = vec![
+        (b"doe".to_vec(), b"reindeer".to_vec()),
+        (b"dog".to_vec(), b"puppy".to_vec()),
+        (b"dogglesworth".to_vec(), b"cat".to_vec()),
+    ];
+    let root = trie_root(&entries);
+    for (key, value) in &entries {
+        let proof = trie_proof(&entries, key);
+        assert_eq!(&verify_proof(&root, key, &proof).unwrap(), value);
+    }
+
+    let proof = trie_proof(&entries, b"dog");
+    assert!(verify_proof(&root, b"dot", &proof).is_err());
+    assert!(verify_proof(&root, b"do", &proof).is_err());
+}
+
+#[test]
+fn mainnet_genesis_header_decodes() {
+    let mut stream = RlpStream::new_list(15);
+    stream.append(&vec![0u8; 32]);
+    stream.append(
+        &hex::decode("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347").unwrap(),
+    );
+    stream.append(&vec![0u8; 20]);
+    stream.append(
+        &hex::decode("d7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544").unwrap(),
+    );
+    stream.append(&hex::decode(EMPTY_TRIE_ROOT).unwrap());
+    stream.append(&hex::decode(EMPTY_TRIE_ROOT).unwrap());
+    stream.append(&vec![0u8; 256]);
+    stream.append(&0x4_0000_0000u64);
+    stream.append(&0u64);
+    stream.append(&5000u64);
+    stream.append(&0u64);
+    stream.append(&0u64);
+    stream.append(
+        &hex::decode("11bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82fa").unwrap(),
+    );
+    stream.append(&vec![0u8; 32]);
+    stream.append(&hex::decode("0000000000000042").unwrap());
+    let genesis = stream.out().to_vec();
+
+    let header = decode_header(&genesis).unwrap();
+    assert_eq!(
+        hex::encode(header.hash),
+        "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+    );
+    assert_eq!(hex::encode(header.receipts_root), EMPTY_TRIE_ROOT);
+    assert_eq!((header.number, header.timestamp), (0, 0));
+
+    let mut tampered = genesis.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    assert_ne!(decode_header(&tampered).unwrap().hash, header.hash);
+    assert!(decode_header(&genesis[1..]).is_err());
+}
+
+#[test]
+fn receipt_proof_yields_the_swap_log() {
+    let entries = receipts(swap_receipt(1, 500, now() + 3600));
+    let root = trie_root(&entries);
+    let key = rlp::encode(&7u64);
+    let proof = trie_proof(&entries, &key);
+
+    let receipt = verify_receipt(&root, 7, &proof).unwrap();
+    let log = decode_swap_initiated(&receipt, 0).unwrap();
+    assert_eq!(log.contract, HTLC);
+    assert_eq!(log.swap_id, SWAP_ID);
+    assert_eq!(log.participant, ETH_RECIPIENT);
+    assert_eq!(log.amount, Uint128::new(500));
+    assert_eq!(hex::encode(log.hashlock), hashlock());
+    assert_eq!(log.timelock, now() + 3600);
+
+    // Index 0 encodes as 0x80, which sorts after every other single byte key
+    let first = trie_proof(&entries, &rlp::encode(&0u64));
+    assert_eq!(verify_receipt(&root, 0, &first).unwrap(), entries[0].1);
+}
+
+#[test]
+fn tampered_receipt_proofs_are_rejected() {
+    let entries = receipts(swap_receipt(1, 500, now() + 3600));
+    let root = trie_root(&entries);
+    let proof = trie_proof(&entries, &rlp::encode(&7u64));
+
+    // A receipt with a different amount hashes to a different leaf
+    let mut forged = proof.clone();
+    let leaf = forged.last_mut().unwrap();
+    let position = leaf.len() - 40;
+    leaf[position] ^= 1;
+    assert!(verify_receipt(&root, 7, &forged).is_err());
+
+    // The proof only resolves the key it was made for
+    assert!(verify_receipt(&root, 8, &proof).is_err());
+    assert!(verify_receipt(&root, 7, &proof[..proof.len() - 1]).is_err());
+    assert!(verify_receipt(&[0; 32], 7, &proof).is_err());
+
+    let reverted = receipts(swap_receipt(0, 500, now() + 3600));
+    let root = trie_root(&reverted);
+    let receipt = verify_receipt(&root, 7, &trie_proof(&reverted, &rlp::encode(&7u64))).unwrap();
+    assert!(decode_swap_initiated(&receipt, 0).is_err());
+    assert!(decode_swap_initiated(&plain_receipt(21_000), 0).is_err());
+}
+
+fn evm_setup() -> Deps {
+    let mut deps = setup("chain-b");
+    let chain = CounterpartChain {
+        chain_id: "ethereum".to_string(),
+        name: "Ethereum".to_string(),
+        address_format: AddressFormat::Evm {},
+        min_timelock_margin: 600,
+        enabled: true,
+        htlc_contract: Some(format!("0x{}", hex::encode(HTLC))),
+        ibc_channel: None,
+    };
+    let msg = ExecuteMsg::UpsertCounterpartChain { chain };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    deps
+}
+
+fn evm_leg(timelock: Option<u64>) -> CounterpartLegMsg {
+    CounterpartLegMsg {
+        chain_id: "ethereum".to_string(),
+        recipient: format!("0x{}", hex::encode(ETH_RECIPIENT)),
+        asset: None,
+        expected_amount: Some(Uint128::new(500)),
+        asset_decimals: None,
+        timelock,
+        role: None,
+    }
+}
+
+fn prove(deps: &mut Deps, swap_id: &str, log_timelock: u64) -> Result<Response, ContractError> {
+    // Every receipt set gets a block of its own
+    let block_number = log_timelock;
+    let entries = receipts(swap_receipt(1, 500, log_timelock));
+    let header_rlp = header(block_number, &trie_root(&entries));
+    let msg = ExecuteMsg::SubmitCounterpartHeader {
+        chain_id: "ethereum".to_string(),
+        header_rlp: Binary::from(header_rlp),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::ProveCounterpartLock {
+        swap_id: swap_id.to_string(),
+        block_number,
+        tx_index: 7,
+        log_index: 0,
+        receipt_proof: trie_proof(&entries, &rlp::encode(&7u64))
+            .into_iter()
+            .map(Binary::from)
+            .collect(),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr(9), &[]), msg)
+}
+
+fn initiate_evm(deps: &mut Deps, leg: CounterpartLegMsg) -> String {
+    let msg = initiate_msg(&addr(2), coin(100, "uatom"), leg);
+    let info = mock_info(&addr(1), &[coin(1_000_100, "uatom")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    common::attr(&res, "swap_id")
+}
+
+#[test]
+fn proven_lock_attests_the_swap() {
+    let mut deps = evm_setup();
+    let swap_id = initiate_evm(&mut deps, evm_leg(None));
+
+    prove(&mut deps, &swap_id, now() + 3600).unwrap();
+    let swap = swap(&deps, &swap_id);
+    assert_eq!(swap.attestation.status, AttestationStatus::LockAttested);
+    assert_eq!(
+        swap.counterpart.counterpart_swap_id,
+        Some(format!("0x{}", hex::encode(SWAP_ID)))
+    );
+}
+
+#[test]
+fn proven_lock_must_leave_the_timelock_margin() {
+    let mut deps = evm_setup();
+    let swap_id = initiate_evm(&mut deps, evm_leg(None));
+
+    // The swap expires at now + 7200, the counterpart needs to expire 600s earlier
+    let err = prove(&mut deps, &swap_id, now() + 6900).unwrap_err();
+    assert!(matches!(err, ContractError::UnsafeTimelockOrdering { .. }));
+    assert_eq!(
+        swap(&deps, &swap_id).attestation.status,
+        AttestationStatus::Unattested
+    );
+}
+
+#[test]
+fn proven_lock_must_match_a_declared_timelock() {
+    let mut deps = evm_setup();
+    let swap_id = initiate_evm(&mut deps, evm_leg(Some(now() + 3600)));
+
+    let err = prove(&mut deps, &swap_id, now() + 3000).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidProof { .. }));
+    prove(&mut deps, &swap_id, now() + 3600).unwrap();
+}
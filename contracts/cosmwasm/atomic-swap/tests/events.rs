@@ -0,0 +1,176 @@
+mod common;
+
+use atomic_swap::contract::execute;
+use atomic_swap::events::{
+    parse_swap_events, RefundReason, SwapCompleted, SwapEvent, SwapInitiated, SwapRefunded,
+    EVENT_SCHEMA_VERSION,
+};
+use atomic_swap::msg::ExecuteMsg;
+use atomic_swap::state::SwapRole;
+use common::{
+    addr, counterpart, hashlock, initiate, initiate_msg, next_block, now, setup, Deps, ADMIN,
+    SECRET,
+};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, Addr, Event, Response, Uint128};
+
+// The only atomic_swap event of a response, read back through the parser
+fn swap_event(res: &Response) -> SwapEvent {
+    let mut events = parse_swap_events(&res.events).unwrap();
+    assert_eq!(events.len(), 1);
+    events.remove(0)
+}
+
+fn swap_id(deps: &mut Deps) -> String {
+    initiate(deps, &addr(1), &addr(2))
+}
+
+#[test]
+fn initiated_event_carries_the_escrow() {
+    let mut deps = setup("chain-b");
+    let mut leg = counterpart("chain-b", addr(200));
+    leg.asset = Some("ubtc".to_string());
+    leg.expected_amount = Some(Uint128::new(7));
+    leg.timelock = Some(now() + 3600);
+    let msg = initiate_msg(&addr(2), coin(100, "uatom"), leg);
+    let info = mock_info(&addr(1), &[coin(1_000_100, "uatom")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let expected = SwapEvent::Initiated(SwapInitiated {
+        swap_id: common::attr(&res, "swap_id"),
+        initiator: Addr::unchecked(addr(1)),
+        participant: Addr::unchecked(addr(2)),
+        denom: "uatom".to_string(),
+        amount: Uint128::new(100),
+        safety_deposit: Uint128::new(1_000_000),
+        escrowed: Uint128::new(1_000_100),
+        hashlock: hashlock(),
+        timelock: now() + 7200,
+        counterpart_chain_id: "chain-b".to_string(),
+        counterpart_recipient: addr(200),
+        counterpart_role: SwapRole::InitiatorLeg,
+        counterpart_asset: Some("ubtc".to_string()),
+        counterpart_amount: Some(Uint128::new(7)),
+        counterpart_timelock: Some(now() + 3600),
+    });
+    assert_eq!(swap_event(&res), expected);
+}
+
+#[test]
+fn completed_event_splits_the_escrow_between_the_parties() {
+    let mut deps = setup("chain-b");
+    let swap_id = swap_id(&mut deps);
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: swap_id.clone(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(&addr(2), &[]), msg).unwrap();
+
+    let expected = SwapEvent::Completed(SwapCompleted {
+        swap_id,
+        initiator: Addr::unchecked(addr(1)),
+        participant: Addr::unchecked(addr(2)),
+        denom: "uatom".to_string(),
+        amount: Uint128::new(100),
+        safety_deposit: Uint128::new(1_000_000),
+        participant_payout: Uint128::new(100),
+        initiator_payout: Uint128::new(1_000_000),
+        hashlock: hashlock(),
+        secret: SECRET.to_string(),
+        counterpart_chain_id: "chain-b".to_string(),
+        counterpart_swap_id: None,
+        completed_at: now(),
+    });
+    assert_eq!(swap_event(&res), expected);
+}
+
+#[test]
+fn refunded_events_return_the_whole_escrow() {
+    let mut deps = setup("chain-b");
+    let expired = swap_id(&mut deps);
+    let env = next_block(&mock_env(), 7200);
+    let msg = ExecuteMsg::RefundSwap {
+        swap_id: expired.clone(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info(&addr(1), &[]), msg).unwrap();
+
+    let refunded = |swap_id: String, reason, refunded_at| {
+        SwapEvent::Refunded(SwapRefunded {
+            swap_id,
+            initiator: Addr::unchecked(addr(1)),
+            denom: "uatom".to_string(),
+            amount: Uint128::new(100),
+            safety_deposit: Uint128::new(1_000_000),
+            initiator_payout: Uint128::new(1_000_100),
+            hashlock: hashlock(),
+            counterpart_chain_id: "chain-b".to_string(),
+            counterpart_swap_id: None,
+            reason,
+            refunded_at,
+        })
+    };
+    assert_eq!(
+        swap_event(&res),
+        refunded(expired, RefundReason::Expired, env.block.time.seconds())
+    );
+
+    // The admin can pull a swap back before it expires
+    let env = next_block(&mock_env(), 0);
+    let emergency = common::initiate_at(&mut deps, env.clone(), &addr(1), &addr(2));
+    let msg = ExecuteMsg::EmergencyRefund {
+        swap_id: emergency.clone(),
+    };
+    let res = execute(deps.as_mut(), env, mock_info(ADMIN, &[]), msg).unwrap();
+    assert_eq!(
+        swap_event(&res),
+        refunded(emergency, RefundReason::Emergency, now())
+    );
+}
+
+#[test]
+fn events_read_back_with_the_wasm_prefix() {
+    let mut deps = setup("chain-b");
+    let msg = initiate_msg(
+        &addr(2),
+        coin(100, "uatom"),
+        counterpart("chain-b", addr(200)),
+    );
+    let info = mock_info(&addr(1), &[coin(1_000_100, "uatom")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let emitted = &res.events[0];
+    let mut indexed = Event::new(format!("wasm-{}", emitted.ty));
+    indexed.attributes = emitted.attributes.clone();
+    let other = Event::new("wasm-transfer").add_attribute("amount", "1uatom");
+
+    let parsed = parse_swap_events(&[other, indexed]).unwrap();
+    assert_eq!(parsed, vec![swap_event(&res)]);
+}
+
+#[test]
+fn unknown_versions_and_missing_fields_are_rejected() {
+    let mut deps = setup("chain-b");
+    let msg = initiate_msg(
+        &addr(2),
+        coin(100, "uatom"),
+        counterpart("chain-b", addr(200)),
+    );
+    let info = mock_info(&addr(1), &[coin(1_000_100, "uatom")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let mut outdated = res.events[0].clone();
+    for attr in outdated.attributes.iter_mut() {
+        if attr.key == "schema_version" {
+            assert_eq!(attr.value, EVENT_SCHEMA_VERSION);
+            attr.value = "0".to_string();
+        }
+    }
+    assert!(SwapEvent::parse(&outdated).is_err());
+
+    // Settlement fields are part of the schema, not optional extras
+    let mut truncated = res.events[0].clone();
+    truncated.attributes.retain(|attr| attr.key != "escrowed");
+    assert!(SwapEvent::parse(&truncated).is_err());
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use atomic_swap::contract::{execute, query};
+use atomic_swap::msg::{ExchangeRateResponse, QueryMsg};
+use common::{addr, counterpart, initiate, initiate_msg, setup, Deps};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, from_json, Decimal, StdResult, Uint128};
+
+fn exchange_rate(
+    deps: &Deps,
+    swap_id: &str,
+    local_decimals: Option<u8>,
+) -> StdResult<ExchangeRateResponse> {
+    let msg = QueryMsg::ExchangeRate {
+        swap_id: swap_id.to_string(),
+        local_decimals,
+    };
+    query(deps.as_ref(), mock_env(), msg).map(|res| from_json(res).unwrap())
+}
+
+// 2 ATOM locked for 0.05 of an 18 decimal asset
+fn initiate_priced(deps: &mut Deps, asset_decimals: Option<u8>) -> String {
+    let mut leg = counterpart("chain-b", addr(200));
+    leg.asset = Some("weth".to_string());
+    leg.expected_amount = Some(Uint128::new(50_000_000_000_000_000));
+    leg.asset_decimals = asset_decimals;
+    let msg = initiate_msg(&addr(2), coin(2_000_000, "uatom"), leg);
+    let info = mock_info(&addr(1), &[coin(3_000_000, "uatom")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    common::attr(&res, "swap_id")
+}
+
+#[test]
+fn rates_scale_by_the_decimals_of_both_assets() {
+    let mut deps = setup("chain-b");
+    let swap_id = initiate_priced(&mut deps, Some(18));
+
+    let res = exchange_rate(&deps, &swap_id, Some(6)).unwrap();
+    assert_eq!(res.locked, coin(2_000_000, "uatom"));
+    assert_eq!(res.counterpart_asset, Some("weth".to_string()));
+    assert_eq!(res.expected_amount, Uint128::new(50_000_000_000_000_000));
+    assert_eq!(res.rate, Decimal::from_ratio(25_000_000_000u128, 1u128));
+    assert_eq!(res.normalized_rate, Some(Decimal::permille(25)));
+
+    // The raw rate needs no decimals, the normalized one needs both
+    let res = exchange_rate(&deps, &swap_id, None).unwrap();
+    assert_eq!(res.rate, Decimal::from_ratio(25_000_000_000u128, 1u128));
+    assert_eq!(res.normalized_rate, None);
+}
+
+#[test]
+fn rates_need_both_sides_of_the_trade() {
+    let mut deps = setup("chain-b");
+    let undeclared = initiate_priced(&mut deps, None);
+    assert_eq!(
+        exchange_rate(&deps, &undeclared, Some(6))
+            .unwrap()
+            .normalized_rate,
+        None
+    );
+
+    let unpriced = initiate(&mut deps, &addr(3), &addr(4));
+    assert!(exchange_rate(&deps, &unpriced, Some(6)).is_err());
+}
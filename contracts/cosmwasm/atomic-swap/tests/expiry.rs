@@ -0,0 +1,87 @@
+mod common;
+
+use atomic_swap::contract::{execute, query};
+use atomic_swap::msg::{ExecuteMsg, ExpiringSwapsResponse, ExpiryCursor, QueryMsg};
+use common::{addr, counterpart, initiate_msg, next_block, now, setup, Deps, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, from_json, Env};
+
+// Initiates a swap expiring at `timelock` in the block after `env`
+fn initiate_until(deps: &mut Deps, env: &mut Env, timelock: u64) -> String {
+    *env = next_block(env, 0);
+    let mut msg = initiate_msg(
+        &addr(2),
+        coin(100, "uatom"),
+        counterpart("chain-b", addr(200)),
+    );
+    if let ExecuteMsg::InitiateSwap { timelock: t, .. } = &mut msg {
+        *t = timelock;
+    }
+    let info = mock_info(&addr(1), &[coin(1_000_100, "uatom")]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    common::attr(&res, "swap_id")
+}
+
+fn expiring(
+    deps: &Deps,
+    env: &Env,
+    before: u64,
+    start_after: Option<ExpiryCursor>,
+    limit: u32,
+) -> ExpiringSwapsResponse {
+    let msg = QueryMsg::ExpiringSwaps {
+        before,
+        start_after,
+        limit: Some(limit),
+    };
+    from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap()
+}
+
+fn listed(res: &ExpiringSwapsResponse) -> Vec<(String, bool)> {
+    res.swaps
+        .iter()
+        .map(|swap| (swap.swap.swap_id.clone(), swap.expired))
+        .collect()
+}
+
+#[test]
+fn settled_swaps_leave_the_expiry_index() {
+    let mut deps = setup("chain-b");
+    let mut env = mock_env();
+    let refunded = initiate_until(&mut deps, &mut env, now() + 3700);
+    let completed = initiate_until(&mut deps, &mut env, now() + 4000);
+    let expired = initiate_until(&mut deps, &mut env, now() + 5000);
+    let open = initiate_until(&mut deps, &mut env, now() + 6000);
+
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: completed,
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info(&addr(2), &[]), msg).unwrap();
+    let env = next_block(&env, 3700);
+    let msg = ExecuteMsg::RefundSwap { swap_id: refunded };
+    execute(deps.as_mut(), env.clone(), mock_info(&addr(1), &[]), msg).unwrap();
+
+    // Swaps past their timelock stay listed until refunded
+    let env = next_block(&env, 1500);
+    let res = expiring(&deps, &env, now() + 10_000, None, 10);
+    assert_eq!(
+        listed(&res),
+        vec![(expired.clone(), true), (open.clone(), false)]
+    );
+    assert_eq!(res.next_start_after, None);
+
+    let first = expiring(&deps, &env, now() + 10_000, None, 1);
+    assert_eq!(listed(&first), vec![(expired.clone(), true)]);
+    let rest = expiring(&deps, &env, now() + 10_000, first.next_start_after, 1);
+    assert_eq!(listed(&rest), vec![(open, false)]);
+
+    // `before` is exclusive
+    assert!(expiring(&deps, &env, now() + 5000, None, 10)
+        .swaps
+        .is_empty());
+    let res = expiring(&deps, &env, now() + 5001, None, 10);
+    assert_eq!(listed(&res), vec![(expired, true)]);
+}
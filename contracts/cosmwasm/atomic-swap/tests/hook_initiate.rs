@@ -0,0 +1,79 @@
+mod common;
+
+use atomic_swap::contract::execute;
+use atomic_swap::error::ContractError;
+use atomic_swap::msg::ExecuteMsg;
+use atomic_swap::transfer::hook_intermediary;
+use bech32::ToBase32;
+use common::{addr, counterpart, hashlock, now, setup, swap, Deps, ADMIN};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, Response};
+
+const VOUCHER: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+const ORIGINAL_SENDER: &str = "osmo1remotesender";
+
+fn intermediary(channel_id: &str, original_sender: &str) -> String {
+    let bytes = hook_intermediary(channel_id, original_sender);
+    bech32::encode("cosmos", bytes.to_base32(), bech32::Variant::Bech32).unwrap()
+}
+
+fn hook_initiate(deps: &mut Deps, sender: &str) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::HookInitiateSwap {
+        source_channel: "channel-3".to_string(),
+        original_sender: ORIGINAL_SENDER.to_string(),
+        initiator: addr(1),
+        participant: addr(2),
+        amount: 100u128.into(),
+        hashlock: hashlock(),
+        timelock: now() + 7200,
+        counterpart: counterpart("chain-b", addr(200)),
+    };
+    let info = mock_info(sender, &[coin(1_000_100, VOUCHER)]);
+    execute(deps.as_mut(), mock_env(), info, msg)
+}
+
+#[test]
+fn hook_initiation_records_the_funding_transfer() {
+    let mut deps = setup("chain-b");
+    let sender = intermediary("channel-3", ORIGINAL_SENDER);
+
+    let res = hook_initiate(&mut deps, &sender).unwrap();
+    let swap = swap(&deps, &common::attr(&res, "swap_id"));
+    assert_eq!(swap.initiator.as_str(), addr(1));
+    assert_eq!(swap.amount, coin(100, VOUCHER));
+
+    let funder = swap.funder.unwrap();
+    assert_eq!(funder.channel_id, "channel-3");
+    assert_eq!(funder.sender, ORIGINAL_SENDER);
+    assert_eq!(funder.intermediary.as_str(), sender);
+}
+
+#[test]
+fn hook_initiation_rejects_other_senders() {
+    let mut deps = setup("chain-b");
+
+    for sender in [
+        addr(1),
+        intermediary("channel-4", ORIGINAL_SENDER),
+        intermediary("channel-3", "osmo1someoneelse"),
+    ] {
+        let err = hook_initiate(&mut deps, &sender).unwrap_err();
+        assert!(matches!(err, ContractError::NotIbcHookSender { .. }));
+    }
+}
+
+#[test]
+fn hook_initiation_screens_the_funding_account() {
+    let mut deps = setup("chain-b");
+    let sender = intermediary("channel-3", ORIGINAL_SENDER);
+    let msg = ExecuteMsg::UpdateAllowlist {
+        add: vec![addr(1), addr(2)],
+        remove: vec![],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    let msg = ExecuteMsg::SetAllowlistOnly { enabled: true };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    let err = hook_initiate(&mut deps, &sender).unwrap_err();
+    assert!(matches!(err, ContractError::AddressNotAllowed { .. }));
+}
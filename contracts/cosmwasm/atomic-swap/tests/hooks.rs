@@ -0,0 +1,109 @@
+mod common;
+
+use atomic_swap::contract::{execute, query};
+use atomic_swap::msg::{AddressesResponse, ExecuteMsg, QueryMsg};
+use atomic_swap::ContractError;
+use common::{addr, initiate, next_block, setup, Deps, ADMIN, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{from_json, CosmosMsg, Response, WasmMsg};
+
+fn update_hooks(
+    deps: &mut Deps,
+    sender: &str,
+    swap_id: &str,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::UpdateSwapHooks {
+        swap_id: swap_id.to_string(),
+        add,
+        remove,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+}
+
+fn notified(res: &Response) -> Vec<String> {
+    res.messages
+        .iter()
+        .filter_map(|msg| match &msg.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => Some(contract_addr.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn swap_hooks(deps: &Deps, swap_id: &str) -> Vec<String> {
+    let msg = QueryMsg::SwapHooks {
+        swap_id: swap_id.to_string(),
+    };
+    let res: AddressesResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    res.addresses
+        .into_iter()
+        .map(|addr| addr.to_string())
+        .collect()
+}
+
+#[test]
+fn completion_runs_the_participant_hooks_only() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+
+    let msg = ExecuteMsg::UpdateHooks {
+        add: vec![addr(50)],
+        remove: vec![],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    update_hooks(&mut deps, &alice, &swap_id, vec![addr(60)], vec![]).unwrap();
+    update_hooks(&mut deps, &bob, &swap_id, vec![addr(70)], vec![]).unwrap();
+    let mut hooks = swap_hooks(&deps, &swap_id);
+    hooks.sort();
+    let mut expected = vec![addr(60), addr(70)];
+    expected.sort();
+    assert_eq!(hooks, expected);
+
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id,
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), msg).unwrap();
+    assert_eq!(notified(&res), vec![addr(50), addr(70)]);
+}
+
+#[test]
+fn refund_runs_the_initiator_hooks_only() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+    update_hooks(&mut deps, &alice, &swap_id, vec![addr(60)], vec![]).unwrap();
+    update_hooks(&mut deps, &bob, &swap_id, vec![addr(70)], vec![]).unwrap();
+
+    let msg = ExecuteMsg::RefundSwap { swap_id };
+    let env = next_block(&mock_env(), 7200);
+    let res = execute(deps.as_mut(), env, mock_info(&alice, &[]), msg).unwrap();
+    assert_eq!(notified(&res), vec![addr(60)]);
+}
+
+#[test]
+fn parties_manage_their_own_hooks() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+
+    let err = update_hooks(&mut deps, &addr(3), &swap_id, vec![addr(60)], vec![]).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    // Removing a hook the other party registered leaves it in place
+    update_hooks(&mut deps, &bob, &swap_id, vec![addr(70)], vec![]).unwrap();
+    update_hooks(&mut deps, &alice, &swap_id, vec![], vec![addr(70)]).unwrap();
+    assert_eq!(swap_hooks(&deps, &swap_id), vec![addr(70)]);
+
+    // One party filling its hooks leaves the other its own
+    let hooks: Vec<_> = (80..85).map(addr).collect();
+    update_hooks(&mut deps, &alice, &swap_id, hooks, vec![]).unwrap();
+    let err = update_hooks(&mut deps, &alice, &swap_id, vec![addr(85)], vec![]).unwrap_err();
+    assert!(matches!(err, ContractError::TooManyHooks { limit: 5, .. }));
+    update_hooks(&mut deps, &bob, &swap_id, vec![addr(71)], vec![]).unwrap();
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use atomic_swap::contract::execute;
+use atomic_swap::error::ContractError;
+use atomic_swap::ibc::{ibc_packet_ack, ibc_packet_receive, ibc_packet_timeout};
+use atomic_swap::ibc_msg::{SwapPacket, SwapPacketAck};
+use atomic_swap::msg::{ExecuteMsg, SwapState};
+use atomic_swap::state::{AttestationStatus, SwapRole};
+use common::{
+    addr, counterpart, hashlock, initiate, initiate_at, initiate_msg, next_block, now, sent_packet,
+    setup, swap, Deps, ADMIN, CHANNEL, SECRET,
+};
+use cosmwasm_std::testing::{
+    mock_env, mock_ibc_packet_ack, mock_ibc_packet_recv, mock_ibc_packet_timeout, mock_info,
+};
+use cosmwasm_std::{coin, from_json, Env, IbcAcknowledgement, IbcReceiveResponse, Uint128};
+
+fn receive(deps: &mut Deps, packet: &SwapPacket) -> Result<IbcReceiveResponse, ContractError> {
+    let msg = mock_ibc_packet_recv(CHANNEL, packet).unwrap();
+    ibc_packet_receive(deps.as_mut(), mock_env(), msg)
+}
+
+fn secret_packet(counterpart_swap_id: &str) -> SwapPacket {
+    SwapPacket::SecretRevealed {
+        swap_id: counterpart_swap_id.to_string(),
+        hashlock: hashlock(),
+        secret: SECRET.to_string(),
+    }
+}
+
+fn complete(deps: &mut Deps, participant: &str, swap_id: &str) -> SwapPacket {
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: swap_id.to_string(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(participant, &[]), msg).unwrap();
+    sent_packet(&res.messages)
+}
+
+fn timeout(deps: &mut Deps, env: Env, packet: &SwapPacket) -> Option<SwapPacket> {
+    let msg = mock_ibc_packet_timeout(CHANNEL, packet).unwrap();
+    let res = ibc_packet_timeout(deps.as_mut(), env, msg).unwrap();
+    (!res.messages.is_empty()).then(|| sent_packet(&res.messages))
+}
+
+#[test]
+fn two_deployments_settle_a_swap_over_ibc() {
+    let (alice_a, alice_b, bob_a, bob_b) = (addr(1), addr(2), addr(3), addr(4));
+    let mut chain_a = setup("chain-b");
+    let mut chain_b = setup("chain-a");
+
+    // Alice locks on chain A, paying Bob, and expects 500uosmo on chain B
+    let mut leg = counterpart("chain-b", alice_b.clone());
+    leg.expected_amount = Some(Uint128::new(500));
+    let msg = initiate_msg(&bob_a, coin(100, "uatom"), leg);
+    let info = mock_info(&alice_a, &[coin(1_000_100, "uatom")]);
+    let res = execute(chain_a.as_mut(), mock_env(), info, msg).unwrap();
+    let swap_a = common::attr(&res, "swap_id");
+    let lock_a = sent_packet(&res.messages);
+
+    // Nothing on chain B waits for Alice's lock yet, it is kept for later swaps
+    let res = receive(&mut chain_b, &lock_a).unwrap();
+    assert_eq!(common::attr_of(&res.attributes, "matched_swaps"), "0");
+
+    // Bob responds on chain B, and the stored lock attests his swap at once
+    let mut leg = counterpart("chain-a", bob_a.clone());
+    leg.asset = Some("uatom".to_string());
+    leg.expected_amount = Some(Uint128::new(100));
+    leg.timelock = Some(now() + 7200);
+    leg.role = Some(SwapRole::ResponderLeg);
+    let mut msg = initiate_msg(&alice_b, coin(500, "uosmo"), leg);
+    if let ExecuteMsg::InitiateSwap { timelock, .. } = &mut msg {
+        *timelock = now() + 3700;
+    }
+    let info = mock_info(&bob_b, &[coin(1_000_500, "uosmo")]);
+    let res = execute(chain_b.as_mut(), mock_env(), info, msg).unwrap();
+    let swap_b = common::attr(&res, "swap_id");
+    assert_eq!(
+        swap(&chain_b, &swap_b).attestation.status,
+        AttestationStatus::LockAttested
+    );
+
+    // Bob's lock reaches chain A and links the two swaps
+    receive(&mut chain_a, &sent_packet(&res.messages)).unwrap();
+    let on_a = swap(&chain_a, &swap_a);
+    assert_eq!(on_a.attestation.status, AttestationStatus::LockAttested);
+    assert_eq!(on_a.counterpart.counterpart_swap_id, Some(swap_b.clone()));
+
+    // Alice claims on chain B, and the revealed secret completes chain A
+    let revealed = complete(&mut chain_b, &alice_b, &swap_b);
+    let res = receive(&mut chain_a, &revealed).unwrap();
+    let ack: SwapPacketAck = from_json(&res.acknowledgement).unwrap();
+    assert!(matches!(ack, SwapPacketAck::Result(_)));
+    let on_a = swap(&chain_a, &swap_a);
+    assert_eq!(on_a.state, SwapState::Completed);
+    assert_eq!(on_a.secret.as_deref(), Some(SECRET));
+}
+
+#[test]
+fn relayed_secret_skips_settled_swaps() {
+    let mut deps = setup("chain-b");
+    let (alice, bob, carol) = (addr(1), addr(2), addr(3));
+    let settled = initiate(&mut deps, &alice, &bob);
+    let open = initiate_at(&mut deps, next_block(&mock_env(), 5), &alice, &carol);
+    complete(&mut deps, &bob, &settled);
+
+    let res = receive(&mut deps, &secret_packet("remote-swap")).unwrap();
+    assert_eq!(
+        common::attr_of(&res.attributes, "completed_swap_id"),
+        open.as_str()
+    );
+    assert_eq!(swap(&deps, &open).state, SwapState::Completed);
+}
+
+#[test]
+fn relayed_secret_fails_without_an_eligible_swap() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+
+    let err = receive(&mut deps, &secret_packet("remote-swap")).unwrap_err();
+    assert!(matches!(err, ContractError::NoCompletableSwap { .. }));
+
+    let swap_id = initiate(&mut deps, &alice, &bob);
+    complete(&mut deps, &bob, &swap_id);
+    let err = receive(&mut deps, &secret_packet("remote-swap")).unwrap_err();
+    assert!(matches!(err, ContractError::NoCompletableSwap { .. }));
+
+    // Swaps linked to another counterpart swap are not completed either
+    let linked = initiate_at(&mut deps, next_block(&mock_env(), 5), &alice, &bob);
+    let msg = ExecuteMsg::LinkCounterpart {
+        swap_id: linked.clone(),
+        counterpart_swap_id: "other-swap".to_string(),
+        lock_tx_hash: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&alice, &[]), msg).unwrap();
+    let err = receive(&mut deps, &secret_packet("remote-swap")).unwrap_err();
+    assert!(matches!(err, ContractError::NoCompletableSwap { .. }));
+    assert_eq!(swap(&deps, &linked).state, SwapState::Initiated);
+}
+
+#[test]
+fn timed_out_lock_is_resent_while_the_swap_is_open() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let msg = initiate_msg(&bob, coin(100, "uatom"), counterpart("chain-b", addr(200)));
+    let info = mock_info(&alice, &[coin(1_000_100, "uatom")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let swap_id = common::attr(&res, "swap_id");
+    let lock = sent_packet(&res.messages);
+
+    assert_eq!(timeout(&mut deps, mock_env(), &lock), Some(lock.clone()));
+    assert_eq!(
+        timeout(&mut deps, next_block(&mock_env(), 7200), &lock),
+        None
+    );
+
+    complete(&mut deps, &bob, &swap_id);
+    assert_eq!(timeout(&mut deps, mock_env(), &lock), None);
+}
+
+#[test]
+fn timed_out_refund_is_resent_until_the_counterpart_expires() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+
+    let msg = ExecuteMsg::EmergencyRefund {
+        swap_id: swap_id.clone(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    let refunded = sent_packet(&res.messages);
+    assert!(matches!(refunded, SwapPacket::Refunded { .. }));
+
+    assert_eq!(
+        timeout(&mut deps, mock_env(), &refunded),
+        Some(refunded.clone())
+    );
+    assert_eq!(
+        timeout(&mut deps, next_block(&mock_env(), 7200), &refunded),
+        None
+    );
+}
+
+#[test]
+fn error_acks_are_reported() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+    let packet = SwapPacket::Refunded {
+        swap_id,
+        hashlock: hashlock(),
+    };
+
+    let ack = IbcAcknowledgement::encode_json(&SwapPacketAck::Error("rejected".into())).unwrap();
+    let msg = mock_ibc_packet_ack(CHANNEL, &packet, ack).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(common::attr_of(&res.attributes, "success"), "false");
+    assert_eq!(common::attr_of(&res.attributes, "error"), "rejected");
+}
@@ -0,0 +1,242 @@
+mod common;
+
+use atomic_swap::contract::{execute, query, reply, sudo};
+use atomic_swap::msg::{ExecuteMsg, QueryMsg, RequestPacket, SudoMsg};
+use atomic_swap::proto::{decode_fields, encode_any, encode_bytes, find_bytes, Field};
+use atomic_swap::state::{RemoteLeg, RemoteLegStatus, SwapRole};
+use atomic_swap::ContractError;
+use common::{addr, counterpart, hashlock, initiate, initiate_msg, now, setup, Deps, ADMIN};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{
+    coin, from_json, Binary, Coin, CosmosMsg, Reply, Response, SubMsgResponse, SubMsgResult,
+};
+
+const ICA_ID: &str = "alice";
+const ICA_CHANNEL: &str = "channel-9";
+const ICA_ADDRESS: &str = "cosmos1ica";
+
+// Registers an account on chain-b and lets the host open it
+fn open_ica(deps: &mut Deps) {
+    let msg = ExecuteMsg::RegisterIca {
+        ica_id: ICA_ID.to_string(),
+        chain_id: "chain-b".to_string(),
+        connection_id: "connection-0".to_string(),
+        remote_contract: "cosmos1remote".to_string(),
+        ack_fee: vec![],
+        timeout_fee: vec![],
+        timeout_seconds: 600,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    let msg = SudoMsg::OpenAck {
+        port_id: format!("icacontroller-{}.{}", mock_env().contract.address, ICA_ID),
+        channel_id: ICA_CHANNEL.to_string(),
+        counterparty_channel_id: "channel-3".to_string(),
+        counterparty_version: format!("{{\"address\":\"{}\"}}", ICA_ADDRESS),
+    };
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+}
+
+fn lock(deps: &mut Deps, swap_id: &str, timelock: u64) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::IcaLockCounterpart {
+        swap_id: swap_id.to_string(),
+        ica_id: ICA_ID.to_string(),
+        amount: coin(500, "uosmo"),
+        safety_deposit: 1_000_000u128.into(),
+        timelock,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg)
+}
+
+// Unpacks the MsgExecuteContract carried by the MsgSubmitTx
+fn remote_execute(res: &Response) -> (ExecuteMsg, Vec<Coin>) {
+    let value = match &res.messages[0].msg {
+        CosmosMsg::Stargate { value, .. } => value,
+        msg => panic!("unexpected message {:?}", msg),
+    };
+    let submit = decode_fields(value.as_slice()).unwrap();
+    let any = decode_fields(find_bytes(&submit, 4).unwrap()).unwrap();
+    let fields = decode_fields(find_bytes(&any, 2).unwrap()).unwrap();
+    let msg = from_json(find_bytes(&fields, 3).unwrap()).unwrap();
+    let funds = fields
+        .iter()
+        .filter(|(number, _)| *number == 5)
+        .map(|(_, field)| match field {
+            Field::Bytes(coin) => {
+                let coin = decode_fields(coin).unwrap();
+                let denom = std::str::from_utf8(find_bytes(&coin, 1).unwrap()).unwrap();
+                let amount = std::str::from_utf8(find_bytes(&coin, 2).unwrap()).unwrap();
+                Coin::new(amount.parse().unwrap(), denom)
+            }
+            _ => panic!("coin is not a message"),
+        })
+        .collect();
+    (msg, funds)
+}
+
+// MsgSubmitTxResponse { sequence, channel }
+fn submitted(deps: &mut Deps, sequence: u8) {
+    let mut data = vec![8, sequence];
+    encode_bytes(&mut data, 2, ICA_CHANNEL.as_bytes());
+    let result = SubMsgResult::Ok(SubMsgResponse {
+        events: vec![],
+        data: Some(Binary::from(data)),
+    });
+    let msg = Reply {
+        id: atomic_swap::ica::ICA_SUBMIT_REPLY_ID,
+        result,
+    };
+    reply(deps.as_mut(), mock_env(), msg).unwrap();
+}
+
+fn request(sequence: u64) -> RequestPacket {
+    RequestPacket {
+        sequence: Some(sequence),
+        source_port: Some(format!(
+            "icacontroller-{}.{}",
+            mock_env().contract.address,
+            ICA_ID
+        )),
+        source_channel: Some(ICA_CHANNEL.to_string()),
+        destination_port: Some("icahost".to_string()),
+        destination_channel: Some("channel-3".to_string()),
+        data: None,
+        timeout_height: None,
+        timeout_timestamp: None,
+    }
+}
+
+// TxMsgData { msg_responses: [Any { MsgExecuteContractResponse { data } }] }
+fn ack(remote_swap_id: &str) -> Binary {
+    let mut response = vec![];
+    encode_bytes(
+        &mut response,
+        1,
+        format!("\"{}\"", remote_swap_id).as_bytes(),
+    );
+    let mut data = vec![];
+    encode_any(
+        &mut data,
+        2,
+        "/cosmwasm.wasm.v1.MsgExecuteContractResponse",
+        &response,
+    );
+    Binary::from(data)
+}
+
+fn remote_leg(deps: &Deps, swap_id: &str) -> RemoteLeg {
+    let msg = QueryMsg::RemoteLeg {
+        swap_id: swap_id.to_string(),
+    };
+    from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+}
+
+#[test]
+fn lock_funds_the_remote_swap_with_amount_and_deposit() {
+    let mut deps = setup("chain-b");
+    open_ica(&mut deps);
+    let swap_id = initiate(&mut deps, &addr(1), &addr(2));
+
+    let res = lock(&mut deps, &swap_id, now() + 3700).unwrap();
+    let (msg, funds) = remote_execute(&res);
+    assert_eq!(funds, vec![coin(1_000_500, "uosmo")]);
+    match msg {
+        ExecuteMsg::InitiateSwap {
+            amount,
+            counterpart: Some(counterpart),
+            ..
+        } => {
+            assert_eq!(amount, coin(500, "uosmo"));
+            assert_eq!(counterpart.role, Some(SwapRole::ResponderLeg));
+        }
+        msg => panic!("unexpected remote message {:?}", msg),
+    }
+}
+
+#[test]
+fn responder_swap_locks_an_initiator_leg_expiring_later() {
+    let mut deps = setup("chain-b");
+    open_ica(&mut deps);
+    let mut leg = counterpart("chain-b", addr(200));
+    leg.timelock = Some(now() + 7200);
+    leg.role = Some(SwapRole::ResponderLeg);
+    let mut msg = initiate_msg(&addr(2), coin(100, "uatom"), leg);
+    if let ExecuteMsg::InitiateSwap { timelock, .. } = &mut msg {
+        *timelock = now() + 3700;
+    }
+    let info = mock_info(&addr(1), &[coin(1_000_100, "uatom")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let swap_id = common::attr(&res, "swap_id");
+
+    // The remote initiator leg has to outlive the local responder leg
+    let err = lock(&mut deps, &swap_id, now() + 3700).unwrap_err();
+    assert!(matches!(err, ContractError::UnsafeTimelockOrdering { .. }));
+
+    let res = lock(&mut deps, &swap_id, now() + 7200).unwrap();
+    match remote_execute(&res).0 {
+        ExecuteMsg::InitiateSwap {
+            hashlock: remote_hashlock,
+            counterpart: Some(counterpart),
+            ..
+        } => {
+            assert_eq!(remote_hashlock, hashlock());
+            assert_eq!(counterpart.role, Some(SwapRole::InitiatorLeg));
+            assert_eq!(counterpart.timelock, Some(now() + 3700));
+        }
+        msg => panic!("unexpected remote message {:?}", msg),
+    }
+}
+
+#[test]
+fn acknowledged_lock_records_the_remote_swap() {
+    let mut deps = setup("chain-b");
+    open_ica(&mut deps);
+    let swap_id = initiate(&mut deps, &addr(1), &addr(2));
+    lock(&mut deps, &swap_id, now() + 3700).unwrap();
+    submitted(&mut deps, 4);
+    assert_eq!(remote_leg(&deps, &swap_id).status, RemoteLegStatus::Pending);
+
+    let msg = SudoMsg::Response {
+        request: request(4),
+        data: ack("remote-swap"),
+    };
+    let res = sudo(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(common::attr(&res, "swap_id"), swap_id);
+
+    let leg = remote_leg(&deps, &swap_id);
+    assert_eq!(leg.status, RemoteLegStatus::Locked);
+    assert_eq!(leg.remote_swap_id.as_deref(), Some("remote-swap"));
+}
+
+#[test]
+fn failed_lock_can_be_retried() {
+    let mut deps = setup("chain-b");
+    open_ica(&mut deps);
+    let swap_id = initiate(&mut deps, &addr(1), &addr(2));
+    lock(&mut deps, &swap_id, now() + 3700).unwrap();
+    submitted(&mut deps, 1);
+
+    // Nothing else can be submitted while the lock is in flight
+    let err = lock(&mut deps, &swap_id, now() + 3700).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidRemoteLeg { .. }));
+
+    let msg = SudoMsg::Timeout {
+        request: request(1),
+    };
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+    let leg = remote_leg(&deps, &swap_id);
+    assert_eq!(leg.status, RemoteLegStatus::Failed);
+    assert_eq!(leg.error.as_deref(), Some("timeout"));
+
+    lock(&mut deps, &swap_id, now() + 3700).unwrap();
+    submitted(&mut deps, 2);
+    let msg = SudoMsg::Error {
+        request: request(2),
+        details: "out of gas".to_string(),
+    };
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(
+        remote_leg(&deps, &swap_id).error.as_deref(),
+        Some("out of gas")
+    );
+}
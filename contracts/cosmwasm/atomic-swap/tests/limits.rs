@@ -0,0 +1,217 @@
+mod common;
+
+use atomic_swap::contract::{execute, query};
+use atomic_swap::msg::{AllowanceResponse, ExecuteMsg, QueryMsg};
+use atomic_swap::state::{OutflowLimits, SwapLimits};
+use atomic_swap::ContractError;
+use common::{addr, counterpart, initiate_msg, next_block, setup, Deps, ADMIN, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, from_json, Env, Response, Uint128};
+
+const HOUR: u64 = 3600;
+
+fn limit(deps: &mut Deps, limits: SwapLimits) {
+    let msg = ExecuteMsg::UpdateLimits { limits };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+}
+
+fn limits() -> SwapLimits {
+    SwapLimits {
+        max_active_swaps_per_initiator: None,
+        max_active_swaps_per_participant: None,
+        max_volume_per_window: vec![],
+        volume_window_seconds: 24 * HOUR,
+        min_swap_amount: vec![],
+    }
+}
+
+// Initiates a 100uatom swap expiring two hours after `env`
+fn initiate(
+    deps: &mut Deps,
+    env: &Env,
+    initiator: &str,
+    participant: &str,
+) -> Result<Response, ContractError> {
+    let mut msg = initiate_msg(
+        participant,
+        coin(100, "uatom"),
+        counterpart("chain-b", addr(200)),
+    );
+    if let ExecuteMsg::InitiateSwap { timelock, .. } = &mut msg {
+        *timelock = env.block.time.seconds() + 2 * HOUR;
+    }
+    let info = mock_info(initiator, &[coin(1_000_100, "uatom")]);
+    execute(deps.as_mut(), env.clone(), info, msg)
+}
+
+fn allowance(deps: &Deps, env: &Env, address: &str) -> AllowanceResponse {
+    let msg = QueryMsg::Allowance {
+        address: address.to_string(),
+        denom: "uatom".to_string(),
+    };
+    from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap()
+}
+
+#[test]
+fn active_swaps_count_against_the_initiator_only() {
+    let mut deps = setup("chain-b");
+    let mut capped = limits();
+    capped.max_active_swaps_per_initiator = Some(1);
+    limit(&mut deps, capped);
+    let (alice, bob, victim) = (addr(1), addr(2), addr(3));
+
+    let env = mock_env();
+    let res = initiate(&mut deps, &env, &alice, &victim).unwrap();
+    let swap_id = common::attr(&res, "swap_id");
+    let env = next_block(&env, 5);
+    let err = initiate(&mut deps, &env, &alice, &bob).unwrap_err();
+    assert!(matches!(err, ContractError::TooManyActiveSwaps { .. }));
+
+    // Others naming the victim as participant take none of the victim's slots
+    initiate(&mut deps, &env, &bob, &victim).unwrap();
+    initiate(&mut deps, &env, &victim, &bob).unwrap();
+    assert_eq!(
+        allowance(&deps, &env, &victim).remaining_swaps_as_initiator,
+        Some(0)
+    );
+
+    // Completing the swap frees the initiator's slot
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id,
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info(&victim, &[]), msg).unwrap();
+    let env = next_block(&env, 5);
+    initiate(&mut deps, &env, &alice, &bob).unwrap();
+}
+
+#[test]
+fn volume_leaves_the_window_as_it_rolls() {
+    let mut deps = setup("chain-b");
+    let mut capped = limits();
+    capped.max_volume_per_window = vec![coin(250, "uatom")];
+    limit(&mut deps, capped);
+    let (alice, bob) = (addr(1), addr(2));
+
+    let start = mock_env();
+    initiate(&mut deps, &start, &alice, &bob).unwrap();
+    let env = next_block(&start, 12 * HOUR);
+    initiate(&mut deps, &env, &alice, &bob).unwrap();
+
+    let allowance_now = allowance(&deps, &env, &alice);
+    assert_eq!(allowance_now.volume_in_window, Uint128::new(200));
+    assert_eq!(allowance_now.remaining_volume, Some(Uint128::new(50)));
+    let frees_at = allowance_now.volume_frees_at.unwrap();
+    assert!(frees_at > start.block.time.seconds() + 24 * HOUR);
+    assert!(frees_at <= start.block.time.seconds() + 25 * HOUR);
+
+    let env = next_block(&start, 24 * HOUR - 1);
+    let err = initiate(&mut deps, &env, &alice, &bob).unwrap_err();
+    assert!(matches!(err, ContractError::VolumeLimitExceeded { .. }));
+
+    // Only the first swap left the window, the second one still counts
+    let mut env = next_block(&start, 0);
+    env.block.time = env
+        .block
+        .time
+        .plus_seconds(frees_at - start.block.time.seconds());
+    assert_eq!(
+        allowance(&deps, &env, &alice).volume_in_window,
+        Uint128::new(100)
+    );
+    initiate(&mut deps, &env, &alice, &bob).unwrap();
+    let env = next_block(&env, 1);
+    let err = initiate(&mut deps, &env, &alice, &bob).unwrap_err();
+    assert!(matches!(err, ContractError::VolumeLimitExceeded { .. }));
+}
+
+#[test]
+fn only_the_admin_caps_outflow() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let env = mock_env();
+    let first = common::attr(&initiate(&mut deps, &env, &alice, &bob).unwrap(), "swap_id");
+    let env = next_block(&env, 5);
+    let second = common::attr(&initiate(&mut deps, &env, &alice, &bob).unwrap(), "swap_id");
+
+    // Room for one swap and its safety deposit per epoch
+    let capped = OutflowLimits {
+        epoch_seconds: 24 * HOUR,
+        caps: vec![coin(1_000_100, "uatom")],
+    };
+    let msg = ExecuteMsg::UpdateOutflowLimits {
+        limits: capped.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&alice, &[]),
+        msg.clone(),
+    );
+    assert!(matches!(err.unwrap_err(), ContractError::Unauthorized {}));
+    execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+    let stored: OutflowLimits =
+        from_json(query(deps.as_ref(), env.clone(), QueryMsg::OutflowLimits {}).unwrap()).unwrap();
+    assert_eq!(stored, capped);
+
+    let complete = |swap_id: &str| ExecuteMsg::CompleteSwap {
+        swap_id: swap_id.to_string(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        complete(&first),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        complete(&second),
+    );
+    assert!(matches!(
+        err.unwrap_err(),
+        ContractError::OutflowLimitExceeded { .. }
+    ));
+}
+
+#[test]
+fn active_swaps_count_against_the_participant() {
+    let mut deps = setup("chain-b");
+    let mut capped = limits();
+    capped.max_active_swaps_per_participant = Some(1);
+    limit(&mut deps, capped);
+    let (alice, bob, carol) = (addr(1), addr(2), addr(3));
+
+    let env = mock_env();
+    let res = initiate(&mut deps, &env, &alice, &bob).unwrap();
+    let swap_id = common::attr(&res, "swap_id");
+    let env = next_block(&env, 5);
+    let err = initiate(&mut deps, &env, &carol, &bob).unwrap_err();
+    match err {
+        ContractError::TooManyActiveSwaps { address, limit } => {
+            assert_eq!((address, limit), (bob.clone(), 1))
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+    let allowance_now = allowance(&deps, &env, &bob);
+    assert_eq!(allowance_now.active_swaps_as_participant, 1);
+    assert_eq!(allowance_now.remaining_swaps_as_participant, Some(0));
+    assert_eq!(allowance_now.remaining_swaps_as_initiator, None);
+
+    // A refund frees the participant's slot just like a completion
+    let env = next_block(&env, 2 * HOUR);
+    let msg = ExecuteMsg::RefundSwap { swap_id };
+    execute(deps.as_mut(), env.clone(), mock_info(&alice, &[]), msg).unwrap();
+    assert_eq!(
+        allowance(&deps, &env, &bob).remaining_swaps_as_participant,
+        Some(1)
+    );
+    initiate(&mut deps, &env, &carol, &bob).unwrap();
+}
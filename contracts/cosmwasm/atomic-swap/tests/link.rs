@@ -0,0 +1,200 @@
+mod common;
+
+use atomic_swap::contract::{execute, query};
+use atomic_swap::msg::{ExecuteMsg, QueryMsg, SwapResponse};
+use atomic_swap::state::{AddressFormat, CounterpartChain};
+use atomic_swap::ContractError;
+use common::{
+    addr, counterpart, initiate, initiate_at, initiate_msg, next_block, setup, swap, Deps, ADMIN,
+    SECRET,
+};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, from_json, Response, StdResult};
+
+const SEPOLIA: &str = "11155111";
+const HTLC_ID: &str = "0xC6A1B8E4D0F2937A5B4C3D2E1F0A9B8C7D6E5F4A3B2C1D0E9F8A7B6C5D4E3F2A";
+
+fn link(
+    deps: &mut Deps,
+    sender: &str,
+    swap_id: &str,
+    counterpart_swap_id: &str,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::LinkCounterpart {
+        swap_id: swap_id.to_string(),
+        counterpart_swap_id: counterpart_swap_id.to_string(),
+        lock_tx_hash: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+}
+
+fn by_counterpart_id(
+    deps: &Deps,
+    chain_id: &str,
+    counterpart_swap_id: &str,
+) -> StdResult<SwapResponse> {
+    let msg = QueryMsg::SwapByCounterpartId {
+        chain_id: chain_id.to_string(),
+        counterpart_swap_id: counterpart_swap_id.to_string(),
+    };
+    query(deps.as_ref(), mock_env(), msg).map(|res| from_json(res).unwrap())
+}
+
+// A swap whose counterpart leg locks on Sepolia
+fn initiate_evm(deps: &mut Deps) -> String {
+    let chain = CounterpartChain {
+        chain_id: SEPOLIA.to_string(),
+        name: "Sepolia".to_string(),
+        address_format: AddressFormat::Evm {},
+        min_timelock_margin: 600,
+        enabled: true,
+        htlc_contract: None,
+        ibc_channel: None,
+    };
+    let msg = ExecuteMsg::UpsertCounterpartChain { chain };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    let leg = counterpart(
+        SEPOLIA,
+        "0x52908400098527886E0F7030069857D2E4169EE7".to_string(),
+    );
+    let msg = initiate_msg(&addr(2), coin(100, "uatom"), leg);
+    let info = mock_info(&addr(1), &[coin(1_000_100, "uatom")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    common::attr(&res, "swap_id")
+}
+
+#[test]
+fn only_the_parties_and_relayers_link() {
+    let mut deps = setup("chain-b");
+    let swap_id = initiate(&mut deps, &addr(1), &addr(2));
+
+    let err = link(&mut deps, &addr(3), &swap_id, "htlc-1").unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    // Relayers are managed by the admin alone
+    let msg = ExecuteMsg::UpdateRelayers {
+        add: vec![addr(3)],
+        remove: vec![],
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&addr(3), &[]),
+        msg.clone(),
+    );
+    assert!(matches!(err.unwrap_err(), ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    let res = link(&mut deps, &addr(3), &swap_id, "htlc-1").unwrap();
+    assert_eq!(common::attr(&res, "linked_by"), addr(3));
+    link(&mut deps, &addr(1), &swap_id, "htlc-1").unwrap();
+    link(&mut deps, &addr(2), &swap_id, "htlc-1").unwrap();
+    assert_eq!(
+        swap(&deps, &swap_id).counterpart.counterpart_swap_id,
+        Some("htlc-1".to_string())
+    );
+}
+
+#[test]
+fn evm_swap_ids_are_stored_lowercased() {
+    let mut deps = setup("chain-b");
+    let swap_id = initiate_evm(&mut deps);
+
+    for invalid in ["0x1234", "htlc-1", &HTLC_ID.replace("0x", "0z")] {
+        let err = link(&mut deps, &addr(1), &swap_id, invalid).unwrap_err();
+        assert!(
+            matches!(err, ContractError::InvalidCounterpartLeg { .. }),
+            "{}",
+            invalid
+        );
+    }
+
+    link(&mut deps, &addr(1), &swap_id, &format!(" {} ", HTLC_ID)).unwrap();
+    let lowercased = HTLC_ID.to_ascii_lowercase();
+    assert_eq!(
+        swap(&deps, &swap_id).counterpart.counterpart_swap_id,
+        Some(lowercased.clone())
+    );
+
+    // Relinking the same id in another casing is a no-op
+    link(&mut deps, &addr(2), &swap_id, &lowercased).unwrap();
+
+    // Lookups take the id as the counterpart chain prints it
+    for input in [HTLC_ID, lowercased.as_str()] {
+        let found = by_counterpart_id(&deps, SEPOLIA, input).unwrap();
+        assert_eq!(found.swap_id, swap_id);
+    }
+    assert!(by_counterpart_id(&deps, "chain-b", HTLC_ID).is_err());
+}
+
+#[test]
+fn counterpart_ids_link_one_swap_each() {
+    let mut deps = setup("chain-b");
+    let first = initiate(&mut deps, &addr(1), &addr(2));
+    let second = initiate_at(&mut deps, next_block(&mock_env(), 0), &addr(1), &addr(2));
+
+    link(&mut deps, &addr(1), &first, "htlc-1").unwrap();
+    let err = link(&mut deps, &addr(1), &first, "htlc-2").unwrap_err();
+    match err {
+        ContractError::CounterpartAlreadyLinked {
+            swap_id,
+            counterpart_swap_id,
+        } => assert_eq!(
+            (swap_id, counterpart_swap_id),
+            (first.clone(), "htlc-1".to_string())
+        ),
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    let err = link(&mut deps, &addr(1), &second, "htlc-1").unwrap_err();
+    match err {
+        ContractError::CounterpartAlreadyLinked { swap_id, .. } => assert_eq!(swap_id, first),
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    link(&mut deps, &addr(1), &second, "htlc-2").unwrap();
+    assert_eq!(
+        by_counterpart_id(&deps, "chain-b", "htlc-1")
+            .unwrap()
+            .swap_id,
+        first
+    );
+    assert_eq!(
+        by_counterpart_id(&deps, "chain-b", "htlc-2")
+            .unwrap()
+            .swap_id,
+        second
+    );
+    assert!(by_counterpart_id(&deps, "chain-b", "htlc-3").is_err());
+}
+
+#[test]
+fn settled_swaps_cannot_be_linked() {
+    let mut deps = setup("chain-b");
+    let completed = initiate(&mut deps, &addr(1), &addr(2));
+    let env = next_block(&mock_env(), 0);
+    let refunded = initiate_at(&mut deps, env.clone(), &addr(1), &addr(2));
+
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: completed.clone(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr(2), &[]), msg).unwrap();
+    let err = link(&mut deps, &addr(1), &completed, "htlc-1").unwrap_err();
+    assert!(matches!(err, ContractError::SwapAlreadyCompleted {}));
+
+    let msg = ExecuteMsg::RefundSwap {
+        swap_id: refunded.clone(),
+    };
+    let env = next_block(&env, 7200);
+    execute(deps.as_mut(), env, mock_info(&addr(1), &[]), msg).unwrap();
+    let err = link(&mut deps, &addr(1), &refunded, "htlc-2").unwrap_err();
+    assert!(matches!(err, ContractError::SwapAlreadyRefunded {}));
+
+    // Neither attempt claimed the counterpart id
+    assert!(by_counterpart_id(&deps, "chain-b", "htlc-1").is_err());
+    assert!(by_counterpart_id(&deps, "chain-b", "htlc-2").is_err());
+}
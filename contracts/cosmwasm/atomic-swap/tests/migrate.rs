@@ -0,0 +1,186 @@
+mod common;
+
+use atomic_swap::contract::{execute, migrate, query};
+use atomic_swap::migrations::LegacyAtomicSwap;
+use atomic_swap::msg::{ExecuteMsg, MigrateMsg, QueryMsg, SwapState, SwapsResponse};
+use atomic_swap::state::{swaps, AtomicSwap, ChainFamily, SwapRole};
+use atomic_swap::ContractError;
+use common::{addr, hashlock, initiate, now, setup, swap, Deps, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, from_json, Addr, Response, StdError, Storage, Uint128};
+use cw_storage_plus::Map;
+
+const RECORDS: Map<&str, LegacyAtomicSwap> = Map::new("swaps");
+const BY_INITIATOR: Map<(&str, &str), bool> = Map::new("swaps_by_initiator");
+const BY_PARTICIPANT: Map<(&str, &str), bool> = Map::new("swaps_by_participant");
+const BY_HASHLOCK: Map<(&str, &str), bool> = Map::new("swaps_by_hashlock");
+
+fn legacy(swap_id: &str) -> LegacyAtomicSwap {
+    LegacyAtomicSwap {
+        swap_id: swap_id.to_string(),
+        initiator: Addr::unchecked(addr(1)),
+        participant: Addr::unchecked(addr(2)),
+        amount: coin(100, "uatom"),
+        hashlock: hashlock(),
+        timelock: now() + 7200,
+        state: SwapState::Initiated,
+        secret: None,
+        ethereum_recipient: "0x52908400098527886E0F7030069857D2E4169EE7".to_string(),
+        ethereum_chain_id: "11155111".to_string(),
+        created_at: now(),
+        completed_at: None,
+        safety_deposit: Uint128::new(1_000_000),
+    }
+}
+
+// Writes the index entries every swap got before the IndexedMap
+fn index_by_hand(deps: &mut Deps, swap_id: &str) {
+    let storage = deps.as_mut().storage;
+    BY_INITIATOR
+        .save(storage, (&addr(1), swap_id), &true)
+        .unwrap();
+    BY_PARTICIPANT
+        .save(storage, (&addr(2), swap_id), &true)
+        .unwrap();
+    BY_HASHLOCK
+        .save(storage, (&hashlock(), swap_id), &true)
+        .unwrap();
+}
+
+// A swap as stored by a deployment from before counterpart legs
+fn store_legacy(deps: &mut Deps, swap_id: &str) {
+    RECORDS
+        .save(deps.as_mut().storage, swap_id, &legacy(swap_id))
+        .unwrap();
+    index_by_hand(deps, swap_id);
+}
+
+fn upgrade(deps: &mut Deps) -> Result<Response, ContractError> {
+    let msg = MigrateMsg {
+        counterpart_chains: None,
+    };
+    migrate(deps.as_mut(), mock_env(), msg)
+}
+
+fn by_initiator(deps: &Deps, start_after: Option<String>) -> Vec<String> {
+    let msg = QueryMsg::SwapsByInitiator {
+        initiator: addr(1),
+        start_after,
+        limit: Some(30),
+    };
+    let res: SwapsResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    res.swaps.into_iter().map(|swap| swap.swap_id).collect()
+}
+
+#[test]
+fn legacy_swaps_move_to_counterpart_legs() {
+    let mut deps = setup("chain-b");
+    store_legacy(&mut deps, "legacy-1");
+
+    // The new code cannot read a legacy record, so none may be left after the upgrade
+    let msg = QueryMsg::Swap {
+        swap_id: "legacy-1".to_string(),
+    };
+    assert!(query(deps.as_ref(), mock_env(), msg).is_err());
+
+    let res = upgrade(&mut deps).unwrap();
+    assert_eq!(common::attr(&res, "migrated_swaps"), "1");
+    assert_eq!(common::attr(&res, "indexed_swaps"), "1");
+    let migrated = swap(&deps, "legacy-1");
+    assert_eq!(migrated.counterpart.chain_family, ChainFamily::Evm);
+    assert_eq!(migrated.counterpart.chain_id, "11155111");
+    assert_eq!(migrated.counterpart.role, SwapRole::InitiatorLeg);
+
+    // The Ethereum-only fields are still served from the counterpart leg
+    assert_eq!(migrated.ethereum_chain_id, "11155111");
+    assert_eq!(
+        migrated.ethereum_recipient,
+        "0x52908400098527886E0F7030069857D2E4169EE7"
+    );
+
+    // Nothing is left for a later upgrade
+    let res = upgrade(&mut deps).unwrap();
+    assert_eq!(common::attr(&res, "migrated_swaps"), "0");
+    assert_eq!(common::attr(&res, "indexed_swaps"), "0");
+}
+
+#[test]
+fn every_swap_is_usable_right_after_the_upgrade() {
+    let mut deps = setup("chain-b");
+    let swap_ids: Vec<String> = (0..150).map(|i| format!("legacy-{:03}", i)).collect();
+    for swap_id in &swap_ids {
+        store_legacy(&mut deps, swap_id);
+    }
+
+    let res = upgrade(&mut deps).unwrap();
+    assert_eq!(common::attr(&res, "migrated_swaps"), "150");
+    assert!(BY_INITIATOR.is_empty(&deps.storage));
+    assert!(BY_HASHLOCK.is_empty(&deps.storage));
+
+    // The last swap in key order settles like any other
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: swap_ids[149].clone(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr(2), &[]), msg).unwrap();
+    assert_eq!(swap(&deps, &swap_ids[149]).state, SwapState::Completed);
+
+    let mut listed = vec![];
+    loop {
+        let page = by_initiator(&deps, listed.last().cloned());
+        if page.is_empty() {
+            break;
+        }
+        listed.extend(page);
+    }
+    assert_eq!(listed, swap_ids);
+}
+
+#[test]
+fn swaps_already_in_the_current_layout_are_only_indexed() {
+    let mut deps = setup("chain-b");
+    store_legacy(&mut deps, "legacy-1");
+
+    // A current record written without the indexes, next to its hand-kept entries
+    let current = initiate(&mut deps, &addr(1), &addr(2));
+    let record = swaps().load(&deps.storage, &current).unwrap();
+    swaps().remove(deps.as_mut().storage, &current).unwrap();
+    Map::<&str, AtomicSwap>::new("swaps")
+        .save(deps.as_mut().storage, &current, &record)
+        .unwrap();
+    index_by_hand(&mut deps, &current);
+    assert!(by_initiator(&deps, None).is_empty());
+
+    let res = upgrade(&mut deps).unwrap();
+    assert_eq!(common::attr(&res, "migrated_swaps"), "1");
+    assert_eq!(common::attr(&res, "indexed_swaps"), "2");
+    assert_eq!(swap(&deps, &current).counterpart.chain_id, "chain-b");
+
+    let mut expected = vec![current, "legacy-1".to_string()];
+    expected.sort();
+    assert_eq!(by_initiator(&deps, None), expected);
+}
+
+#[test]
+fn unreadable_swaps_fail_the_upgrade() {
+    let mut deps = setup("chain-b");
+    store_legacy(&mut deps, "legacy-1");
+    Map::<&str, String>::new("swaps")
+        .save(deps.as_mut().storage, "corrupt", &"corrupt".to_string())
+        .unwrap();
+    assert!(upgrade(&mut deps).is_err());
+
+    // A broken record in the current layout is reported as one, not read as legacy
+    let mut deps = setup("chain-b");
+    store_legacy(&mut deps, "legacy-1");
+    let key = Map::<&str, AtomicSwap>::new("swaps").key("broken");
+    deps.storage.set(&key, br#"{"counterpart":{"chain_id":7}}"#);
+    match upgrade(&mut deps).unwrap_err() {
+        ContractError::Std(StdError::ParseErr { target_type, .. }) => {
+            assert!(target_type.ends_with("::AtomicSwap"), "{}", target_type)
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use atomic_swap::contract::{execute, reply};
+use atomic_swap::error::ContractError;
+use atomic_swap::ibc::ibc_packet_receive;
+use atomic_swap::ibc_msg::SwapPacket;
+use atomic_swap::msg::{ExecuteMsg, SwapState};
+use atomic_swap::state::{CompletionAction, IbcDelivery};
+use common::{addr, hashlock, initiate, setup, swap, Deps, ADMIN, CHANNEL, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_ibc_packet_recv, mock_info};
+use cosmwasm_std::{
+    coin, to_json_binary, BankMsg, CosmosMsg, Reply, Response, SubMsgResult, WasmMsg,
+};
+
+fn delivery() -> IbcDelivery {
+    IbcDelivery {
+        channel_id: "channel-7".to_string(),
+        receiver: "osmo1receiver".to_string(),
+        timeout_seconds: 600,
+        memo: None,
+    }
+}
+
+fn set_route(
+    deps: &mut Deps,
+    sender: &str,
+    swap_id: &str,
+    delivery: Option<IbcDelivery>,
+    action: Option<CompletionAction>,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::SetPayoutRoute {
+        swap_id: swap_id.to_string(),
+        delivery,
+        action,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+}
+
+// Completes the swap the way a counterpart deployment does, without the participant
+fn relay_secret(deps: &mut Deps) -> Vec<CosmosMsg> {
+    let packet = SwapPacket::SecretRevealed {
+        swap_id: "remote-swap".to_string(),
+        hashlock: hashlock(),
+        secret: SECRET.to_string(),
+    };
+    let msg = mock_ibc_packet_recv(CHANNEL, &packet).unwrap();
+    let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    res.messages.into_iter().map(|msg| msg.msg).collect()
+}
+
+#[test]
+fn only_the_participant_sets_the_route() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+
+    let err = set_route(&mut deps, &alice, &swap_id, Some(delivery()), None).unwrap_err();
+    assert!(matches!(err, ContractError::OnlyParticipant {}));
+
+    set_route(&mut deps, &bob, &swap_id, Some(delivery()), None).unwrap();
+    assert_eq!(swap(&deps, &swap_id).delivery, Some(delivery()));
+
+    set_route(&mut deps, &bob, &swap_id, None, None).unwrap();
+    assert_eq!(swap(&deps, &swap_id).delivery, None);
+}
+
+#[test]
+fn relayed_completion_pays_the_participant_by_default() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+
+    let messages = relay_secret(&mut deps);
+    assert_eq!(swap(&deps, &swap_id).state, SwapState::Completed);
+    assert!(messages.iter().any(|msg| matches!(
+        msg,
+        CosmosMsg::Bank(BankMsg::Send { to_address, .. }) if *to_address == bob
+    )));
+}
+
+#[test]
+fn relayed_completion_follows_the_participants_route() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+    set_route(&mut deps, &bob, &swap_id, Some(delivery()), None).unwrap();
+
+    let messages = relay_secret(&mut deps);
+    assert!(messages
+        .iter()
+        .any(|msg| matches!(msg, CosmosMsg::Stargate { .. })));
+}
+
+#[test]
+fn participant_routes_through_allowed_actions_only() {
+    let mut deps = setup("chain-b");
+    let (alice, bob, vault) = (addr(1), addr(2), addr(9));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+    let action = CompletionAction {
+        contract: vault.clone(),
+        msg: to_json_binary(&"deposit").unwrap(),
+    };
+
+    let err = set_route(&mut deps, &bob, &swap_id, None, Some(action.clone())).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidCompletionAction { .. }));
+
+    let msg = ExecuteMsg::UpdateActionContracts {
+        add: vec![vault.clone()],
+        remove: vec![],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    set_route(&mut deps, &bob, &swap_id, None, Some(action)).unwrap();
+
+    let messages = relay_secret(&mut deps);
+    assert!(messages.iter().any(|msg| matches!(
+        msg,
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) if *contract_addr == vault
+    )));
+}
+
+#[test]
+fn failed_actions_pay_the_participant_through_the_bank() {
+    let mut deps = setup("chain-b");
+    let (alice, bob, vault) = (addr(1), addr(2), addr(9));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+    let msg = ExecuteMsg::UpdateActionContracts {
+        add: vec![vault.clone()],
+        remove: vec![],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    let action = CompletionAction {
+        contract: vault.clone(),
+        msg: to_json_binary(&"deposit").unwrap(),
+    };
+
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: swap_id.clone(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: Some(action),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), msg).unwrap();
+    let call = res
+        .messages
+        .iter()
+        .find(|msg| matches!(&msg.msg, CosmosMsg::Wasm(WasmMsg::Execute { .. })))
+        .unwrap();
+
+    let failed = Reply {
+        id: call.id,
+        result: SubMsgResult::Err("out of gas".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), failed).unwrap();
+    assert_eq!(common::attr(&res, "method"), "completion_action_failed");
+    assert_eq!(common::attr(&res, "recipient"), bob);
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: bob.clone(),
+            amount: vec![coin(100, "uatom")],
+        })
+    );
+
+    // The completion stands, only the route of the payout changed
+    let settled = swap(&deps, &swap_id);
+    assert_eq!(settled.state, SwapState::Completed);
+    assert_eq!(settled.secret, Some(SECRET.to_string()));
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use atomic_swap::contract::{execute, query, reply};
+use atomic_swap::msg::{ClaimableResponse, ExecuteMsg, QueryMsg};
+use atomic_swap::ContractError;
+use common::{addr, initiate, setup, Deps, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{
+    coin, from_json, BankMsg, Coin, CosmosMsg, Reply, ReplyOn, Response, SubMsgResponse,
+    SubMsgResult,
+};
+
+fn complete(deps: &mut Deps, swap_id: &str, participant: &str) -> Response {
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: swap_id.to_string(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(participant, &[]), msg).unwrap()
+}
+
+// Reply ids of the bank payouts in the response, by recipient
+fn payouts(res: &Response) -> Vec<(u64, String)> {
+    res.messages
+        .iter()
+        .filter_map(|msg| match &msg.msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, .. }) => {
+                assert_eq!(msg.reply_on, ReplyOn::Always);
+                Some((msg.id, to_address.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn bounce(deps: &mut Deps, reply_id: u64) -> Result<Response, ContractError> {
+    let result = SubMsgResult::Err("account is blocked".to_string());
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: reply_id,
+            result,
+        },
+    )
+}
+
+fn deliver(deps: &mut Deps, reply_id: u64) -> Result<Response, ContractError> {
+    let result = SubMsgResult::Ok(SubMsgResponse {
+        events: vec![],
+        data: None,
+    });
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: reply_id,
+            result,
+        },
+    )
+}
+
+fn claimable(deps: &Deps, address: &str) -> Vec<Coin> {
+    let msg = QueryMsg::Claimable {
+        address: address.to_string(),
+    };
+    let res: ClaimableResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    res.balances
+}
+
+#[test]
+fn failed_payouts_become_claimable() {
+    let mut deps = setup("chain-b");
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(&mut deps, &alice, &bob);
+
+    let res = complete(&mut deps, &swap_id, &bob);
+    let sent = payouts(&res);
+    assert_eq!(sent, vec![(1_000, bob.clone()), (1_001, alice.clone())]);
+
+    // Both sends of one completion fail, each is held for its own recipient
+    let res = bounce(&mut deps, 1_001).unwrap();
+    assert_eq!(common::attr(&res, "method"), "payout_failed");
+    assert_eq!(common::attr(&res, "swap_id"), swap_id);
+    assert_eq!(common::attr(&res, "recipient"), alice);
+    bounce(&mut deps, 1_000).unwrap();
+    assert_eq!(claimable(&deps, &alice), vec![coin(1_000_000, "uatom")]);
+    assert_eq!(claimable(&deps, &bob), vec![coin(100, "uatom")]);
+
+    // A reply is only accepted once
+    assert!(bounce(&mut deps, 1_000).is_err());
+    assert_eq!(claimable(&deps, &bob), vec![coin(100, "uatom")]);
+}
+
+#[test]
+fn payouts_in_the_same_block_fail_independently() {
+    let mut deps = setup("chain-b");
+    let first = initiate(&mut deps, &addr(1), &addr(2));
+    let second = initiate(&mut deps, &addr(3), &addr(4));
+
+    let res = complete(&mut deps, &first, &addr(2));
+    let sent = payouts(&res);
+    deliver(&mut deps, sent[0].0).unwrap();
+    bounce(&mut deps, sent[1].0).unwrap();
+
+    // The next transaction of the block reuses the ids its predecessor released
+    let res = complete(&mut deps, &second, &addr(4));
+    assert_eq!(payouts(&res), vec![(1_000, addr(4)), (1_001, addr(3))]);
+    let res = bounce(&mut deps, 1_000).unwrap();
+    assert_eq!(common::attr(&res, "swap_id"), second);
+    deliver(&mut deps, 1_001).unwrap();
+
+    assert!(claimable(&deps, &addr(2)).is_empty());
+    assert_eq!(claimable(&deps, &addr(1)), vec![coin(1_000_000, "uatom")]);
+    assert_eq!(claimable(&deps, &addr(4)), vec![coin(100, "uatom")]);
+    assert!(claimable(&deps, &addr(3)).is_empty());
+}
+
+#[test]
+fn payouts_still_in_flight_keep_their_ids() {
+    let mut deps = setup("chain-b");
+    let first = initiate(&mut deps, &addr(1), &addr(2));
+    let second = initiate(&mut deps, &addr(3), &addr(4));
+
+    // Both completions are staged before either hears back
+    let early = payouts(&complete(&mut deps, &first, &addr(2)));
+    let late = payouts(&complete(&mut deps, &second, &addr(4)));
+    assert_eq!(late, vec![(1_002, addr(4)), (1_003, addr(3))]);
+
+    bounce(&mut deps, early[0].0).unwrap();
+    bounce(&mut deps, late[0].0).unwrap();
+    assert_eq!(claimable(&deps, &addr(2)), vec![coin(100, "uatom")]);
+    assert_eq!(claimable(&deps, &addr(4)), vec![coin(100, "uatom")]);
+}
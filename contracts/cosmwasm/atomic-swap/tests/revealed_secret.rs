@@ -0,0 +1,48 @@
+mod common;
+
+use atomic_swap::contract::{execute, query};
+use atomic_swap::msg::{ExecuteMsg, QueryMsg};
+use atomic_swap::state::RevealedSecret;
+use common::{addr, hashlock, initiate, initiate_at, next_block, now, setup, Deps, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{from_json, Env, StdResult};
+
+fn revealed(deps: &Deps) -> StdResult<RevealedSecret> {
+    let msg = QueryMsg::RevealedSecret {
+        hashlock: hashlock(),
+    };
+    query(deps.as_ref(), mock_env(), msg).map(|res| from_json(res).unwrap())
+}
+
+fn complete(deps: &mut Deps, env: Env, swap_id: &str) {
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: swap_id.to_string(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), env, mock_info(&addr(2), &[]), msg).unwrap();
+}
+
+#[test]
+fn the_first_completion_reveals_the_secret() {
+    let mut deps = setup("chain-b");
+    let first = initiate(&mut deps, &addr(1), &addr(2));
+    let env = next_block(&mock_env(), 0);
+    let second = initiate_at(&mut deps, env.clone(), &addr(1), &addr(2));
+    assert!(revealed(&deps).is_err());
+
+    let env = next_block(&env, 60);
+    complete(&mut deps, env.clone(), &first);
+    let expected = RevealedSecret {
+        secret: SECRET.to_string(),
+        swap_id: first,
+        block_height: env.block.height,
+        revealed_at: now() + 60,
+    };
+    assert_eq!(revealed(&deps).unwrap(), expected);
+
+    // Settling another swap on the same hashlock keeps the original reveal
+    complete(&mut deps, next_block(&env, 60), &second);
+    assert_eq!(revealed(&deps).unwrap(), expected);
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use atomic_swap::contract::{execute, query};
+use atomic_swap::msg::{ExecuteMsg, QueryMsg, SortOrder, SwapFilter, SwapState, SwapsResponse};
+use common::{addr, counterpart, initiate_msg, next_block, setup, Deps, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{coin, from_json, Env, Uint128};
+
+// Initiates a swap of `amount` uatom in the block after `env`
+fn initiate_amount(deps: &mut Deps, env: &mut Env, amount: u128) -> String {
+    *env = next_block(env, 0);
+    let msg = initiate_msg(
+        &addr(2),
+        coin(amount, "uatom"),
+        counterpart("chain-b", addr(200)),
+    );
+    let info = mock_info(&addr(1), &[coin(amount + 1_000_000, "uatom")]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    common::attr(&res, "swap_id")
+}
+
+fn page(
+    deps: &Deps,
+    filter: &SwapFilter,
+    start_after: Option<String>,
+    limit: u32,
+    order: SortOrder,
+) -> (Vec<String>, Option<String>) {
+    let msg = QueryMsg::Swaps {
+        filter: Some(filter.clone()),
+        start_after,
+        limit: Some(limit),
+        order: Some(order),
+    };
+    let res: SwapsResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    let swap_ids = res.swaps.into_iter().map(|swap| swap.swap_id).collect();
+    (swap_ids, res.next_start_after)
+}
+
+// Follows the cursor until the listing ends
+fn all(deps: &Deps, filter: &SwapFilter, limit: u32, order: SortOrder) -> Vec<String> {
+    let mut listed = vec![];
+    let mut start_after = None;
+    loop {
+        let (swap_ids, next) = page(deps, filter, start_after, limit, order.clone());
+        listed.extend(swap_ids);
+        match next {
+            Some(next) => start_after = Some(next),
+            None => return listed,
+        }
+    }
+}
+
+#[test]
+fn filtered_swaps_page_in_either_order() {
+    let mut deps = setup("chain-b");
+    let mut env = mock_env();
+    let ids: Vec<String> = [100, 200, 300, 400, 500]
+        .into_iter()
+        .map(|amount| initiate_amount(&mut deps, &mut env, amount))
+        .collect();
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id: ids[2].clone(),
+        secret: SECRET.to_string(),
+        delivery: None,
+        action: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info(&addr(2), &[]), msg).unwrap();
+
+    let filter = SwapFilter {
+        state: Some(SwapState::Initiated),
+        min_amount: Some(Uint128::new(200)),
+        ..SwapFilter::default()
+    };
+    let mut expected = vec![ids[1].clone(), ids[3].clone(), ids[4].clone()];
+    expected.sort();
+
+    let (first, next) = page(&deps, &filter, None, 2, SortOrder::Ascending);
+    assert_eq!(first, expected[..2]);
+    assert_eq!(next, Some(expected[1].clone()));
+    assert_eq!(all(&deps, &filter, 2, SortOrder::Ascending), expected);
+
+    expected.reverse();
+    assert_eq!(all(&deps, &filter, 2, SortOrder::Descending), expected);
+
+    // Without the state filter the completed swap is listed too
+    let filter = SwapFilter {
+        min_amount: Some(Uint128::new(200)),
+        ..SwapFilter::default()
+    };
+    assert_eq!(all(&deps, &filter, 1, SortOrder::Descending).len(), 4);
+}
+
+#[test]
+fn pages_stop_at_the_scan_budget() {
+    let mut deps = setup("chain-b");
+    let mut env = mock_env();
+    for _ in 0..305 {
+        initiate_amount(&mut deps, &mut env, 100);
+    }
+    let ids = all(&deps, &SwapFilter::default(), 30, SortOrder::Ascending);
+    assert_eq!(ids.len(), 305);
+
+    // Nothing matches, yet each page hands back where its scan stopped
+    let filter = SwapFilter {
+        min_amount: Some(Uint128::new(1_000)),
+        ..SwapFilter::default()
+    };
+    let (swap_ids, next) = page(&deps, &filter, None, 30, SortOrder::Ascending);
+    assert!(swap_ids.is_empty());
+    assert_eq!(next, Some(ids[299].clone()));
+    let (swap_ids, next) = page(&deps, &filter, next, 30, SortOrder::Ascending);
+    assert!(swap_ids.is_empty());
+    assert_eq!(next, None);
+}
@@ -0,0 +1,187 @@
+mod common;
+
+use atomic_swap::contract::{execute, query, reply, sudo};
+use atomic_swap::msg::{
+    ClaimableResponse, ExecuteMsg, IbcLifecycleComplete, QueryMsg, RequestPacket, SudoMsg,
+};
+use atomic_swap::state::IbcDelivery;
+use common::{addr, initiate, setup, Deps, SECRET};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{
+    coin, from_json, Binary, Coin, CosmosMsg, Reply, ReplyOn, SubMsgResponse, SubMsgResult,
+};
+
+const DELIVERY_CHANNEL: &str = "channel-7";
+
+fn delivery() -> IbcDelivery {
+    IbcDelivery {
+        channel_id: DELIVERY_CHANNEL.to_string(),
+        receiver: "osmo1receiver".to_string(),
+        timeout_seconds: 600,
+        memo: None,
+    }
+}
+
+// Completes a swap with an IBC delivery and returns the transfer's reply id
+fn complete_with_delivery(deps: &mut Deps) -> u64 {
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = initiate(deps, &alice, &bob);
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id,
+        secret: SECRET.to_string(),
+        delivery: Some(delivery()),
+        action: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), msg).unwrap();
+
+    let transfer = res
+        .messages
+        .iter()
+        .find(|msg| matches!(msg.msg, CosmosMsg::Stargate { .. }))
+        .unwrap();
+    assert_eq!(transfer.reply_on, ReplyOn::Always);
+    transfer.id
+}
+
+// MsgTransferResponse { sequence }
+fn sent(deps: &mut Deps, reply_id: u64, sequence: u8) {
+    let result = SubMsgResult::Ok(SubMsgResponse {
+        events: vec![],
+        data: Some(Binary::from(vec![8, sequence])),
+    });
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: reply_id,
+            result,
+        },
+    )
+    .unwrap();
+}
+
+fn request(port: &str, sequence: u64) -> RequestPacket {
+    RequestPacket {
+        sequence: Some(sequence),
+        source_port: Some(port.to_string()),
+        source_channel: Some(DELIVERY_CHANNEL.to_string()),
+        destination_port: Some("transfer".to_string()),
+        destination_channel: Some("channel-1".to_string()),
+        data: None,
+        timeout_height: None,
+        timeout_timestamp: None,
+    }
+}
+
+fn claimable(deps: &Deps) -> Vec<Coin> {
+    let msg = QueryMsg::Claimable { address: addr(2) };
+    let res: ClaimableResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    res.balances
+}
+
+fn pending(deps: &Deps, sequence: u64) -> bool {
+    let msg = QueryMsg::IbcTransfer {
+        channel_id: DELIVERY_CHANNEL.to_string(),
+        sequence,
+    };
+    query(deps.as_ref(), mock_env(), msg).is_ok()
+}
+
+#[test]
+fn rejected_transfer_becomes_claimable() {
+    let mut deps = setup("chain-b");
+    let reply_id = complete_with_delivery(&mut deps);
+
+    let result = SubMsgResult::Err("channel not found".to_string());
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: reply_id,
+            result,
+        },
+    )
+    .unwrap();
+    assert_eq!(common::attr(&res, "method"), "ibc_transfer_failed");
+    assert_eq!(claimable(&deps), vec![coin(100, "uatom")]);
+}
+
+#[test]
+fn neutron_acknowledgement_settles_transfer() {
+    let mut deps = setup("chain-b");
+    let reply_id = complete_with_delivery(&mut deps);
+    sent(&mut deps, reply_id, 5);
+    assert!(pending(&deps, 5));
+
+    let msg = SudoMsg::Response {
+        request: request("transfer", 5),
+        data: Binary::from(b"{\"result\":\"AQ==\"}".to_vec()),
+    };
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(!pending(&deps, 5));
+    assert!(claimable(&deps).is_empty());
+}
+
+#[test]
+fn neutron_error_and_timeout_credit_claimable() {
+    let mut deps = setup("chain-b");
+    let first = complete_with_delivery(&mut deps);
+    sent(&mut deps, first, 5);
+
+    let msg = SudoMsg::Error {
+        request: request("transfer", 5),
+        details: "ABCI code: 1".to_string(),
+    };
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(!pending(&deps, 5));
+    assert_eq!(claimable(&deps), vec![coin(100, "uatom")]);
+
+    let mut env = mock_env();
+    env.block.height += 1;
+    let (alice, bob) = (addr(1), addr(2));
+    let swap_id = common::initiate_at(&mut deps, env, &alice, &bob);
+    let msg = ExecuteMsg::CompleteSwap {
+        swap_id,
+        secret: SECRET.to_string(),
+        delivery: Some(delivery()),
+        action: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), msg).unwrap();
+    sent(&mut deps, first, 6);
+
+    let msg = SudoMsg::Timeout {
+        request: request("transfer", 6),
+    };
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(!pending(&deps, 6));
+    assert_eq!(claimable(&deps), vec![coin(200, "uatom")]);
+}
+
+#[test]
+fn ibc_hooks_callbacks_still_settle_transfers() {
+    let mut deps = setup("chain-b");
+    let reply_id = complete_with_delivery(&mut deps);
+    sent(&mut deps, reply_id, 9);
+
+    let msg = SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout {
+        channel: DELIVERY_CHANNEL.to_string(),
+        sequence: 9,
+    });
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(!pending(&deps, 9));
+    assert_eq!(claimable(&deps), vec![coin(100, "uatom")]);
+}
+
+#[test]
+fn interchain_account_packets_are_not_taken_for_transfers() {
+    let mut deps = setup("chain-b");
+    let reply_id = complete_with_delivery(&mut deps);
+    sent(&mut deps, reply_id, 5);
+
+    let msg = SudoMsg::Timeout {
+        request: request("icacontroller-contract.swap", 5),
+    };
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(pending(&deps, 5));
+    assert!(claimable(&deps).is_empty());
+}